@@ -0,0 +1,104 @@
+//! Times building a frame of a level with 100k entities, with and without
+//! the spatial grid. Run with `cargo bench --bench render`.
+//!
+//! Only the draw list is built, so it runs without a window. Drawing it is
+//! one fill_rects call per colour on top of this.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tiny_rpg::data::Data;
+use tiny_rpg::entity::Entity;
+use tiny_rpg::helper::Builder;
+use tiny_rpg::maps::{Level, LevelId, Map};
+use tiny_rpg::math::{Dimension, Point};
+use tiny_rpg::render;
+
+const WIDTH: i32 = 500;
+const HEIGHT: i32 = 200;
+const TILE_SIZE: u32 = 32;
+const FRAMES: u32 = 100;
+
+/// A level filled with entities, a wall every few tiles and items in
+/// between.
+fn crowded_map() -> Map {
+    let mut entities = Vec::with_capacity((WIDTH * HEIGHT) as usize);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if (x * 7 + y * 3) % 11 == 0 {
+                entities.push(Entity::new_wall(x, y, 1, 1));
+            } else {
+                entities.push(Entity::new_item(x, y));
+            }
+        }
+    }
+    let level = Level::new()
+        .id(LevelId::new(1))
+        .number(1)
+        .entities(entities)
+        .build()
+        .unwrap();
+    Map::new()
+        .level(level)
+        .start(LevelId::new(1), Point::new(WIDTH / 2, HEIGHT / 2))
+        .build()
+        .unwrap()
+}
+
+/// Builds FRAMES draw lists and returns the average time for one.
+fn time_frames<F: FnMut() -> render::DrawList>(mut frame: F) -> (Duration, usize) {
+    let mut rect_count = 0;
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        rect_count = black_box(frame()).get_rect_count();
+    }
+    (start.elapsed() / FRAMES, rect_count)
+}
+
+fn main() {
+    let screen_size = Dimension::new(TILE_SIZE * 30, TILE_SIZE * 20);
+    let mut data = Data::from_map(crowded_map());
+    data.update_spatial_grid();
+    data.update_cameras(TILE_SIZE, screen_size, Duration::from_millis(16));
+    let camera = *data.get_camera();
+    let visible_area = camera.get_visible_rectangle(TILE_SIZE, screen_size);
+    println!(
+        "{} entities, {} frames each",
+        data.get_entities().len(),
+        FRAMES
+    );
+
+    let (every_entity, rect_count) = time_frames(|| {
+        render::get_objects_draw_list(
+            TILE_SIZE,
+            screen_size,
+            &camera,
+            data.get_player(),
+            data.get_entities(),
+            data.get_field_of_view(),
+            data.get_level(),
+            data.get_clock(),
+        )
+    });
+    println!(
+        "every entity:  {:?} a frame, {} rects",
+        every_entity, rect_count
+    );
+
+    let (spatial_grid, rect_count) = time_frames(|| {
+        render::get_objects_draw_list(
+            TILE_SIZE,
+            screen_size,
+            &camera,
+            data.get_player(),
+            data.get_entities_in(visible_area),
+            data.get_field_of_view(),
+            data.get_level(),
+            data.get_clock(),
+        )
+    });
+    println!(
+        "spatial grid:  {:?} a frame, {} rects",
+        spatial_grid, rect_count
+    );
+}
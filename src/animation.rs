@@ -0,0 +1,232 @@
+//! Animated skins.
+//!
+//! An animation is a set of clips for one atlas, like idle or walk_left.
+//! Each clip is a list of frames shown for the same time each. Entities
+//! say which clip they are playing and when it started, and the frame is
+//! picked from the render clock when drawing. The render clock keeps
+//! running between turns, so nothing about turns changes.
+//!
+//! Clips are listed after their atlas in atlases.txt, with the frames and
+//! how long each is shown in milliseconds:
+//!
+//! ```text
+//! atlas slime slime.bmp 16x16
+//! clip slime idle 0,1 400
+//! clip slime walk_left 2,3 80
+//! ```
+//!
+//! Idle loops, death stays on its last frame and every other clip plays
+//! once then goes back to idle.
+//!
+//! # Planned changes:
+//!
+//! - Events on frames, like a sound on a footstep.
+
+use std::time::Duration;
+
+/// The clips an entity can play.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Clip {
+    /// Standing still. Loops.
+    Idle,
+    /// Stepping left.
+    WalkLeft,
+    /// Stepping right.
+    WalkRight,
+    /// Stepping up.
+    WalkUp,
+    /// Stepping down.
+    WalkDown,
+    /// Attacking whatever is in front of it.
+    Attack,
+    /// Dying. Stays on the last frame.
+    Death,
+}
+
+impl Clip {
+    /// Every clip.
+    pub const ALL: [Clip; 7] = [
+        Clip::Idle,
+        Clip::WalkLeft,
+        Clip::WalkRight,
+        Clip::WalkUp,
+        Clip::WalkDown,
+        Clip::Attack,
+        Clip::Death,
+    ];
+
+    /// Returns the name of the clip, used in atlases.txt.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Clip::Idle => "idle",
+            Clip::WalkLeft => "walk_left",
+            Clip::WalkRight => "walk_right",
+            Clip::WalkUp => "walk_up",
+            Clip::WalkDown => "walk_down",
+            Clip::Attack => "attack",
+            Clip::Death => "death",
+        }
+    }
+    /// Returns the clip with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Clip> {
+        Clip::ALL
+            .iter()
+            .find(|clip| clip.get_name() == name)
+            .copied()
+    }
+    /// Returns the walk clip for a step by x and y. Anything that isn't a
+    /// single step is idle.
+    pub fn walk(x: i32, y: i32) -> Clip {
+        match (x.signum(), y.signum()) {
+            (-1, 0) => Clip::WalkLeft,
+            (1, 0) => Clip::WalkRight,
+            (0, -1) => Clip::WalkUp,
+            (0, 1) => Clip::WalkDown,
+            _ => Clip::Idle,
+        }
+    }
+}
+
+/// The frames of one clip.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipFrames {
+    frames: Vec<u32>,
+    frame_duration: Duration,
+}
+
+impl ClipFrames {
+    /// Creates a new instance of ClipFrames. Each frame is shown for
+    /// frame_duration.
+    pub fn new(frames: Vec<u32>, frame_duration: Duration) -> Result<ClipFrames, &'static str> {
+        if frames.is_empty() {
+            return Err("A clip needs at least one frame.");
+        }
+        if frame_duration == Duration::from_millis(0) {
+            return Err("Clip frames need to last longer than 0ms.");
+        }
+        Ok(ClipFrames {
+            frames,
+            frame_duration,
+        })
+    }
+    /// Returns the atlas frames, in order.
+    pub fn get_frames(&self) -> &Vec<u32> {
+        &self.frames
+    }
+    /// Returns how long each frame is shown.
+    pub fn get_frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+    /// Returns how long the clip takes to play once.
+    pub fn get_length(&self) -> Duration {
+        self.frame_duration * self.frames.len() as u32
+    }
+    /// Returns the frame shown after playing for elapsed, looping.
+    fn get_looped_frame(&self, elapsed: Duration) -> u32 {
+        let index = (elapsed.as_millis() / self.frame_duration.as_millis()) as usize;
+        self.frames[index % self.frames.len()]
+    }
+    /// Returns the frame shown after playing for elapsed, staying on the
+    /// last frame once done.
+    fn get_held_frame(&self, elapsed: Duration) -> u32 {
+        let index = (elapsed.as_millis() / self.frame_duration.as_millis()) as usize;
+        self.frames[index.min(self.frames.len() - 1)]
+    }
+}
+
+/// The clips of one atlas.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+    clips: Vec<(Clip, ClipFrames)>,
+}
+
+impl Animation {
+    /// Creates a new instance of Animation without any clips.
+    pub fn new() -> Animation {
+        Animation::default()
+    }
+    /// Adds a clip, replacing the clip it had with the same name.
+    pub fn add_clip(&mut self, clip: Clip, frames: ClipFrames) {
+        self.clips.retain(|other| other.0 != clip);
+        self.clips.push((clip, frames));
+    }
+    /// Returns the frames of a clip, if the animation has it.
+    pub fn get_clip(&self, clip: Clip) -> Option<&ClipFrames> {
+        self.clips
+            .iter()
+            .find(|other| other.0 == clip)
+            .map(|other| &other.1)
+    }
+    /// Returns the atlas frame to show for clip after it has played for
+    /// elapsed. A clip the animation doesn't have plays idle instead, and
+    /// without idle it is frame 0.
+    pub fn get_frame(&self, clip: Clip, elapsed: Duration) -> u32 {
+        let idle = self.get_clip(Clip::Idle);
+        match (clip, self.get_clip(clip)) {
+            (Clip::Idle, Some(frames)) => frames.get_looped_frame(elapsed),
+            (Clip::Death, Some(frames)) => frames.get_held_frame(elapsed),
+            (_, Some(frames)) if elapsed < frames.get_length() => frames.get_held_frame(elapsed),
+            // done, so back to idle from when the clip ended
+            (_, Some(frames)) => idle.map_or(0, |idle| {
+                idle.get_looped_frame(elapsed - frames.get_length())
+            }),
+            (_, None) => idle.map_or(0, |idle| idle.get_looped_frame(elapsed)),
+        }
+    }
+}
+
+/// Which clip something is playing and when it started on the render clock.
+///
+/// Clips are started from game logic, which doesn't know the render clock,
+/// so a new clip has no start until Data stamps it at the end of the turn.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AnimationState {
+    clip: Clip,
+    started: Option<Duration>,
+}
+
+impl AnimationState {
+    /// Creates a new instance of AnimationState playing idle.
+    pub fn new() -> AnimationState {
+        AnimationState {
+            clip: Clip::Idle,
+            started: None,
+        }
+    }
+    /// Returns the clip being played.
+    pub fn get_clip(&self) -> Clip {
+        self.clip
+    }
+    /// Plays clip from the start, even if it was already playing.
+    pub fn play(&mut self, clip: Clip) {
+        self.clip = clip;
+        self.started = None;
+    }
+    /// Returns true if the clip was played but hasn't been stamped yet.
+    /// Idle never needs stamping.
+    pub fn is_pending(&self) -> bool {
+        self.started.is_none() && self.clip != Clip::Idle
+    }
+    /// Sets when the clip started, if it is waiting for it.
+    pub fn start(&mut self, clock: Duration) {
+        if self.is_pending() {
+            self.started = Some(clock);
+        }
+    }
+    /// Returns how long the clip has played for at clock. A clip that
+    /// hasn't been stamped yet has only just started, apart from idle,
+    /// which loops anyway so it acts like it has always been playing.
+    pub fn get_elapsed(&self, clock: Duration) -> Duration {
+        match (self.started, self.clip) {
+            (Some(started), _) => clock.saturating_sub(started),
+            (None, Clip::Idle) => clock,
+            (None, _) => Duration::from_millis(0),
+        }
+    }
+}
+
+impl Default for AnimationState {
+    fn default() -> Self {
+        AnimationState::new()
+    }
+}
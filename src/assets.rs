@@ -0,0 +1,280 @@
+//! Loads the images used by sprite skins.
+//!
+//! An atlas is one image cut into frames of the same size, counted left to
+//! right then top to bottom starting at 0. Atlases are listed in an
+//! atlases.txt file in an assets directory, with the image path relative
+//! to that directory and the size of a frame:
+//!
+//! ```text
+//! atlas walls walls.bmp 16x16
+//! atlas slime slime.png 16x16
+//! ```
+//!
+//! Animated skins also need clips, listed after their atlas, see the
+//! animation module. Fonts are atlases too, see the text module.
+//!
+//! BMP images always work. PNG images need the png feature, which needs
+//! SDL2_image installed.
+//!
+//! Every atlas is loaded once, when its directory is loaded, and kept until
+//! the game closes.
+//!
+//! # Planned changes:
+//!
+//! - Load atlases when a level first needs them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::video::WindowContext;
+
+use crate::animation::{Animation, Clip, ClipFrames};
+use crate::helper::FileError;
+use crate::math::Dimension;
+use crate::save;
+use crate::text::Font;
+
+/// The name of the file listing the atlases in an assets directory.
+pub const ATLASES_FILE: &str = "atlases.txt";
+
+/// An image cut into frames.
+pub struct Atlas<'a> {
+    texture: Texture<'a>,
+    frame_size: Dimension,
+    columns: u32,
+    frame_count: u32,
+    animation: Animation,
+}
+
+impl<'a> Atlas<'a> {
+    /// Creates a new instance of Atlas. Any part of the texture too small
+    /// for a whole frame is left out.
+    pub fn new(texture: Texture<'a>, frame_size: Dimension) -> Result<Atlas<'a>, &'static str> {
+        let query = texture.query();
+        if frame_size.get_width() == 0 || frame_size.get_height() == 0 {
+            return Err("Frames need a width and height.");
+        }
+        let columns = query.width / frame_size.get_width();
+        let rows = query.height / frame_size.get_height();
+        if columns == 0 || rows == 0 {
+            return Err("The image is smaller than a frame.");
+        }
+        Ok(Atlas {
+            texture,
+            frame_size,
+            columns,
+            frame_count: columns * rows,
+            animation: Animation::new(),
+        })
+    }
+    /// Returns a reference to the texture holding every frame.
+    pub fn get_texture(&self) -> &Texture<'a> {
+        &self.texture
+    }
+    /// Returns a mutable reference to the texture, for tinting it.
+    pub fn get_mut_texture(&mut self) -> &mut Texture<'a> {
+        &mut self.texture
+    }
+    /// Returns the size of a frame in pixels.
+    pub fn get_frame_size(&self) -> Dimension {
+        self.frame_size
+    }
+    /// Returns how many frames the atlas has.
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count
+    }
+    /// Returns the clips of the atlas.
+    pub fn get_animation(&self) -> &Animation {
+        &self.animation
+    }
+    /// Adds a clip to the atlas. Every frame of the clip has to be in the
+    /// atlas.
+    pub fn add_clip(&mut self, clip: Clip, frames: ClipFrames) -> Result<(), &'static str> {
+        if frames
+            .get_frames()
+            .iter()
+            .any(|frame| *frame >= self.frame_count)
+        {
+            return Err("The clip has a frame the atlas doesn't.");
+        }
+        self.animation.add_clip(clip, frames);
+        Ok(())
+    }
+    /// Returns where the frame is in the texture, if the atlas has it.
+    pub fn get_frame(&self, frame: u32) -> Option<Rect> {
+        if frame >= self.frame_count {
+            return None;
+        }
+        let (width, height) = (self.frame_size.get_width(), self.frame_size.get_height());
+        Some(Rect::new(
+            ((frame % self.columns) * width) as i32,
+            ((frame / self.columns) * height) as i32,
+            width,
+            height,
+        ))
+    }
+}
+
+/// Holds every loaded atlas by name.
+pub struct Assets<'a> {
+    texture_creator: &'a TextureCreator<WindowContext>,
+    atlases: HashMap<String, Atlas<'a>>,
+    fonts: HashMap<String, Font>,
+}
+
+impl<'a> Assets<'a> {
+    /// Creates a new instance of Assets with no atlases. Textures are made
+    /// with texture_creator, so they can only be drawn on its canvas.
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>) -> Assets<'a> {
+        Assets {
+            texture_creator,
+            atlases: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+    /// Loads the image at path as an atlas called name. An atlas with the
+    /// same name is replaced.
+    pub fn load_atlas(
+        &mut self,
+        name: &str,
+        path: &Path,
+        frame_size: Dimension,
+    ) -> Result<(), FileError> {
+        self.insert_atlas(name, load_surface(path)?, frame_size)
+    }
+    /// Loads the image at path as a font called name, with first as the
+    /// character code of its first glyph. Black in the image is see
+    /// through. A font or atlas with the same name is replaced.
+    pub fn load_font(
+        &mut self,
+        name: &str,
+        path: &Path,
+        glyph_size: Dimension,
+        first: u32,
+    ) -> Result<(), FileError> {
+        let mut surface = load_surface(path)?;
+        surface
+            .set_color_key(true, Color::RGB(0, 0, 0))
+            .map_err(|error| FileError::new(&error))?;
+        self.insert_atlas(name, surface, glyph_size)?;
+        let glyph_count = self.atlases[name].get_frame_count();
+        self.fonts
+            .insert(name.to_string(), Font::new(glyph_size, first, glyph_count));
+        Ok(())
+    }
+    /// Turns a loaded image into an atlas called name.
+    fn insert_atlas(
+        &mut self,
+        name: &str,
+        surface: Surface,
+        frame_size: Dimension,
+    ) -> Result<(), FileError> {
+        let texture = self
+            .texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|error| FileError::new(&error.to_string()))?;
+        let atlas = Atlas::new(texture, frame_size).map_err(FileError::new)?;
+        self.atlases.insert(name.to_string(), atlas);
+        Ok(())
+    }
+    /// Loads every atlas listed in the atlases.txt file in directory.
+    pub fn load_directory(&mut self, directory: &Path) -> Result<(), FileError> {
+        let contents = fs::read_to_string(directory.join(ATLASES_FILE))?;
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = save::strip_comment(line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => {}
+                ["atlas", name, file, frame_size] => {
+                    let frame_size = parse_frame_size(line_number, frame_size)?;
+                    self.load_atlas(name, &directory.join(file), frame_size)
+                        .map_err(|error| {
+                            FileError::on_line(line_number, &format!("{}: {}", file, error))
+                        })?;
+                }
+                ["clip", name, clip, frames, milliseconds] => {
+                    let clip = Clip::from_name(clip)
+                        .ok_or_else(|| FileError::on_line(line_number, "Unknown clip."))?;
+                    let frames = frames
+                        .split(',')
+                        .map(|frame| save::parse_number(line_number, frame))
+                        .collect::<Result<Vec<u32>, FileError>>()?;
+                    let frame_duration =
+                        Duration::from_millis(save::parse_number(line_number, milliseconds)?);
+                    let atlas = self.atlases.get_mut(*name).ok_or_else(|| {
+                        FileError::on_line(line_number, "Clips need their atlas listed first.")
+                    })?;
+                    ClipFrames::new(frames, frame_duration)
+                        .and_then(|frames| atlas.add_clip(clip, frames))
+                        .map_err(|error| FileError::on_line(line_number, error))?;
+                }
+                ["font", name, file, glyph_size, first @ ..] if first.len() <= 1 => {
+                    let glyph_size = parse_frame_size(line_number, glyph_size)?;
+                    let first = match first.first() {
+                        Some(first) => save::parse_number(line_number, first)?,
+                        None => ' ' as u32,
+                    };
+                    self.load_font(name, &directory.join(file), glyph_size, first)
+                        .map_err(|error| {
+                            FileError::on_line(line_number, &format!("{}: {}", file, error))
+                        })?;
+                }
+                _ => return Err(FileError::on_line(line_number, "Unknown atlas record.")),
+            }
+        }
+        Ok(())
+    }
+    /// Returns the atlas called name, if it was loaded.
+    pub fn get_atlas(&self, name: &str) -> Option<&Atlas<'a>> {
+        self.atlases.get(name)
+    }
+    /// Returns a mutable reference to the atlas called name, if it was
+    /// loaded.
+    pub fn get_mut_atlas(&mut self, name: &str) -> Option<&mut Atlas<'a>> {
+        self.atlases.get_mut(name)
+    }
+    /// Returns the font called name, if it was loaded.
+    pub fn get_font(&self, name: &str) -> Option<&Font> {
+        self.fonts.get(name)
+    }
+}
+
+/// Reads a frame size like 16x16.
+fn parse_frame_size(line_number: usize, word: &str) -> Result<Dimension, FileError> {
+    match word.split_once('x') {
+        Some((width, height)) => Ok(Dimension::new(
+            save::parse_number(line_number, width)?,
+            save::parse_number(line_number, height)?,
+        )),
+        None => Err(FileError::on_line(
+            line_number,
+            "Expected a frame size like 16x16.",
+        )),
+    }
+}
+
+/// Loads any image SDL2_image can read.
+#[cfg(feature = "png")]
+fn load_surface(path: &Path) -> Result<Surface<'static>, FileError> {
+    use sdl2::image::LoadSurface;
+
+    Surface::from_file(path).map_err(|error| FileError::new(&error))
+}
+
+/// Loads a BMP image. Anything else needs the png feature.
+#[cfg(not(feature = "png"))]
+fn load_surface(path: &Path) -> Result<Surface<'static>, FileError> {
+    if !matches!(path.extension(), Some(extension) if extension.eq_ignore_ascii_case("bmp")) {
+        return Err(FileError::new(
+            "Only BMP images can be loaded without the png feature.",
+        ));
+    }
+    Surface::load_bmp(path).map_err(|error| FileError::new(&error))
+}
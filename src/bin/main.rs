@@ -2,6 +2,10 @@ use tiny_rpg::main_loop;
 use tiny_rpg::start;
 
 fn main() {
-    let init = start::main_start().unwrap();
+    let init = match start::main_start() {
+        Ok(Some(init)) => init,
+        Ok(None) => return,
+        Err(()) => std::process::exit(1),
+    };
     main_loop(init).unwrap();
 }
@@ -0,0 +1,355 @@
+//! The camera decides which part of a level is on the screen.
+//!
+//! It follows a target, usually the player, but only once the target leaves
+//! a dead zone in the middle of the screen. It can slide after the target
+//! instead of jumping, and is kept inside the bounds of the level so the
+//! void outside the walls isn't shown.
+//!
+//! Positions on the camera are in tiles and can be between tiles while it
+//! slides. world_to_screen and screen_to_world turn them into pixels and
+//! back, for rendering and for the mouse.
+//!
+//! # Planned changes:
+//!
+//! - Screen shake.
+
+use std::time::Duration;
+
+use crate::helper::{Builder, BuilderError};
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+
+/// Builds a Camera. Everything has a default, a camera that sticks to its
+/// target.
+pub struct CameraBuilder {
+    dead_zone: Dimension,
+    smoothing: Option<f32>,
+}
+
+impl CameraBuilder {
+    /// Sets the size in tiles of the area in the middle of the screen the
+    /// target can move around in without the camera moving.
+    pub fn set_dead_zone(mut self, dead_zone: Dimension) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+    /// Makes the camera slide after its target instead of jumping. speed is
+    /// roughly how many times a second the camera closes the gap, so higher
+    /// is snappier.
+    pub fn set_smoothing(mut self, speed: f32) -> Self {
+        self.smoothing = Some(speed);
+        self
+    }
+}
+
+impl Builder for CameraBuilder {
+    type product = Camera;
+
+    /// Creates an instance of CameraBuilder for a camera with no dead zone
+    /// or smoothing.
+    fn new() -> CameraBuilder {
+        CameraBuilder {
+            dead_zone: Dimension::new(0, 0),
+            smoothing: None,
+        }
+    }
+    /// Builds an instance of Camera. It looks at 0,0 until it follows
+    /// something.
+    fn build(self) -> Result<Camera, BuilderError> {
+        if let Some(speed) = self.smoothing {
+            if speed <= 0.0 {
+                return Err(BuilderError::new("Smoothing speed must be above 0."));
+            }
+        }
+        Ok(Camera {
+            center: (0.0, 0.0),
+            dead_zone: self.dead_zone,
+            smoothing: self.smoothing,
+        })
+    }
+}
+
+/// Where the view is looking. center is the point of the level in the
+/// middle of the screen, in tiles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    center: (f32, f32),
+    dead_zone: Dimension,
+    smoothing: Option<f32>,
+}
+
+impl Camera {
+    /// Creates an instance of CameraBuilder to build a Camera.
+    pub fn new() -> CameraBuilder {
+        CameraBuilder::new()
+    }
+    /// Returns the point of the level in the middle of the screen, in tiles.
+    pub fn get_center(&self) -> (f32, f32) {
+        self.center
+    }
+    /// Returns the size of the dead zone in tiles.
+    pub fn get_dead_zone(&self) -> Dimension {
+        self.dead_zone
+    }
+    /// Returns how fast the camera slides, if it does.
+    pub fn get_smoothing(&self) -> Option<f32> {
+        self.smoothing
+    }
+    /// Moves the camera toward target, which is kept inside the dead zone.
+    /// elapsed is the time since the last follow, for smoothing.
+    ///
+    /// If the target is off screen, like after a warp, the camera jumps
+    /// straight to it rather than sliding across the level.
+    pub fn follow(
+        &mut self,
+        target: Rectangle,
+        bounds: Option<Rectangle>,
+        tile_size: u32,
+        screen_size: Dimension,
+        elapsed: Duration,
+    ) {
+        let view = get_view_size(tile_size, screen_size);
+        let (target_x, target_y) = get_rectangle_center(target);
+        let desired = (
+            follow_axis(self.center.0, target_x, self.dead_zone.get_width() as f32),
+            follow_axis(self.center.1, target_y, self.dead_zone.get_height() as f32),
+        );
+        let off_screen = (target_x - self.center.0).abs() > view.0 / 2.0
+            || (target_y - self.center.1).abs() > view.1 / 2.0;
+        self.center = match self.smoothing {
+            Some(speed) if !off_screen => {
+                let amount = 1.0 - (-speed * elapsed.as_secs_f32()).exp();
+                (
+                    self.center.0 + (desired.0 - self.center.0) * amount,
+                    self.center.1 + (desired.1 - self.center.1) * amount,
+                )
+            }
+            _ => desired,
+        };
+        if let Some(bounds) = bounds {
+            self.clamp(bounds, view);
+        }
+    }
+    /// Puts target in the middle of the screen straight away, kept inside
+    /// bounds.
+    pub fn snap_to(
+        &mut self,
+        target: Rectangle,
+        bounds: Option<Rectangle>,
+        tile_size: u32,
+        screen_size: Dimension,
+    ) {
+        self.center = get_rectangle_center(target);
+        if let Some(bounds) = bounds {
+            self.clamp(bounds, get_view_size(tile_size, screen_size));
+        }
+    }
+    /// Returns where the top left corner of the tile at point is drawn, in
+    /// pixels.
+    pub fn world_to_screen(&self, point: Point, tile_size: u32, screen_size: Dimension) -> Point {
+        self.position_to_screen(
+            (point.get_x() as f32, point.get_y() as f32),
+            tile_size,
+            screen_size,
+        )
+    }
+    /// The same as world_to_screen, but for a position that can be between
+    /// tiles, like an entity sliding.
+    pub fn position_to_screen(
+        &self,
+        position: (f32, f32),
+        tile_size: u32,
+        screen_size: Dimension,
+    ) -> Point {
+        let tile_size = tile_size as f32;
+        let x = (position.0 - self.center.0) * tile_size + screen_size.get_width() as f32 / 2.0;
+        let y = (position.1 - self.center.1) * tile_size + screen_size.get_height() as f32 / 2.0;
+        Point::new(x.round() as i32, y.round() as i32)
+    }
+    /// Returns the tile under the pixel at point, like the mouse.
+    pub fn screen_to_world(&self, point: Point, tile_size: u32, screen_size: Dimension) -> Point {
+        let tile_size = tile_size as f32;
+        let x = (point.get_x() as f32 - screen_size.get_width() as f32 / 2.0) / tile_size
+            + self.center.0;
+        let y = (point.get_y() as f32 - screen_size.get_height() as f32 / 2.0) / tile_size
+            + self.center.1;
+        Point::new(x.floor() as i32, y.floor() as i32)
+    }
+    /// Returns the tiles that are at least partly on the screen.
+    pub fn get_visible_rectangle(&self, tile_size: u32, screen_size: Dimension) -> Rectangle {
+        let top_left = self.screen_to_world(Point::new(0, 0), tile_size, screen_size);
+        let bottom_right = self.screen_to_world(
+            Point::new(
+                screen_size.get_width() as i32 - 1,
+                screen_size.get_height() as i32 - 1,
+            ),
+            tile_size,
+            screen_size,
+        );
+        Rectangle::new(
+            top_left.get_x(),
+            top_left.get_y(),
+            (bottom_right.get_x() - top_left.get_x() + 1).max(0) as u32,
+            (bottom_right.get_y() - top_left.get_y() + 1).max(0) as u32,
+        )
+    }
+    /// Keeps the view inside bounds. A level smaller than the view is put
+    /// in the middle of it instead.
+    fn clamp(&mut self, bounds: Rectangle, view: (f32, f32)) {
+        self.center = (
+            clamp_axis(
+                self.center.0,
+                bounds.x() as f32,
+                bounds.width() as f32,
+                view.0,
+            ),
+            clamp_axis(
+                self.center.1,
+                bounds.y() as f32,
+                bounds.height() as f32,
+                view.1,
+            ),
+        );
+    }
+}
+
+/// Returns how many tiles fit on the screen each way.
+fn get_view_size(tile_size: u32, screen_size: Dimension) -> (f32, f32) {
+    let tile_size = tile_size.max(1) as f32;
+    (
+        screen_size.get_width() as f32 / tile_size,
+        screen_size.get_height() as f32 / tile_size,
+    )
+}
+
+/// Returns the middle of a rectangle in tiles.
+fn get_rectangle_center(rectangle: Rectangle) -> (f32, f32) {
+    (
+        rectangle.x() as f32 + rectangle.width() as f32 / 2.0,
+        rectangle.y() as f32 + rectangle.height() as f32 / 2.0,
+    )
+}
+
+/// Moves center along one axis just enough for target to be inside the dead
+/// zone.
+fn follow_axis(center: f32, target: f32, dead_zone: f32) -> f32 {
+    let half = dead_zone / 2.0;
+    if target > center + half {
+        target - half
+    } else if target < center - half {
+        target + half
+    } else {
+        center
+    }
+}
+
+/// Keeps center along one axis so the view stays between start and start
+/// plus length.
+fn clamp_axis(center: f32, start: f32, length: f32, view: f32) -> f32 {
+    if length <= view {
+        start + length / 2.0
+    } else {
+        center
+            .max(start + view / 2.0)
+            .min(start + length - view / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ten tiles each way.
+    const TILE_SIZE: u32 = 32;
+    fn screen() -> Dimension {
+        Dimension::new(320, 320)
+    }
+
+    fn tile(x: i32, y: i32) -> Rectangle {
+        Rectangle::new(x, y, 1, 1)
+    }
+
+    fn camera_at(x: i32, y: i32, dead_zone: Dimension) -> Camera {
+        let mut camera = Camera::new().set_dead_zone(dead_zone).build().unwrap();
+        camera.snap_to(tile(x, y), None, TILE_SIZE, screen());
+        camera
+    }
+
+    fn follow(camera: &mut Camera, target: Rectangle, bounds: Option<Rectangle>) {
+        camera.follow(
+            target,
+            bounds,
+            TILE_SIZE,
+            screen(),
+            Duration::from_millis(100),
+        );
+    }
+
+    #[test]
+    fn targets_move_freely_in_the_dead_zone() {
+        let mut camera = camera_at(10, 10, Dimension::new(4, 2));
+        assert_eq!(camera.get_center(), (10.5, 10.5));
+        follow(&mut camera, tile(12, 11), None);
+        assert_eq!(camera.get_center(), (10.5, 10.5));
+        // just past the edge, the camera moves only as far as it has to
+        follow(&mut camera, tile(13, 12), None);
+        assert_eq!(camera.get_center(), (11.5, 11.5));
+        follow(&mut camera, tile(5, 11), None);
+        assert_eq!(camera.get_center(), (7.5, 11.5));
+    }
+
+    #[test]
+    fn smoothing_slides_unless_off_screen() {
+        let mut camera = Camera::new().set_smoothing(5.0).build().unwrap();
+        camera.snap_to(tile(0, 0), None, TILE_SIZE, screen());
+        follow(&mut camera, tile(2, 0), None);
+        let (x, y) = camera.get_center();
+        assert!(x > 0.5 && x < 2.5, "{}", x);
+        assert_eq!(y, 0.5);
+        follow(&mut camera, tile(100, -50), None);
+        assert_eq!(camera.get_center(), (100.5, -49.5));
+    }
+
+    #[test]
+    fn the_view_stays_in_the_level() {
+        let bounds = Some(Rectangle::new(0, 0, 40, 20));
+        let mut camera = camera_at(0, 0, Dimension::new(0, 0));
+        camera.snap_to(tile(1, 1), bounds, TILE_SIZE, screen());
+        assert_eq!(camera.get_center(), (5.0, 5.0));
+        follow(&mut camera, tile(39, 19), bounds);
+        assert_eq!(camera.get_center(), (35.0, 15.0));
+        follow(&mut camera, tile(20, 10), bounds);
+        assert_eq!(camera.get_center(), (20.5, 10.5));
+        // smaller than the view, so it sits in the middle
+        let small = Some(Rectangle::new(-2, 0, 6, 4));
+        camera.snap_to(tile(-2, 0), small, TILE_SIZE, screen());
+        assert_eq!(camera.get_center(), (1.0, 2.0));
+    }
+
+    #[test]
+    fn screen_and_world_round_trip() {
+        let camera = camera_at(-4, 3, Dimension::new(0, 0));
+        assert_eq!(
+            camera.world_to_screen(Point::new(-4, 3), TILE_SIZE, screen()),
+            Point::new(144, 144)
+        );
+        for (x, y) in [(-3, -7), (-1, 0), (0, -1), (5, -2), (-40, 12)].iter() {
+            let point = Point::new(*x, *y);
+            let corner = camera.world_to_screen(point, TILE_SIZE, screen());
+            assert_eq!(camera.screen_to_world(corner, TILE_SIZE, screen()), point);
+            let inside = Point::new(corner.get_x() + 31, corner.get_y() + 31);
+            assert_eq!(camera.screen_to_world(inside, TILE_SIZE, screen()), point);
+        }
+    }
+
+    #[test]
+    fn partly_shown_tiles_are_visible() {
+        // centred on the middle of a tile, so half a tile shows at each edge
+        let camera = camera_at(0, 0, Dimension::new(0, 0));
+        let visible = camera.get_visible_rectangle(TILE_SIZE, screen());
+        assert_eq!(visible, Rectangle::new(-5, -5, 11, 11));
+        // 5.625 tiles each side across, so a sliver of -6 and 6 shows
+        let screen = Dimension::new(360, 300);
+        let visible = camera.get_visible_rectangle(TILE_SIZE, screen);
+        assert_eq!(visible, Rectangle::new(-6, -5, 13, 11));
+    }
+}
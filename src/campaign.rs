@@ -0,0 +1,370 @@
+//! Loads campaigns and level files from disk.
+//!
+//! A campaign is a directory with a campaign.txt file and any number of
+//! level files ending in .level. Like saves, both are plain text with one
+//! record per line and `#` starting a comment.
+//!
+//! campaign.txt says where the player starts, how levels connect, which
+//! dungeons to generate and the quests the player starts with:
+//!
+//! ```text
+//! start 1 5,5
+//! quest The Cellar: Find the stairs down.
+//! connection east 1 2 1,10
+//! dungeon 100 5 42 caves 60 40
+//! connection down 2 100 up
+//! ```
+//!
+//! A connection arrives at a point, like `1,10`, or at a named entrance of
+//! the level it goes to. Dungeon floors have an `up` and a `down` entrance
+//! next to their stairs. The first floor of a dungeon gets a warp back up to
+//! the level of the first connection into it, arriving at the entrance of
+//! that level named after the connection, `down` above.
+//!
+//! A level file describes a single level:
+//!
+//! ```text
+//! id 1
+//! name The Meadow
+//! type Game
+//! number 1
+//! boundary 0 0 50 50
+//! entrance down 24,3
+//! entity x=7 y=7 abilities=move
+//! terrain mud cost=3 skin=rgb:90,70,40
+//! tiles 1 1 38 18 grass
+//! tiles 20 5 4 4 mud
+//! ```
+//!
+//! `entrance name x,y` names a point warps and connections can arrive at,
+//! which warps use with `abilities=warp:2:down`.
+//!
+//! `tiles x y width height name` sets the terrain under a rectangle, later
+//! records drawing over earlier ones. The rectangle has to be inside the
+//! `boundary`, if the level has one, and no bigger than
+//! terrain::MAX_FILL_AREA tiles. Terrain can be one of the built in
+//! types from terrain.rs or one added with a `terrain` record, which can
+//! set `cost`, the turns a step onto it takes, `passable=false` and `skin`.
+//!
+//! An entity's skin can be a colour, `skin=rgb:90,90,90` or
+//! `skin=rgba:90,90,90,128`, or a frame of an atlas, `skin=sprite:slime:0`
+//! stretched over the entity or `skin=tiled:walls:0` repeated on each tile.
+//! `skin=animated:slime` plays the clips of an atlas as the entity moves.
+//! Atlases and clips are listed in an atlases.txt file in the campaign
+//! directory, see assets.rs and animation.rs.
+//!
+//! Walls in level files only break if they have health, like
+//! `entity x=4 y=4 width=3 health=3/3 abilities=opaque`. `boundary` walls
+//! are indestructible, as is anything with `abilities=indestructible`.
+//!
+//! Levels with the MainMenu or Settings type replace the built in menus.
+//! They need a `spawn x,y` record for where the player starts, and their
+//! options are entities with a menu ability, such as
+//! `entity x=9 y=2 width=3 abilities=menu:new_game`.
+//!
+//! # Planned changes:
+//!
+//! - Write levels back out, for a level editor.
+
+use std::fs;
+use std::path::Path;
+
+use crate::dungeon::{Algorithm, Dungeon};
+use crate::entity::Entity;
+use crate::helper::{Builder, FileError};
+use crate::maps::{Arrival, Connection, Level, LevelId, LevelType, Map};
+use crate::math::Rectangle;
+use crate::quests::Quest;
+use crate::save::{parse_entity, parse_number, parse_point, split_record, strip_comment};
+use crate::terrain::{Terrain, TileLayer};
+
+/// The name of the file in a campaign directory that holds the campaign.
+pub const CAMPAIGN_FILE: &str = "campaign.txt";
+/// The extension of level files in a campaign directory.
+pub const LEVEL_EXTENSION: &str = "level";
+
+/// Loads every level file and the campaign file in directory into a Map.
+/// seed is mixed into every dungeon seed, 0 keeps the ones in campaign.txt.
+pub fn load_campaign(directory: &Path, seed: u64) -> Result<Map, FileError> {
+    let mut builder = Map::new().set_seed(seed);
+    let mut paths: Vec<_> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == LEVEL_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+    for path in paths.iter() {
+        builder = builder.level(read_level(path)?);
+    }
+
+    let contents = fs::read_to_string(directory.join(CAMPAIGN_FILE))?;
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let (record, rest) = split_record(strip_comment(line));
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        builder = match (record, words.as_slice()) {
+            ("", []) => builder,
+            ("start", [level, spawn]) => builder.start(
+                parse_level_id(line_number, level)?,
+                parse_point(line_number, spawn)?,
+            ),
+            ("quest", _) => builder.add_quest(
+                Quest::from_record(rest).map_err(|error| FileError::on_line(line_number, error))?,
+            ),
+            ("connection", [name, from, to, arrival]) => builder.connection(Connection::new(
+                name,
+                parse_level_id(line_number, from)?,
+                parse_level_id(line_number, to)?,
+                parse_arrival(line_number, arrival)?,
+            )),
+            ("dungeon", [first_id, floors, seed, algorithm, size @ ..]) => {
+                let mut dungeon = Dungeon::new()
+                    .set_first_id(parse_level_id(line_number, first_id)?)
+                    .set_floor_count(parse_number(line_number, floors)?)
+                    .set_seed(parse_number(line_number, seed)?)
+                    .set_algorithm(parse_algorithm(line_number, algorithm)?);
+                match size {
+                    [] => {}
+                    [width, height] => {
+                        dungeon = dungeon.set_size(
+                            parse_number(line_number, width)?,
+                            parse_number(line_number, height)?,
+                        )
+                    }
+                    _ => {
+                        return Err(FileError::on_line(
+                            line_number,
+                            "Expected a width and height.",
+                        ))
+                    }
+                }
+                builder.add_dungeon(dungeon)
+            }
+            _ => return Err(FileError::on_line(line_number, "Unknown campaign record.")),
+        };
+    }
+
+    builder
+        .build()
+        .map_err(|error| FileError::new(&error.to_string()))
+}
+
+/// Reads a single level file.
+pub fn read_level(path: &Path) -> Result<Level, FileError> {
+    let contents = fs::read_to_string(path)?;
+    parse_level(&contents)
+        .map_err(|error| FileError::new(&format!("{}: {}", path.display(), error)))
+}
+
+/// Parses the contents of a level file.
+pub fn parse_level(contents: &str) -> Result<Level, FileError> {
+    let mut builder = Level::new();
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut tiles = TileLayer::new();
+    // checked against the boundary once it is known, it can come later
+    let mut tile_areas: Vec<(usize, Rectangle)> = Vec::new();
+    let mut bounds: Option<Rectangle> = None;
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let (record, rest) = split_record(strip_comment(line));
+        builder = match record {
+            "" => builder,
+            "id" => builder.id(parse_level_id(line_number, rest)?),
+            "name" => builder.name(rest),
+            "type" => match LevelType::from_name(rest) {
+                Some(level_type) => builder.level_type(level_type),
+                None => return Err(FileError::on_line(line_number, "Unknown level type.")),
+            },
+            "number" => builder.number(parse_number(line_number, rest)?),
+            "spawn" => builder.spawn(parse_point(line_number, rest)?),
+            "entrance" => match rest.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                [name, point] => builder.entrance(name, parse_point(line_number, point)?),
+                _ => return Err(FileError::on_line(line_number, "Expected a name and x,y.")),
+            },
+            "boundary" => {
+                let numbers = rest
+                    .split_whitespace()
+                    .map(|word| parse_number::<i32>(line_number, word))
+                    .collect::<Result<Vec<i32>, FileError>>()?;
+                match numbers.as_slice() {
+                    [x, y, width, height] if *width > 2 && *height > 2 => {
+                        let boundary = Rectangle::new(*x, *y, *width as u32, *height as u32);
+                        bounds = Some(match bounds {
+                            Some(bounds) => bounds.union(boundary),
+                            None => boundary,
+                        });
+                        entities.append(&mut Map::get_boundry_entities(boundary))
+                    }
+                    _ => {
+                        return Err(FileError::on_line(
+                            line_number,
+                            "Expected x y width height, with a size bigger than 2.",
+                        ))
+                    }
+                }
+                builder
+            }
+            "entity" => {
+                entities.push(parse_entity(line_number, rest)?);
+                builder
+            }
+            "terrain" => {
+                let terrain = Terrain::from_record(rest)
+                    .map_err(|error| FileError::on_line(line_number, error))?;
+                tiles.add_terrain(terrain);
+                builder
+            }
+            "tiles" => {
+                let words: Vec<&str> = rest.split_whitespace().collect();
+                match words.as_slice() {
+                    [x, y, width, height, name] => {
+                        let area = Rectangle::new(
+                            parse_number(line_number, x)?,
+                            parse_number(line_number, y)?,
+                            parse_number(line_number, width)?,
+                            parse_number(line_number, height)?,
+                        );
+                        tiles
+                            .fill(area, name)
+                            .map_err(|error| FileError::on_line(line_number, error))?;
+                        tile_areas.push((line_number, area));
+                    }
+                    _ => {
+                        return Err(FileError::on_line(
+                            line_number,
+                            "Expected x y width height and a terrain name.",
+                        ))
+                    }
+                }
+                builder
+            }
+            _ => return Err(FileError::on_line(line_number, "Unknown level record.")),
+        };
+    }
+    if let Some(bounds) = bounds {
+        if let Some((line_number, _)) = tile_areas
+            .iter()
+            .find(|(_, area)| bounds.union(*area) != bounds)
+        {
+            return Err(FileError::on_line(
+                *line_number,
+                "Tiles go outside the boundary.",
+            ));
+        }
+    }
+
+    builder
+        .entities(entities)
+        .tiles(tiles)
+        .build()
+        .map_err(|error| FileError::new(&error.to_string()))
+}
+
+fn parse_level_id(line_number: usize, word: &str) -> Result<LevelId, FileError> {
+    Ok(LevelId::new(parse_number(line_number, word)?))
+}
+
+/// Reads an arrival, a point like `1,10` or else the name of an entrance.
+fn parse_arrival(line_number: usize, word: &str) -> Result<Arrival, FileError> {
+    if word.contains(',') {
+        Ok(Arrival::At(parse_point(line_number, word)?))
+    } else {
+        Ok(Arrival::Entrance(word.to_string()))
+    }
+}
+
+fn parse_algorithm(line_number: usize, word: &str) -> Result<Algorithm, FileError> {
+    match word {
+        "rooms" => Ok(Algorithm::RoomsAndCorridors),
+        "caves" => Ok(Algorithm::Caves),
+        "bsp" => Ok(Algorithm::BinarySpacePartition),
+        _ => Err(FileError::on_line(
+            line_number,
+            "Unknown algorithm, use rooms, caves or bsp.",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Data;
+    use crate::hud::HudItem;
+    use crate::math::Point;
+
+    const GOOD_LEVEL: &str = "\
+id 3
+name The Well # a comment
+type Game
+number 4
+boundary 0 0 10 8
+entrance top 2,2
+entity x=4 y=4 abilities=move
+terrain mud cost=3
+tiles 1 1 3 3 mud
+";
+
+    /// Returns the error from parsing contents, which must fail.
+    fn level_error(contents: &str) -> String {
+        match parse_level(contents) {
+            Ok(_) => panic!("{:?} was accepted", contents),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_a_level() {
+        let level = parse_level(GOOD_LEVEL).unwrap();
+        assert_eq!(level.get_id(), LevelId::new(3));
+        assert_eq!(level.get_name(), "The Well");
+        assert_eq!(level.get_level_number(), 4);
+        assert_eq!(level.get_entrance("top"), Some(Point::new(2, 2)));
+        // the boundary walls and the one entity
+        assert_eq!(level.get_level_entities().len(), 5);
+        assert_eq!(level.get_tiles().get_cost(Point::new(2, 2)), Some(3));
+        assert_eq!(level.get_tiles().get_cost(Point::new(5, 5)), Some(1));
+    }
+
+    #[test]
+    fn bad_records_say_their_line() {
+        let unknown = format!("{}dragon 1 2\n", GOOD_LEVEL);
+        assert!(level_error(&unknown).contains("line 10:"));
+        let boundary = GOOD_LEVEL.replace("boundary 0 0 10 8", "boundary 0 0 2 8");
+        assert!(level_error(&boundary).contains("line 5:"));
+        let boundary = GOOD_LEVEL.replace("boundary 0 0 10 8", "boundary 0 0 ten 8");
+        assert!(level_error(&boundary).contains("line 5:"));
+        let tiles = GOOD_LEVEL.replace("1 1 3 3 mud", "1 1 3 3 marsh");
+        assert!(level_error(&tiles).contains("line 9:"));
+    }
+
+    #[test]
+    fn tiles_stay_in_the_level() {
+        let outside = GOOD_LEVEL.replace("1 1 3 3 mud", "8 6 3 3 mud");
+        assert!(level_error(&outside).contains("line 9: Tiles go outside"));
+        // a typo in the size is caught before filling, boundary or not
+        let huge = GOOD_LEVEL.replace("1 1 3 3 mud", "1 1 3 3000000 mud");
+        assert!(level_error(&huge).contains("line 9: Too many tiles"));
+        let huge = huge.replace("boundary 0 0 10 8\n", "");
+        assert!(level_error(&huge).contains("line 8: Too many tiles"));
+        // the edges of the boundary are still inside
+        let edges = GOOD_LEVEL.replace("1 1 3 3 mud", "0 0 10 8 mud");
+        assert!(parse_level(&edges).is_ok());
+    }
+
+    #[test]
+    fn loads_the_example_campaign() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("campaigns/example");
+        let map = load_campaign(&directory, 0).unwrap();
+        let level = map.get_current_level();
+        assert_eq!(level.get_name(), "The Meadow");
+        assert_eq!(level.get_level_entities().len(), 8);
+        assert!(map.find_connection(LevelId::new(2), "down").is_some());
+
+        // the campaign's quest is on the HUD from the start
+        let data = Data::from_map(map);
+        assert_eq!(data.get_current_quests().len(), 1);
+        assert!(HudItem::Quest.get_text(&data).is_some());
+    }
+}
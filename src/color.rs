@@ -0,0 +1,43 @@
+//! Colours for text, panels and anything else drawn without a sprite.
+//!
+//! The game keeps its own Color so everything but drawing builds without
+//! SDL. It has the same fields and constructors as the SDL one and turns
+//! into it wherever SDL wants a colour.
+//!
+//! # Planned changes:
+//!
+//! - Reading colours by name, like `red`, in level and message files.
+
+/// A colour with red, green, blue and alpha from 0 to 255.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Color {
+    /// How red the colour is.
+    pub r: u8,
+    /// How green the colour is.
+    pub g: u8,
+    /// How blue the colour is.
+    pub b: u8,
+    /// How solid the colour is, 255 hides what is under it.
+    pub a: u8,
+}
+
+impl Color {
+    /// Creates a new instance of Color that is fully solid. Named like the
+    /// SDL one so the two read the same.
+    #[allow(non_snake_case)]
+    pub const fn RGB(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+    /// Creates a new instance of Color with an alpha.
+    #[allow(non_snake_case)]
+    pub const fn RGBA(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl From<Color> for sdl2::pixels::Color {
+    fn from(color: Color) -> sdl2::pixels::Color {
+        sdl2::pixels::Color::RGBA(color.r, color.g, color.b, color.a)
+    }
+}
@@ -1,34 +1,72 @@
 //! Stores data that is manipulated or read in other
-//! parts of the program. Soon will have save functionality.
+//! parts of the program. Saving is done with the Save struct in save.rs.
 //!
 //! # Planned changes:
 //!
-//! - Add save struct
 //! - Probably add DataBuilder
 //! - Remove objects attribute from Data struct.
 
-use sdl2::keyboard::Keycode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::camera::Camera;
 use crate::entity::Entity;
 use crate::events;
-use crate::helper::{self, Builder};
+use crate::fov::{self, FieldOfView};
+use crate::helper::{self, Builder, FileError};
+use crate::hud::Hud;
+use crate::input::{self, Axis, Button, Input, InputEvent, InputState, Key, KeyRepeat};
 use crate::maps;
-use crate::math;
+use crate::math::{self, TwoDimensional};
+use crate::messages::{self, Message, MessageCategory, MessageLog};
+use crate::minimap::Minimap;
+use crate::path;
 use crate::quests;
-// use crate::Screen;
+use crate::save;
+use crate::screens::{MenuLevel, Screen};
+use crate::spatial::SpatialGrid;
+use crate::terrain::Terrain;
+use crate::tween::{TweenInput, Tweening};
+
+/// The first line of every settings file. Bump the number when the format
+/// changes.
+const SETTINGS_HEADER: &str = "tiny_rpg_settings 1";
+
+/// The file in the save directory settings are kept in between games.
+pub const SETTINGS_PATH: &str = "settings.txt";
 
 /// A structure that stores data for tiny RPG.
 ///
-/// Soon will have the ability to save.
+/// Can be saved and loaded with save::Save.
 pub struct Data {
-    // current_screen: Screen,
+    screens: Vec<Screen>,
+    quitting: bool,
+    fullscreen: bool,
     player: Entity,
     objects: Vec<Entity>,
+    spatial_grid: SpatialGrid,
+    spatial_grid_stale: bool,
     map: maps::Map,
     complete_quests: Vec<quests::Quest>,
     current_quests: Vec<quests::Quest>,
     settings: Settings,
+    input: InputState,
+    field_of_view: FieldOfView,
+    camera: Camera,
+    save_directory: PathBuf,
     time: u32,
+    clock: Duration,
+    messages: MessageLog,
+    hud: Hud,
+    screen_size: math::Dimension,
+    tile_size: u32,
+    travel: Vec<math::Point>,
+    last_travel_step: Duration,
+    minimap: Minimap,
+    minimap_stale: bool,
+    minimap_shown: bool,
+    striking: bool,
 }
 
 impl Data {
@@ -37,22 +75,218 @@ impl Data {
     /// Does not use a build as the data is always
     /// changing anyways.
     pub fn new() -> Data {
-        Data {
-            // current_screen: Screen::MainMenu,
-            player: Entity::new_player(5, 5),
-            objects: Vec::new(),
-            map: maps::Map::my_map(),
+        Data::from_map(maps::Map::my_map())
+    }
+    /// Creates a new instance of Data that plays through the given map. The
+    /// player starts at the maps spawn point.
+    pub fn from_map(map: maps::Map) -> Data {
+        let spawn = map.get_spawn();
+        let player = Entity::new_player(spawn.get_x(), spawn.get_y());
+        let objects = map.get_current_level().get_level_entities().clone();
+        let mut data = Data {
+            screens: vec![Screen::MainMenu(MenuLevel::for_type(
+                &map,
+                maps::LevelType::MainMenu,
+            ))],
+            quitting: false,
+            fullscreen: false,
+            field_of_view: FieldOfView::compute(
+                player.get_position(),
+                fov::DEFAULT_SIGHT_RADIUS,
+                &[],
+            ),
+            camera: Camera::new()
+                .set_dead_zone(math::Dimension::new(4, 2))
+                .set_smoothing(12.0)
+                .build()
+                .unwrap(),
+            player,
+            objects,
+            spatial_grid: SpatialGrid::default(),
+            spatial_grid_stale: true,
             complete_quests: Vec::new(),
-            current_quests: Vec::new(),
+            current_quests: map.get_quests().clone(),
+            map,
             settings: Settings::new().build().unwrap(),
+            input: InputState::new(),
+            save_directory: PathBuf::from("."),
             time: 0,
+            clock: Duration::from_millis(0),
+            messages: MessageLog::default(),
+            hud: Hud::default(),
+            screen_size: math::Dimension::new(0, 0),
+            tile_size: 1,
+            travel: Vec::new(),
+            last_travel_step: Duration::from_millis(0),
+            minimap: Minimap::new(),
+            minimap_stale: true,
+            minimap_shown: true,
+            striking: false,
+        };
+        data.update_field_of_view();
+        data
+    }
+    /// Starts the map over from the beginning, with the player back at the
+    /// spawn point. Settings and screens are kept.
+    pub fn new_game(&mut self) {
+        self.map.restart();
+        let spawn = self.map.get_spawn();
+        self.player = Entity::new_player(spawn.get_x(), spawn.get_y());
+        self.objects = self.get_level().get_level_entities().clone();
+        self.spatial_grid_stale = true;
+        self.minimap_stale = true;
+        self.striking = false;
+        self.time = 0;
+        self.travel.clear();
+        self.messages.clear();
+        self.current_quests = self.map.get_quests().clone();
+        self.complete_quests.clear();
+        self.update_field_of_view();
+    }
+    /// Puts a screen on top of the screen stack. It gets all input until it
+    /// is popped.
+    pub fn push_screen(&mut self, screen: Screen) {
+        self.screens.push(screen);
+    }
+    /// Removes the top screen. If it was the last screen the game quits, as
+    /// there is nothing left to show.
+    pub fn pop_screen(&mut self) -> Option<Screen> {
+        let screen = self.screens.pop();
+        if self.screens.is_empty() {
+            self.quit();
         }
+        screen
+    }
+    /// Removes every screen without quitting. A screen should be pushed
+    /// straight after.
+    pub fn clear_screens(&mut self) {
+        self.screens.clear();
+    }
+    /// Returns the screen on top of the stack.
+    pub fn get_screen(&self) -> Option<&Screen> {
+        self.screens.last()
+    }
+    /// Returns a reference to the screen stack, bottom first.
+    pub fn get_screens(&self) -> &Vec<Screen> {
+        &self.screens
+    }
+    /// Returns a mutable reference to the screen stack, bottom first.
+    pub fn get_mut_screens(&mut self) -> &mut Vec<Screen> {
+        &mut self.screens
+    }
+    /// Returns the camera looking at the game.
+    pub fn get_camera(&self) -> &Camera {
+        &self.camera
+    }
+    /// Moves the game camera after the player, and the cameras of any menu
+    /// levels in the screen stack after theirs.
+    pub fn update_cameras(
+        &mut self,
+        tile_size: u32,
+        screen_size: math::Dimension,
+        elapsed: Duration,
+    ) {
+        let bounds = self.get_level().get_bounds();
+        self.camera.follow(
+            self.player.get_rectangle(),
+            bounds,
+            tile_size,
+            screen_size,
+            elapsed,
+        );
+        for screen in self.screens.iter_mut() {
+            if let Screen::MainMenu(menu_level) | Screen::Settings(menu_level) = screen {
+                menu_level.update_camera(tile_size, screen_size, elapsed);
+            }
+        }
+    }
+    /// Returns the size of the area drawn in, which widgets are laid out
+    /// in.
+    pub fn get_screen_size(&self) -> math::Dimension {
+        self.screen_size
+    }
+    /// Changes the size of the area drawn in, when the window is resized.
+    pub fn set_screen_size(&mut self, screen_size: math::Dimension) {
+        self.screen_size = screen_size;
+    }
+    /// Returns the size in pixels tiles are drawn at.
+    pub fn get_tile_size(&self) -> u32 {
+        self.tile_size
+    }
+    /// Changes the size tiles are drawn at, when the viewport changes.
+    pub fn set_tile_size(&mut self, tile_size: u32) {
+        self.tile_size = tile_size.max(1);
+    }
+    /// Returns the tile of the game under a pixel, like the mouse. Uses the
+    /// same camera as drawing, so it matches what is on screen.
+    pub fn screen_to_world(&self, point: math::Point) -> math::Point {
+        self.camera
+            .screen_to_world(point, self.tile_size, self.screen_size)
+    }
+    /// Switches between a window and fullscreen. The main loop changes the
+    /// window to match.
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen = !self.fullscreen;
+    }
+    /// Sets if the game should be fullscreen.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+    /// Returns true if the game should be fullscreen.
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+    /// Sets the directory saves are kept in.
+    pub fn set_save_directory(&mut self, directory: &Path) {
+        self.save_directory = directory.to_path_buf();
+    }
+    /// Returns the path of the save used by quick save, quick load and the
+    /// pause menu.
+    pub fn get_save_path(&self) -> PathBuf {
+        self.save_directory.join(save::DEFAULT_SAVE_PATH)
+    }
+    /// Returns the path settings are read from and written to, next to
+    /// the saves.
+    pub fn get_settings_path(&self) -> PathBuf {
+        self.save_directory.join(SETTINGS_PATH)
     }
-    /// Takes a level and gets the entities from it.
-    pub fn set_level(&mut self, level: maps::Level) {
-        if self.map.change_level(level) {
+    /// Tells the main loop to stop after this frame.
+    pub fn quit(&mut self) {
+        self.quitting = true;
+    }
+    /// Returns true once something has asked the game to quit.
+    pub fn is_quitting(&self) -> bool {
+        self.quitting
+    }
+    /// Changes to the level with the given id and gets the entities from it.
+    ///
+    /// The entities of the level being left are stored back in it first, so
+    /// they are the same when the player returns. What the player sees isn't
+    /// updated, since they are still where they were on the old level, warp
+    /// does that once they are moved.
+    pub fn set_level(&mut self, id: maps::LevelId) -> Result<(), &'static str> {
+        let entities = self.objects.clone();
+        self.map
+            .get_mut_current_level()
+            .set_level_entities(entities);
+        if self.map.change_level(id)? {
             self.objects = self.get_level().get_level_entities().clone();
+            self.spatial_grid_stale = true;
+            self.minimap_stale = true;
         }
+        Ok(())
+    }
+    /// Moves the player to a point or entrance on the level with the given
+    /// id. Nothing changes if the level or entrance can't be found.
+    pub fn warp(&mut self, id: maps::LevelId, arrival: &maps::Arrival) -> Result<(), &'static str> {
+        let point = self.map.find_arrival(id, arrival)?;
+        self.set_level(id)?;
+        self.player.set_position(point);
+        self.travel.clear();
+        let message = format!("You enter {}.", self.get_level().get_name());
+        self.add_message(MessageCategory::Travel, &message);
+        self.update_field_of_view();
+        Ok(())
     }
     /// Returns &Entity if it ia at position, else returns none.
     ///
@@ -102,17 +336,72 @@ impl Data {
     pub fn get_settings(&self) -> &Settings {
         &self.settings
     }
+    /// Returns a mutable reference to settings.
+    pub fn get_mut_settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+    /// Returns a reference to the input state, which knows the connected
+    /// controllers.
+    pub fn get_input_state(&self) -> &InputState {
+        &self.input
+    }
+    /// Returns a mutable reference to the input state.
+    pub fn get_mut_input_state(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+    /// Moves time forward for held inputs. Held movement puts a repeat in
+    /// the input buffer, other actions only happen once per press.
+    pub fn update_input(&mut self, elapsed: Duration) {
+        let repeat = self.settings.get_key_repeat();
+        for input_event in self.input.update(elapsed, repeat).into_iter() {
+            let action = self.get_action(&input_event);
+            if matches!(action, Some(action) if action.is_movement()) {
+                self.input.buffer_repeat(input_event);
+            }
+        }
+    }
+    /// Returns the name of the binding profile of the device the input
+    /// event came from, if it is a known controller.
+    pub fn get_profile_name(&self, input_event: &InputEvent) -> Option<&str> {
+        self.input.get_device_name(input_event.get_device())
+    }
+    /// Returns the name of the binding profile of the device last used, if
+    /// it is a known controller.
+    pub fn get_last_profile_name(&self) -> Option<&str> {
+        self.input
+            .get_last_device()
+            .and_then(|device| self.input.get_device_name(device))
+    }
+    /// Returns the action bound to the input event.
+    pub fn get_action(&self, input_event: &InputEvent) -> Option<events::PlayerAction> {
+        self.settings
+            .get_action(input_event.get_input(), self.get_profile_name(input_event))
+    }
+    /// Replaces settings, such as with ones read from the settings file.
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
     /// Adds a given Entity to the list of entities.
     // also have preconfig entites, maybe use and enum
     pub fn add_entity(&mut self, entity: Entity) {
         self.objects.push(entity);
+        self.spatial_grid_stale = true;
     }
     /// Goes through the list of entities and have them perform
     /// their actions.
     pub fn entities_act(&mut self) {
+        // entities are taken out while they act, which moves every index
+        self.spatial_grid_stale = true;
+        // the player has already moved, and every clip and slide from this
+        // turn starts now on the render clock
+        let tween_duration = self.settings.get_tweening().get_duration();
+        self.player.start_clip(self.clock);
+        self.player.start_tween(self.clock, tween_duration);
         for _i in 0..self.objects.len() {
             let mut object = self.objects.remove(0);
             object.perform_turn(self);
+            object.start_clip(self.clock);
+            object.start_tween(self.clock, tween_duration);
             self.add_entity(object);
         }
     }
@@ -122,13 +411,149 @@ impl Data {
     }
     /// Gets a mutable reference to the entities in the level.
     pub fn get_mut_entities(&mut self) -> &mut Vec<Entity> {
+        // can't tell what the caller changes, so assume it moved things
+        self.spatial_grid_stale = true;
         &mut self.objects
     }
+    /// Returns the entities colliding with area, in the order they are in
+    /// the level.
+    ///
+    /// Uses the spatial grid when it is up to date, otherwise every entity
+    /// is checked.
+    pub fn get_entities_in(&self, area: math::Rectangle) -> Vec<&Entity> {
+        if self.spatial_grid_stale {
+            return self
+                .objects
+                .iter()
+                .filter(|object| object.get_rectangle().is_colliding(area))
+                .collect();
+        }
+        self.spatial_grid
+            .query(area)
+            .into_iter()
+            .map(|index| &self.objects[index])
+            .filter(|object| object.get_rectangle().is_colliding(area))
+            .collect()
+    }
+    /// Rebuilds the spatial grid if the entities changed since it was last
+    /// built. Called once a frame, so a turn where many entities move only
+    /// rebuilds it once.
+    pub fn update_spatial_grid(&mut self) {
+        if self.spatial_grid_stale {
+            self.spatial_grid =
+                SpatialGrid::new(self.objects.iter().map(|object| object.get_rectangle()));
+            self.spatial_grid_stale = false;
+        }
+    }
+    /// Damages whatever can be damaged at point. Only the tile at point of
+    /// a wall is damaged, the rest of the wall is split off around it.
+    /// Returns true if it broke, or an error saying why nothing happened.
+    pub fn damage_tile(&mut self, point: math::Point, damage: u32) -> Result<bool, &'static str> {
+        let tile = math::Rectangle::new(point.get_x(), point.get_y(), 1, 1);
+        let index = self
+            .objects
+            .iter()
+            .position(|object| {
+                object.get_rectangle().is_colliding(tile)
+                    && (object.get_health().is_some() || object.get_abilities().is_indestructible())
+            })
+            .ok_or("There is nothing there to strike.")?;
+        if !self.objects[index].is_destructible() {
+            return Err("It doesn't even scratch.");
+        }
+        let mut struck = self.objects.remove(index);
+        if struck.get_abilities().can_not_move() {
+            self.objects.append(&mut struck.split_off(point));
+        }
+        let broken = struck.take_damage(damage);
+        if !broken {
+            self.objects.push(struck);
+        }
+        self.spatial_grid_stale = true;
+        self.minimap_stale = true;
+        Ok(broken)
+    }
+    /// Starts or stops striking. While striking the next move hits what is
+    /// that way instead.
+    pub fn toggle_striking(&mut self) {
+        self.striking = !self.striking;
+    }
+    /// Returns true if the next move is a strike.
+    pub fn is_striking(&self) -> bool {
+        self.striking
+    }
+    /// Returns if the next move is a strike, and stops striking.
+    pub fn take_striking(&mut self) -> bool {
+        std::mem::take(&mut self.striking)
+    }
     /// Changes out entities in the level.
     ///
     /// Useful for room changes.
     pub fn change_entities(&mut self, entities: Vec<Entity>) {
         self.objects = entities;
+        self.spatial_grid_stale = true;
+        self.minimap_stale = true;
+        self.update_field_of_view();
+    }
+    /// Returns the rectangles of every entity that blocks sight.
+    pub fn get_opaque_rectangles(&self) -> Vec<math::Rectangle> {
+        self.objects
+            .iter()
+            .filter(|object| object.get_abilities().is_opaque())
+            .map(|object| object.get_rectangle())
+            .collect()
+    }
+    /// Returns true if an opaque entity covers the point.
+    pub fn is_opaque(&self, point: math::Point) -> bool {
+        let tile = math::Rectangle::new(point.get_x(), point.get_y(), 1, 1);
+        self.objects.iter().any(|object| {
+            object.get_abilities().is_opaque() && object.get_rectangle().is_colliding(tile)
+        })
+    }
+    /// Returns true if something at from could see to within radius.
+    pub fn can_see(&self, from: math::Point, to: math::Point, radius: u32) -> bool {
+        fov::can_see(from, to, radius, |point| self.is_opaque(point))
+    }
+    /// Recomputes what the player can see. Should be called whenever the
+    /// player or an opaque entity moves.
+    pub fn update_field_of_view(&mut self) {
+        self.field_of_view = FieldOfView::compute(
+            self.player.get_position(),
+            fov::DEFAULT_SIGHT_RADIUS,
+            &self.get_opaque_rectangles(),
+        );
+        let visible_points = self.field_of_view.get_visible_points();
+        let level = self.map.get_mut_current_level();
+        let explored = level.get_explored().len();
+        level.explore(visible_points);
+        // the minimap only needs redrawing when something new was seen
+        if level.get_explored().len() != explored {
+            self.minimap_stale = true;
+        }
+    }
+    /// Rebuilds the static layer of the minimap if the level or what has
+    /// been explored changed. Called once a frame like the spatial grid.
+    pub fn update_minimap(&mut self) {
+        if self.minimap_stale {
+            self.minimap = Minimap::build(self.map.get_current_level(), &self.objects);
+            self.minimap_stale = false;
+        }
+    }
+    /// Returns the static layer of the minimap.
+    pub fn get_minimap(&self) -> &Minimap {
+        &self.minimap
+    }
+    /// Shows or hides the minimap.
+    pub fn toggle_minimap(&mut self) {
+        self.minimap_shown = !self.minimap_shown;
+    }
+    /// Returns true if the minimap should be drawn.
+    pub fn is_minimap_shown(&self) -> bool {
+        self.minimap_shown
+    }
+    /// Returns what the player could see the last time it was updated.
+    pub fn get_field_of_view(&self) -> &FieldOfView {
+        &self.field_of_view
     }
     /// Changes the time by one. Time is the turn counter.
     ///
@@ -140,9 +565,245 @@ impl Data {
     pub fn get_time(&self) -> u32 {
         self.time
     }
+    /// Sets the game time. Mostly for loading saves.
+    pub fn set_time(&mut self, time: u32) {
+        self.time = time;
+    }
+    /// Moves the render clock on by elapsed. The render clock runs every
+    /// frame, unlike time, and drives animations.
+    pub fn advance_clock(&mut self, elapsed: Duration) {
+        self.clock += elapsed;
+    }
+    /// Adds a message for the player, stamped with the current turn.
+    pub fn add_message(&mut self, category: MessageCategory, text: &str) {
+        self.messages.add(Message::new(self.time, category, text));
+    }
+    /// Adds an error as a system message in red. The message log is the
+    /// only place it goes, errors before there is a screen are printed by
+    /// main_loop.
+    pub fn add_error(&mut self, error: &str) {
+        self.log_message(
+            Message::new(self.time, MessageCategory::System, error)
+                .set_color(messages::ERROR_COLOR),
+        );
+    }
+    /// Adds a message that was already made, such as one with its own
+    /// colour.
+    pub fn log_message(&mut self, message: Message) {
+        self.messages.add(message);
+    }
+    /// Returns the message log.
+    pub fn get_messages(&self) -> &MessageLog {
+        &self.messages
+    }
+    /// Replaces the message log, such as with the one in a save.
+    pub fn set_messages(&mut self, messages: MessageLog) {
+        self.messages = messages;
+    }
+    /// Starts a quest.
+    pub fn start_quest(&mut self, quest: quests::Quest) {
+        self.current_quests.push(quest);
+    }
+    /// Moves the current quest called name to the completed quests.
+    pub fn complete_quest(&mut self, name: &str) {
+        if let Some(index) = self
+            .current_quests
+            .iter()
+            .position(|quest| quest.get_name() == name)
+        {
+            let quest = self.current_quests.remove(index);
+            self.complete_quests.push(quest);
+        }
+    }
+    /// Returns the quests being done, oldest first.
+    pub fn get_current_quests(&self) -> &Vec<quests::Quest> {
+        &self.current_quests
+    }
+    /// Returns the quests that are done.
+    pub fn get_complete_quests(&self) -> &Vec<quests::Quest> {
+        &self.complete_quests
+    }
+    /// Replaces the quests being done and the ones that are done, like
+    /// when loading.
+    pub fn set_quests(&mut self, current: Vec<quests::Quest>, complete: Vec<quests::Quest>) {
+        self.current_quests = current;
+        self.complete_quests = complete;
+    }
+    /// Returns what the HUD shows.
+    pub fn get_hud(&self) -> &Hud {
+        &self.hud
+    }
+    /// Changes what the HUD shows, such as to the layout in the config.
+    pub fn set_hud(&mut self, hud: Hud) {
+        self.hud = hud;
+    }
+    /// Returns what the player can tell about the tile at point, or None if
+    /// it can't be seen or there is nothing there.
+    pub fn describe_tile(&self, point: math::Point) -> Option<String> {
+        if !self.field_of_view.is_visible(point) {
+            return None;
+        }
+        let tile = math::Rectangle::new(point.get_x(), point.get_y(), 1, 1);
+        if self.player.get_rectangle().is_colliding(tile) {
+            return Some(match self.player.get_health() {
+                Some((health, most)) => format!("You, {}/{} health.", health, most),
+                None => "You.".to_string(),
+            });
+        }
+        self.check_position(tile)
+            .map(Entity::get_description)
+            .or_else(|| {
+                self.get_level()
+                    .get_tiles()
+                    .get_terrain(point)
+                    .map(Terrain::get_description)
+            })
+    }
+    /// Returns how many turns moving onto position takes because of the
+    /// terrain under it, or None if the terrain can't be walked on.
+    /// Entities in the way are not checked.
+    pub fn get_move_cost(&self, position: math::Rectangle) -> Option<u32> {
+        self.get_level().get_tiles().get_area_cost(position)
+    }
+    /// Starts walking the player to target, a step a turn. Only tiles the
+    /// player has seen are walked through. Returns false, with a message
+    /// saying why, if there is no way there.
+    pub fn travel_to(&mut self, target: math::Point) -> bool {
+        let level = self.get_level();
+        let size = self.player.get_size();
+        let is_known =
+            |point: math::Point| level.is_explored(point) || self.field_of_view.is_visible(point);
+        if !is_known(target) {
+            self.add_message(MessageCategory::Travel, "You don't know the way there.");
+            return false;
+        }
+        let path = path::find_path(self.player.get_position(), target, |point| {
+            let position = math::Rectangle::new(
+                point.get_x(),
+                point.get_y(),
+                size.get_width(),
+                size.get_height(),
+            );
+            if !is_known(point) || self.check_position(position).is_some() {
+                return None;
+            }
+            self.get_move_cost(position)
+        });
+        match path {
+            Some(path) if !path.is_empty() => {
+                self.travel = path;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.add_message(MessageCategory::Travel, "You can't find a way there.");
+                false
+            }
+        }
+    }
+    /// Returns the steps left on the way to where the player is walking.
+    pub fn get_travel(&self) -> &Vec<math::Point> {
+        &self.travel
+    }
+    /// Returns true while the player is walking somewhere.
+    pub fn is_travelling(&self) -> bool {
+        !self.travel.is_empty()
+    }
+    /// Stops walking.
+    pub fn cancel_travel(&mut self) {
+        self.travel.clear();
+    }
+    /// Returns the move for the next step of the walk once the last step
+    /// has finished sliding and as long as a held move takes to repeat, or
+    /// None if it isn't time yet. The walk stops if the game isn't on top
+    /// or something got in the way.
+    pub fn take_travel_step(&mut self) -> Option<events::PlayerAction> {
+        let next = *self.travel.first()?;
+        let interval = Duration::from_millis(self.settings.get_key_repeat().get_interval() as u64);
+        if self.player.is_tweening(self.clock) || self.clock - self.last_travel_step < interval {
+            return None;
+        }
+        if self.get_screen() != Some(&Screen::Game) {
+            self.cancel_travel();
+            return None;
+        }
+        let (x, y) = (
+            next.get_x() - self.player.get_position().get_x(),
+            next.get_y() - self.player.get_position().get_y(),
+        );
+        let action = match (x, y) {
+            (-1, 0) => events::PlayerAction::MoveLeft,
+            (1, 0) => events::PlayerAction::MoveRight,
+            (0, -1) => events::PlayerAction::MoveUp,
+            (0, 1) => events::PlayerAction::MoveDown,
+            _ => {
+                // the player was moved some other way, like a load
+                self.cancel_travel();
+                return None;
+            }
+        };
+        // the last step can walk into a warp
+        let last = self.travel.len() == 1;
+        let blocked = match self.check_position(self.player.get_move(math::Point::new(x, y))) {
+            Some(entity) => !last || entity.get_abilities().is_warp().is_none(),
+            None => false,
+        };
+        if blocked {
+            self.cancel_travel();
+            self.add_message(MessageCategory::Travel, "Something is in the way.");
+            return None;
+        }
+        self.travel.remove(0);
+        self.last_travel_step = self.clock;
+        Some(action)
+    }
+    /// Gets how long the game has been rendering for.
+    pub fn get_clock(&self) -> Duration {
+        self.clock
+    }
+    /// Returns true if the buffered input is movement that has to wait for
+    /// the player to finish sliding, because tweening queues input.
+    pub fn is_waiting_for_tween(&self) -> bool {
+        self.settings.get_tweening().get_input() == TweenInput::Queue
+            && self.is_buffered_move_in_game()
+            && self.player.is_tweening(self.clock)
+    }
+    /// Jumps every slide to its end if tweening fast forwards and the
+    /// buffered input is movement. Called before the input is handled.
+    pub fn fast_forward_tweens(&mut self) {
+        if self.settings.get_tweening().get_input() != TweenInput::FastForward
+            || !self.is_buffered_move_in_game()
+            || !self.player.is_tweening(self.clock)
+        {
+            return;
+        }
+        self.player.finish_tween();
+        for object in self.objects.iter_mut() {
+            object.finish_tween();
+        }
+    }
+    /// Returns true if the game is on top of the screen stack and the
+    /// buffered input is bound to movement.
+    fn is_buffered_move_in_game(&self) -> bool {
+        let action = self
+            .input
+            .get_buffered()
+            .and_then(|input_event| self.get_action(input_event));
+        matches!(self.get_screen(), Some(Screen::Game))
+            && matches!(action, Some(action) if action.is_movement())
+    }
     /// Returns a reference to the level.
     pub fn get_level(&self) -> &maps::Level {
-        &self.map.get_current_level()
+        self.map.get_current_level()
+    }
+    /// Returns a reference to the map.
+    pub fn get_map(&self) -> &maps::Map {
+        &self.map
+    }
+    /// Returns a mutable reference to the map.
+    pub fn get_mut_map(&mut self) -> &mut maps::Map {
+        self.minimap_stale = true;
+        &mut self.map
     }
     /// Tries to give the program randomness based on game events.
     ///
@@ -166,44 +827,106 @@ impl Data {
     }
 }
 
+/// An input and the action it does.
+pub type Binding = (Input, events::PlayerAction);
+
 /// Builds the Settings enum.
 ///
 /// Settings are things that can be changed in game or on the main menu.
 pub struct SettingsBuilder {
-    bindings: Vec<(Keycode, events::PlayerAction)>,
+    bindings: Vec<Binding>,
+    profiles: Vec<(String, Vec<Binding>)>,
+    key_repeat: KeyRepeat,
+    tweening: Tweening,
+}
+
+impl SettingsBuilder {
+    /// Replaces the default bindings.
+    pub fn set_bindings(mut self, bindings: Vec<Binding>) -> Self {
+        self.bindings = bindings;
+        self
+    }
+    /// Sets how held movement repeats. Defaults to input::DEFAULT_REPEAT_DELAY
+    /// and input::DEFAULT_REPEAT_INTERVAL.
+    pub fn set_key_repeat(mut self, key_repeat: KeyRepeat) -> Self {
+        self.key_repeat = key_repeat;
+        self
+    }
+    /// Sets how moves slide. Defaults to tween::DEFAULT_TWEEN_DURATION with
+    /// movement queued during a slide.
+    pub fn set_tweening(mut self, tweening: Tweening) -> Self {
+        self.tweening = tweening;
+        self
+    }
+    /// Adds a binding profile for the controller with the given name.
+    pub fn add_profile(mut self, name: &str, bindings: Vec<Binding>) -> Self {
+        self.profiles.push((name.to_string(), bindings));
+        self
+    }
 }
 
 impl Builder for SettingsBuilder {
     type product = Settings;
 
-    /// Creates a new instance of SettingsBuilder.
+    /// Creates a new instance of SettingsBuilder with the default bindings.
     fn new() -> SettingsBuilder {
-        use events::PlayerAction::*;
-
-        let bindings = vec![
-            (Keycode::A, MoveLeft),
-            (Keycode::D, MoveRight),
-            (Keycode::S, MoveDown),
-            (Keycode::W, MoveUp),
-        ];
-
-        SettingsBuilder { bindings }
+        SettingsBuilder {
+            bindings: Settings::get_default_bindings(),
+            profiles: Vec::new(),
+            key_repeat: KeyRepeat::default(),
+            tweening: Tweening::default(),
+        }
     }
     /// Creates a instance of Settings from the SettingsBuilder.
+    ///
+    /// Returns an error if an input is bound to two different actions, or a
+    /// profile has something other than controller inputs.
     fn build(self) -> Result<Settings, helper::BuilderError> {
-        let mut bindings = Vec::new();
-        for (keycode, action) in self.bindings.iter() {
-            bindings.push((*keycode, *action));
+        let mut settings = Settings {
+            bindings: Vec::new(),
+            profiles: Vec::new(),
+            key_repeat: self.key_repeat,
+            tweening: self.tweening,
+        };
+        for (input, action) in self.bindings.into_iter() {
+            if settings.bind(input, action, None).is_err() {
+                return Err(helper::BuilderError::new(
+                    "An input can only be bound to one action.",
+                ));
+            }
+        }
+        for (name, bindings) in self.profiles.into_iter() {
+            if bindings.iter().any(|binding| !binding.0.is_controller()) {
+                return Err(helper::BuilderError::new(
+                    "Profiles can only have controller inputs.",
+                ));
+            }
+            settings.profiles.push((name.clone(), Vec::new()));
+            for (input, action) in bindings.into_iter() {
+                if settings.bind(input, action, Some(&name)).is_err() {
+                    return Err(helper::BuilderError::new(
+                        "An input can only be bound to one action.",
+                    ));
+                }
+            }
         }
-        Ok(Settings { bindings })
+        Ok(settings)
     }
 }
 
 /// Settings is an enum that can be changed in game or on the main menu.
 ///
-/// May make a binding struct or type alias.
+/// Keys and controller inputs share one list of bindings. A controller can
+/// also have its own profile, found by the controller's name, which is used
+/// instead of the shared controller bindings.
+///
+/// Settings are kept in a plain text file like saves, so changes last
+/// between games.
 pub struct Settings {
-    bindings: Vec<(Keycode, events::PlayerAction)>,
+    bindings: Vec<Binding>,
+    profiles: Vec<(String, Vec<Binding>)>,
+    key_repeat: KeyRepeat,
+    tweening: Tweening,
 }
 
 impl Settings {
@@ -212,12 +935,493 @@ impl Settings {
     pub fn new() -> SettingsBuilder {
         SettingsBuilder::new()
     }
+    /// The bindings a new player starts with.
+    pub fn get_default_bindings() -> Vec<Binding> {
+        use events::PlayerAction::*;
+
+        vec![
+            (Input::Key(Key::A), MoveLeft),
+            (Input::Key(Key::D), MoveRight),
+            (Input::Key(Key::S), MoveDown),
+            (Input::Key(Key::W), MoveUp),
+            (Input::Key(Key::F5), QuickSave),
+            (Input::Key(Key::F9), QuickLoad),
+            (Input::Key(Key::I), Inventory),
+            (Input::Key(Key::Return), Confirm),
+            (Input::Key(Key::Space), Confirm),
+            (Input::Key(Key::Escape), Back),
+            (Input::Key(Key::F11), ToggleFullscreen),
+            (Input::Key(Key::L), History),
+            (Input::Key(Key::M), ToggleMinimap),
+            (Input::Key(Key::F), Strike),
+            (Input::Button(Button::DPadLeft), MoveLeft),
+            (Input::Button(Button::DPadRight), MoveRight),
+            (Input::Button(Button::DPadDown), MoveDown),
+            (Input::Button(Button::DPadUp), MoveUp),
+            (Input::Axis(Axis::LeftX, false), MoveLeft),
+            (Input::Axis(Axis::LeftX, true), MoveRight),
+            (Input::Axis(Axis::LeftY, true), MoveDown),
+            (Input::Axis(Axis::LeftY, false), MoveUp),
+            (Input::Button(Button::Y), Inventory),
+            (Input::Button(Button::A), Confirm),
+            (Input::Button(Button::B), Back),
+            (Input::Button(Button::Start), Back),
+            (Input::Button(Button::Back), History),
+            (Input::Button(Button::RightShoulder), ToggleMinimap),
+            (Input::Button(Button::X), Strike),
+        ]
+    }
     /// Returns a reference of the bindings attribute.
     ///
-    /// The binding attribute is a tuple of Keycode followed by action.
+    /// The binding attribute is a tuple of Input followed by action.
     /// May be moved away from data to make the program less dependent
     /// on sdl2.
-    pub fn get_bindings(&self) -> &Vec<(Keycode, events::PlayerAction)> {
+    pub fn get_bindings(&self) -> &Vec<Binding> {
         &self.bindings
     }
+    /// Returns how held movement repeats.
+    pub fn get_key_repeat(&self) -> KeyRepeat {
+        self.key_repeat
+    }
+    /// Changes how held movement repeats.
+    pub fn set_key_repeat(&mut self, key_repeat: KeyRepeat) {
+        self.key_repeat = key_repeat;
+    }
+    /// Returns how moves slide.
+    pub fn get_tweening(&self) -> Tweening {
+        self.tweening
+    }
+    /// Changes how moves slide.
+    pub fn set_tweening(&mut self, tweening: Tweening) {
+        self.tweening = tweening;
+    }
+    /// Returns the bindings of the controller profile with the given name.
+    pub fn get_profile(&self, name: &str) -> Option<&Vec<Binding>> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.0 == name)
+            .map(|profile| &profile.1)
+    }
+    /// Returns every controller profile.
+    pub fn get_profiles(&self) -> &Vec<(String, Vec<Binding>)> {
+        &self.profiles
+    }
+    /// Returns the bindings used for input from the device with the given
+    /// profile name. Keys always use the shared bindings.
+    fn get_bindings_for(&self, input: Option<Input>, profile: Option<&str>) -> &Vec<Binding> {
+        let is_key = matches!(input, Some(input) if !input.is_controller());
+        match profile.and_then(|name| self.get_profile(name)) {
+            Some(bindings) if !is_key => bindings,
+            _ => &self.bindings,
+        }
+    }
+    /// Returns the bindings that changes to the given profile go into. A
+    /// profile is made from the shared controller bindings the first time
+    /// it is changed.
+    fn get_mut_bindings_for(
+        &mut self,
+        input: Option<Input>,
+        profile: Option<&str>,
+    ) -> &mut Vec<Binding> {
+        let is_key = matches!(input, Some(input) if !input.is_controller());
+        let name = match profile {
+            Some(name) if !is_key => name,
+            _ => return &mut self.bindings,
+        };
+        let index = match self.profiles.iter().position(|profile| profile.0 == name) {
+            Some(index) => index,
+            None => {
+                let controller_bindings = self
+                    .bindings
+                    .iter()
+                    .filter(|binding| binding.0.is_controller())
+                    .cloned()
+                    .collect();
+                self.profiles.push((name.to_string(), controller_bindings));
+                self.profiles.len() - 1
+            }
+        };
+        &mut self.profiles[index].1
+    }
+    /// Returns the action bound to input, using the profile of the device
+    /// it came from if it has one.
+    pub fn get_action(&self, input: Input, profile: Option<&str>) -> Option<events::PlayerAction> {
+        self.get_bindings_for(Some(input), profile)
+            .iter()
+            .find(|binding| binding.0 == input)
+            .map(|binding| binding.1)
+    }
+    /// Returns every input bound to action, oldest first.
+    pub fn get_inputs(&self, action: events::PlayerAction, profile: Option<&str>) -> Vec<Input> {
+        self.get_bindings_for(None, profile)
+            .iter()
+            .filter(|binding| binding.1 == action)
+            .map(|binding| binding.0)
+            .collect()
+    }
+    /// Returns the other action input is already bound to, if binding it
+    /// to action would take it away from something else.
+    pub fn get_conflict(
+        &self,
+        input: Input,
+        action: events::PlayerAction,
+        profile: Option<&str>,
+    ) -> Option<events::PlayerAction> {
+        self.get_action(input, profile)
+            .filter(|other| *other != action)
+    }
+    /// Adds input as another input for action. Actions can have any number
+    /// of inputs, but an input only does one thing. Controller inputs with
+    /// a profile name go into that controller's profile.
+    ///
+    /// Returns the conflicting action as an error if the input is already
+    /// bound to something else. Unbind it there first.
+    pub fn bind(
+        &mut self,
+        input: Input,
+        action: events::PlayerAction,
+        profile: Option<&str>,
+    ) -> Result<(), events::PlayerAction> {
+        if let Some(other) = self.get_conflict(input, action, profile) {
+            return Err(other);
+        }
+        let bindings = self.get_mut_bindings_for(Some(input), profile);
+        if !bindings.iter().any(|binding| binding.0 == input) {
+            bindings.push((input, action));
+        }
+        Ok(())
+    }
+    /// Removes the newest input bound to action and returns it. With a
+    /// profile name the controller's profile is changed instead.
+    ///
+    /// Actions that are needed to get around menus always keep their last
+    /// input, so the player can never lock themselves out.
+    pub fn unbind(&mut self, action: events::PlayerAction, profile: Option<&str>) -> Option<Input> {
+        let bindings = self.get_mut_bindings_for(None, profile);
+        let inputs: Vec<Input> = bindings
+            .iter()
+            .filter(|binding| binding.1 == action)
+            .map(|binding| binding.0)
+            .collect();
+        if action.is_required() && inputs.len() <= 1 {
+            return None;
+        }
+        let input = *inputs.last()?;
+        bindings.retain(|binding| binding.0 != input);
+        Some(input)
+    }
+    /// Puts every binding back to its default and removes all profiles.
+    pub fn reset_bindings(&mut self) {
+        self.bindings = Settings::get_default_bindings();
+        self.profiles.clear();
+    }
+    /// Writes the settings to a file at path.
+    pub fn write(&self, path: &Path) -> Result<(), FileError> {
+        let mut lines = vec![SETTINGS_HEADER.to_string()];
+        lines.push(format!("repeat_delay {}", self.key_repeat.get_delay()));
+        lines.push(format!(
+            "repeat_interval {}",
+            self.key_repeat.get_interval()
+        ));
+        lines.push(format!(
+            "tween_duration {}",
+            self.tweening.get_duration().as_millis()
+        ));
+        lines.push(format!(
+            "tween_input {}",
+            self.tweening.get_input().get_name()
+        ));
+        for (input, action) in self.bindings.iter() {
+            lines.push(format!("bind {} {}", action.get_name(), input.to_record()));
+        }
+        // so the defaults don't come back when the file is read
+        for action in events::PlayerAction::ALL.iter() {
+            if self.get_inputs(*action, None).is_empty() {
+                lines.push(format!("unbound {}", action.get_name()));
+            }
+        }
+        for (name, bindings) in self.profiles.iter() {
+            lines.push(format!("profile {}", name));
+            for (input, action) in bindings.iter() {
+                lines.push(format!("bind {} {}", action.get_name(), input.to_record()));
+            }
+        }
+        lines.push(String::new());
+
+        fs::write(path, lines.join("\n"))?;
+        Ok(())
+    }
+    /// Reads settings from a file at path.
+    ///
+    /// Bind records after a profile record belong to that profile. Any
+    /// action the file doesn't mention outside of profiles keeps its
+    /// default inputs, so new actions still work with old files.
+    pub fn read(path: &Path) -> Result<Settings, FileError> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == SETTINGS_HEADER => {}
+            _ => return Err(FileError::on_line(1, "Not a tiny rpg settings file.")),
+        }
+
+        let mut bindings: Vec<Binding> = Vec::new();
+        let mut profiles: Vec<(String, Vec<Binding>)> = Vec::new();
+        let mut file_actions: Vec<events::PlayerAction> = Vec::new();
+        let mut delay = input::DEFAULT_REPEAT_DELAY;
+        let mut interval = input::DEFAULT_REPEAT_INTERVAL;
+        let mut tweening = Tweening::default();
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let (record, rest) = save::split_record(line);
+            match record {
+                "" => {}
+                "repeat_delay" => delay = save::parse_number(line_number, rest)?,
+                "repeat_interval" => interval = save::parse_number(line_number, rest)?,
+                "tween_duration" => {
+                    tweening =
+                        Tweening::new(save::parse_number(line_number, rest)?, tweening.get_input())
+                }
+                "tween_input" => {
+                    let input = TweenInput::from_name(rest).ok_or_else(|| {
+                        FileError::on_line(line_number, "Expected queue or fast_forward.")
+                    })?;
+                    tweening = Tweening::new(tweening.get_duration().as_millis() as u32, input);
+                }
+                "bind" => {
+                    let (action, input) = save::split_record(rest);
+                    let action = events::PlayerAction::from_name(action)
+                        .ok_or_else(|| FileError::on_line(line_number, "Unknown action."))?;
+                    let input = Input::from_record(input)
+                        .map_err(|error| FileError::on_line(line_number, error))?;
+                    match profiles.last_mut() {
+                        Some(profile) => profile.1.push((input, action)),
+                        None => {
+                            bindings.push((input, action));
+                            file_actions.push(action);
+                        }
+                    }
+                }
+                "unbound" => match events::PlayerAction::from_name(rest) {
+                    Some(action) if action.is_required() => {
+                        return Err(FileError::on_line(
+                            line_number,
+                            "Actions needed for menus always need a key.",
+                        ))
+                    }
+                    Some(action) => file_actions.push(action),
+                    None => return Err(FileError::on_line(line_number, "Unknown action.")),
+                },
+                "profile" if !rest.is_empty() => profiles.push((rest.to_string(), Vec::new())),
+                _ => return Err(FileError::on_line(line_number, "Unknown record.")),
+            }
+        }
+        for (input, action) in Settings::get_default_bindings().into_iter() {
+            let input_is_taken = bindings.iter().any(|binding| binding.0 == input);
+            if !file_actions.contains(&action) && !input_is_taken {
+                bindings.push((input, action));
+            }
+        }
+
+        let mut builder = Settings::new()
+            .set_bindings(bindings)
+            .set_key_repeat(KeyRepeat::new(delay, interval))
+            .set_tweening(tweening);
+        for (name, profile) in profiles.iter() {
+            builder = builder.add_profile(name, profile.clone());
+        }
+        builder
+            .build()
+            .map_err(|error| FileError::new(&error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maps::{Level, LevelId, Map};
+    use crate::math::{Point, Rectangle};
+
+    /// Two empty levels, starting in the top left of the first.
+    fn two_level_map() -> maps::Map {
+        let levels = (1..=2).map(|id| {
+            Level::new()
+                .id(LevelId::new(id))
+                .number(id as i32)
+                .entities(Map::get_boundry_entities(Rectangle::new(0, 0, 60, 60)))
+                .build()
+                .unwrap()
+        });
+        levels
+            .fold(Map::new(), |map, level| map.level(level))
+            .start(LevelId::new(1), Point::new(2, 2))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn warp_explores_around_the_arrival() {
+        let mut data = Data::from_map(two_level_map());
+        data.warp(LevelId::new(2), &maps::Arrival::At(Point::new(40, 40)))
+            .unwrap();
+        let level = data.get_level();
+        assert!(level.is_explored(Point::new(40, 40)));
+        assert!(!level.is_explored(Point::new(2, 2)));
+    }
+
+    /// Entities on the current level that cover point.
+    fn entities_at(data: &Data, point: Point) -> Vec<&Entity> {
+        data.get_entities()
+            .iter()
+            .filter(|entity| entity.get_rectangle().contains_point(point))
+            .collect()
+    }
+
+    #[test]
+    fn striking_a_wall_breaks_one_tile() {
+        let mut data = Data::from_map(two_level_map());
+        let mut entities = data.get_entities().clone();
+        entities.push(Entity::from_record("x=4 y=4 width=3 health=2/2 abilities=opaque").unwrap());
+        data.change_entities(entities);
+
+        assert_eq!(data.damage_tile(Point::new(5, 4), 1), Ok(false));
+        assert_eq!(
+            entities_at(&data, Point::new(5, 4))[0].get_rectangle(),
+            Rectangle::new(5, 4, 1, 1)
+        );
+        assert_eq!(
+            entities_at(&data, Point::new(5, 4))[0].get_health(),
+            Some((1, 2))
+        );
+        // the rest of the wall is split off untouched
+        assert_eq!(
+            entities_at(&data, Point::new(4, 4))[0].get_health(),
+            Some((2, 2))
+        );
+        assert_eq!(entities_at(&data, Point::new(6, 4)).len(), 1);
+
+        assert_eq!(data.damage_tile(Point::new(5, 4), 1), Ok(true));
+        assert!(entities_at(&data, Point::new(5, 4)).is_empty());
+        assert_eq!(entities_at(&data, Point::new(4, 4)).len(), 1);
+        assert!(data.damage_tile(Point::new(5, 4), 1).is_err());
+    }
+
+    #[test]
+    fn boundaries_can_not_be_broken() {
+        let mut data = Data::from_map(two_level_map());
+        let before = data.get_entities().clone();
+        assert_eq!(
+            data.damage_tile(Point::new(0, 5), 100),
+            Err("It doesn't even scratch.")
+        );
+        assert_eq!(data.get_entities(), &before);
+    }
+
+    #[test]
+    fn settings_are_kept_with_the_saves() {
+        let mut data = Data::from_map(two_level_map());
+        data.set_save_directory(Path::new("saves"));
+        assert_eq!(data.get_settings_path(), Path::new("saves/settings.txt"));
+        assert_eq!(data.get_save_path().parent(), Some(Path::new("saves")));
+    }
+
+    #[test]
+    fn failed_loads_are_logged() {
+        let mut data = Data::from_map(two_level_map());
+        data.set_save_directory(Path::new("no/such/directory"));
+        events::PlayerAction::QuickLoad.perform_action(&mut data);
+        let message = data.get_messages().get_messages().last().unwrap();
+        assert_eq!(message.get_category(), MessageCategory::System);
+        assert_eq!(message.get_color(), messages::ERROR_COLOR);
+    }
+
+    #[test]
+    fn travel_needs_a_known_way() {
+        let mut data = Data::from_map(two_level_map());
+        assert!(!data.travel_to(Point::new(50, 50)));
+        assert!(!data.is_travelling());
+        let message = data.get_messages().get_messages().last().unwrap();
+        assert_eq!(message.get_category(), MessageCategory::Travel);
+        assert_eq!(message.get_text(), "You don't know the way there.");
+        assert!(data.travel_to(Point::new(5, 2)));
+        assert_eq!(
+            data.get_travel(),
+            &vec![Point::new(3, 2), Point::new(4, 2), Point::new(5, 2)]
+        );
+    }
+
+    #[test]
+    fn travel_walks_until_any_input() {
+        let mut data = Data::from_map(two_level_map());
+        data.push_screen(Screen::Game);
+        assert!(data.travel_to(Point::new(5, 2)));
+        data.advance_clock(Duration::from_secs(1));
+        events::update_input(&mut data, Duration::ZERO);
+        assert_eq!(data.get_player().get_position(), Point::new(3, 2));
+        assert_eq!(data.get_travel().len(), 2);
+        data.get_mut_input_state()
+            .handle_event(&input::DeviceEvent::KeyDown {
+                key: Key::Q,
+                repeat: false,
+            });
+        data.advance_clock(Duration::from_secs(1));
+        events::update_input(&mut data, Duration::ZERO);
+        assert!(!data.is_travelling());
+        assert_eq!(data.get_player().get_position(), Point::new(3, 2));
+        // the input only stopped the walk
+        assert!(data.get_input_state().get_buffered().is_none());
+    }
+
+    /// Taps the key bound to moving right.
+    fn tap_right(data: &mut Data) {
+        let input_state = data.get_mut_input_state();
+        input_state.handle_event(&input::DeviceEvent::KeyDown {
+            key: Key::D,
+            repeat: false,
+        });
+        input_state.handle_event(&input::DeviceEvent::KeyUp { key: Key::D });
+    }
+
+    /// A game on the two level map that slides moves for 100ms.
+    fn tweening_game(input: TweenInput) -> Data {
+        let mut data = Data::from_map(two_level_map());
+        data.set_settings(
+            Settings::new()
+                .set_tweening(Tweening::new(100, input))
+                .build()
+                .unwrap(),
+        );
+        data.push_screen(Screen::Game);
+        data
+    }
+
+    #[test]
+    fn queued_moves_wait_for_the_slide() {
+        let mut data = tweening_game(TweenInput::Queue);
+        tap_right(&mut data);
+        events::update_input(&mut data, Duration::ZERO);
+        assert_eq!(data.get_player().get_position(), Point::new(3, 2));
+        tap_right(&mut data);
+        events::update_input(&mut data, Duration::ZERO);
+        assert_eq!(data.get_player().get_position(), Point::new(3, 2));
+        assert!(data.is_waiting_for_tween());
+        assert!(data.get_input_state().get_buffered().is_some());
+        data.advance_clock(Duration::from_millis(100));
+        events::update_input(&mut data, Duration::ZERO);
+        assert_eq!(data.get_player().get_position(), Point::new(4, 2));
+    }
+
+    #[test]
+    fn fast_forwarded_moves_go_straight_away() {
+        let mut data = tweening_game(TweenInput::FastForward);
+        tap_right(&mut data);
+        events::update_input(&mut data, Duration::ZERO);
+        tap_right(&mut data);
+        assert!(!data.is_waiting_for_tween());
+        events::update_input(&mut data, Duration::ZERO);
+        assert_eq!(data.get_player().get_position(), Point::new(4, 2));
+        assert!(data.get_input_state().get_buffered().is_none());
+        // the new slide starts where the last one was skipped to
+        let tween = data.get_player().get_tween().unwrap();
+        assert_eq!(tween.get_from().get_point(), Point::new(3, 2));
+    }
 }
@@ -0,0 +1,909 @@
+//! Seeded procedural generation of dungeon floors.
+//!
+//! A DungeonBuilder carves a grid of walls and floor with one of a few
+//! algorithms, then turns the walls into wall entities and fills the floor
+//! with enemies, items and warps to the floors above and below. The same
+//! seed and floor number always give the same Level.
+//!
+//! Every floor is carved on its own. Warps arrive at the UP_ENTRANCE or
+//! DOWN_ENTRANCE of the floor they go to, next to its stairs, which are
+//! only looked up once that floor is made. With an exit set, the first
+//! floor also gets a warp up out of the dungeon.
+//!
+//! # Planned changes:
+//!
+//! - Let spawn tables depend on the floor number.
+//! - Add doors, secret rooms and treasure rooms.
+
+use std::collections::VecDeque;
+
+use crate::entity::Entity;
+use crate::helper::{Builder, BuilderError, Random};
+use crate::maps::{Arrival, Connection, Level, LevelId, LevelType, Map};
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+
+/// The smallest width or height a dungeon can be.
+const MIN_DUNGEON_SIZE: u32 = 12;
+/// The smallest side of a room.
+const MIN_ROOM_SIZE: i32 = 3;
+/// The largest side of a room when using rooms and corridors.
+const MAX_ROOM_SIZE: i32 = 10;
+/// The smallest side of a binary space partition leaf.
+const MIN_LEAF_SIZE: i32 = 8;
+/// How likely a tile starts as a wall for caves.
+const CAVE_WALL_CHANCE: f32 = 0.45;
+/// How many times the cave rules are applied.
+const CAVE_STEPS: u32 = 5;
+/// How close to the spawn point nothing can be spawned.
+const SAFE_DISTANCE: i32 = 3;
+
+/// The entrance of a floor next to the warp up, where things coming down
+/// arrive.
+pub const UP_ENTRANCE: &str = "up";
+/// The entrance of a floor next to the warp down, where things coming up
+/// arrive.
+pub const DOWN_ENTRANCE: &str = "down";
+
+/// The ways a dungeon can be carved.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Algorithm {
+    /// Rectangular rooms placed randomly and joined by corridors.
+    RoomsAndCorridors,
+    /// Natural looking caves grown with cellular automata.
+    Caves,
+    /// The dungeon is split in half over and over with a room in every
+    /// piece, siblings joined by corridors. Gives evenly spread rooms.
+    BinarySpacePartition,
+}
+
+/// A function that creates an entity at x and y.
+pub type Spawner = fn(i32, i32) -> Entity;
+
+/// A weighted list of entities to spawn. Entities with a higher weight
+/// are picked more often.
+#[derive(Clone, Default)]
+pub struct SpawnTable {
+    entries: Vec<(u32, Spawner)>,
+}
+
+impl SpawnTable {
+    /// Creates an empty SpawnTable.
+    pub fn new() -> SpawnTable {
+        SpawnTable {
+            entries: Vec::new(),
+        }
+    }
+    /// The enemies spawned by default.
+    pub fn default_enemies() -> SpawnTable {
+        SpawnTable::new().add(1, Entity::new_slimer_entity)
+    }
+    /// The items spawned by default.
+    pub fn default_items() -> SpawnTable {
+        SpawnTable::new().add(1, Entity::new_item)
+    }
+    /// Adds a function that creates an entity at x and y with the given
+    /// weight. A weight of zero is never picked.
+    pub fn add(mut self, weight: u32, spawn: Spawner) -> Self {
+        self.entries.push((weight, spawn));
+        self
+    }
+    /// Picks an entry and creates its entity at point. Returns None if the
+    /// table is empty or every weight is zero.
+    pub fn spawn(&self, random: &mut Random, point: Point) -> Option<Entity> {
+        let total: u32 = self.entries.iter().map(|(weight, _)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = (random.next_u64() % total as u64) as u32;
+        for (weight, spawn) in self.entries.iter() {
+            if roll < *weight {
+                return Some(spawn(point.get_x(), point.get_y()));
+            }
+            roll -= weight;
+        }
+        None
+    }
+}
+
+/// Builds a floor of a dungeon. Everything has a default, so a floor can
+/// be built straight from new.
+#[derive(Clone)]
+pub struct DungeonBuilder {
+    seed: u64,
+    size: Dimension,
+    algorithm: Algorithm,
+    floor: i32,
+    floor_count: i32,
+    first_id: LevelId,
+    first_number: i32,
+    enemies: SpawnTable,
+    enemy_count: u32,
+    items: SpawnTable,
+    item_count: u32,
+    exit: Option<(LevelId, Arrival)>,
+}
+
+impl DungeonBuilder {
+    /// Sets the seed. Every floor uses its own seed made from this one.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    /// Returns the seed.
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+    /// Sets the width and height of the floor in tiles.
+    pub fn set_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Dimension::new(width, height);
+        self
+    }
+    /// Sets the algorithm used to carve the floor.
+    pub fn set_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+    /// Sets which floor to build. Floors start at 1.
+    pub fn set_floor(mut self, floor: i32) -> Self {
+        self.floor = floor;
+        self
+    }
+    /// Sets how many floors the dungeon has. Every floor but the last gets
+    /// a warp down and every floor but the first gets a warp up.
+    pub fn set_floor_count(mut self, floor_count: i32) -> Self {
+        self.floor_count = floor_count;
+        self
+    }
+    /// Sets the LevelId of the first floor. Every floor after it uses the
+    /// next id, so the ids from this one up to the floor count must be free.
+    pub fn set_first_id(mut self, first_id: LevelId) -> Self {
+        self.first_id = first_id;
+        self
+    }
+    /// Sets the level number of the first floor, every floor after it uses
+    /// the next number. add_floors moves it past numbers already used by
+    /// other game levels, so two dungeons don't share floor numbers.
+    pub fn set_first_number(mut self, first_number: i32) -> Self {
+        self.first_number = first_number;
+        self
+    }
+    /// Sets the enemies to spawn and how many.
+    pub fn set_enemies(mut self, enemies: SpawnTable, count: u32) -> Self {
+        self.enemies = enemies;
+        self.enemy_count = count;
+        self
+    }
+    /// Sets the items to spawn and how many.
+    pub fn set_items(mut self, items: SpawnTable, count: u32) -> Self {
+        self.items = items;
+        self.item_count = count;
+        self
+    }
+    /// Sets where the warp up from the first floor goes. Without an exit
+    /// the first floor has no way up.
+    pub fn set_exit(mut self, level: LevelId, arrival: Arrival) -> Self {
+        self.exit = Some((level, arrival));
+        self
+    }
+    /// Returns where the warp up from the first floor goes, if anywhere.
+    pub fn get_exit(&self) -> Option<&(LevelId, Arrival)> {
+        self.exit.as_ref()
+    }
+}
+
+impl Builder for DungeonBuilder {
+    type product = Dungeon;
+
+    /// Creates a new instance of DungeonBuilder with defaults for a single
+    /// small floor.
+    fn new() -> DungeonBuilder {
+        DungeonBuilder {
+            seed: 0,
+            size: Dimension::new(60, 40),
+            algorithm: Algorithm::RoomsAndCorridors,
+            floor: 1,
+            floor_count: 1,
+            first_id: LevelId::new(1),
+            first_number: 1,
+            enemies: SpawnTable::default_enemies(),
+            enemy_count: 6,
+            items: SpawnTable::default_items(),
+            item_count: 4,
+            exit: None,
+        }
+    }
+    /// Generates the floor.
+    ///
+    /// Returns an error if the size is too small or the floor is not
+    /// between 1 and the floor count.
+    fn build(self) -> Result<Dungeon, BuilderError> {
+        self.validate().map_err(BuilderError::new)?;
+
+        let layout = self.layout(self.floor);
+        let mut random = Random::new(self.floor_seed(self.floor).wrapping_add(1));
+        let spawn = layout.arrival_near(layout.up);
+        let mut taken = vec![spawn, layout.up, layout.down];
+        let mut entities = layout.grid.to_wall_entities();
+
+        let up = match &self.exit {
+            _ if self.floor > 1 => Some((
+                self.floor_id(self.floor - 1),
+                Arrival::Entrance(DOWN_ENTRANCE.to_string()),
+            )),
+            exit => exit.clone(),
+        };
+        if let Some((level, arrival)) = up {
+            entities.push(Entity::new_warp(
+                layout.up.get_x(),
+                layout.up.get_y(),
+                level,
+                arrival,
+            ));
+        }
+        if self.floor < self.floor_count {
+            entities.push(Entity::new_warp(
+                layout.down.get_x(),
+                layout.down.get_y(),
+                self.floor_id(self.floor + 1),
+                Arrival::Entrance(UP_ENTRANCE.to_string()),
+            ));
+        }
+
+        let mut open_tiles: Vec<Point> = layout
+            .grid
+            .get_floor_points()
+            .into_iter()
+            .filter(|point| {
+                (point.get_x() - spawn.get_x()).abs() + (point.get_y() - spawn.get_y()).abs()
+                    > SAFE_DISTANCE
+            })
+            .collect();
+        for (table, count) in [
+            (&self.enemies, self.enemy_count),
+            (&self.items, self.item_count),
+        ]
+        .iter()
+        {
+            for _ in 0..*count {
+                open_tiles.retain(|point| !taken.contains(point));
+                if open_tiles.is_empty() {
+                    break;
+                }
+                let point = open_tiles[random.range(0, open_tiles.len() as i32 - 1) as usize];
+                taken.push(point);
+                if let Some(entity) = table.spawn(&mut random, point) {
+                    entities.push(entity);
+                }
+            }
+        }
+
+        Ok(Dungeon {
+            level: Level::new()
+                .id(self.floor_id(self.floor))
+                .name(&format!("Floor {}", self.floor))
+                .level_type(LevelType::Game)
+                .number(self.floor_number(self.floor))
+                .entities(entities)
+                .entrance(UP_ENTRANCE, spawn)
+                .entrance(DOWN_ENTRANCE, layout.arrival_near(layout.down))
+                .build()?,
+            spawn,
+        })
+    }
+}
+
+impl DungeonBuilder {
+    /// Adds every floor of the dungeon to the map, along with "down" and
+    /// "up" connections between them and an "up" connection to the exit.
+    /// Floors are only generated the first time they are visited.
+    pub fn add_floors(self, map: &mut Map) -> Result<(), &'static str> {
+        self.validate()?;
+        let first_number =
+            map.get_free_numbers(LevelType::Game, self.first_number, self.floor_count);
+        let dungeon = self.set_first_number(first_number);
+        for floor in 1..=dungeon.floor_count {
+            let builder = dungeon.clone().set_floor(floor);
+            map.define_level(
+                dungeon.floor_id(floor),
+                LevelType::Game,
+                dungeon.floor_number(floor),
+                Box::new(move || builder.clone().build().unwrap().release().0),
+            )?;
+        }
+        for floor in 1..dungeon.floor_count {
+            map.connect(Connection::new(
+                "down",
+                dungeon.floor_id(floor),
+                dungeon.floor_id(floor + 1),
+                Arrival::Entrance(UP_ENTRANCE.to_string()),
+            ))?;
+            map.connect(Connection::new(
+                "up",
+                dungeon.floor_id(floor + 1),
+                dungeon.floor_id(floor),
+                Arrival::Entrance(DOWN_ENTRANCE.to_string()),
+            ))?;
+        }
+        if let Some((level, arrival)) = &dungeon.exit {
+            map.connect(Connection::new(
+                "up",
+                dungeon.floor_id(1),
+                *level,
+                arrival.clone(),
+            ))?;
+        }
+        Ok(())
+    }
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.size.get_width() < MIN_DUNGEON_SIZE || self.size.get_height() < MIN_DUNGEON_SIZE {
+            return Err("Dungeon is too small, use set_size with at least 12 by 12.");
+        }
+        if self.floor < 1 || self.floor > self.floor_count {
+            return Err(
+                "Floor must be between 1 and the floor count, use set_floor and set_floor_count.",
+            );
+        }
+        Ok(())
+    }
+    /// Returns the level number of a floor.
+    pub fn floor_number(&self, floor: i32) -> i32 {
+        self.first_number + floor - 1
+    }
+    /// Returns the LevelId of a floor.
+    pub fn floor_id(&self, floor: i32) -> LevelId {
+        LevelId::new(self.first_id.get() + floor as u32 - 1)
+    }
+    /// Gives every floor its own seed so floors can be carved on their own.
+    fn floor_seed(&self, floor: i32) -> u64 {
+        self.seed ^ (floor as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+    /// Carves a floor and picks where its warps go.
+    fn layout(&self, floor: i32) -> Layout {
+        let mut random = Random::new(self.floor_seed(floor));
+        let mut grid = Grid::new(self.size);
+        let mut rooms = Vec::new();
+
+        match self.algorithm {
+            Algorithm::RoomsAndCorridors => carve_rooms(&mut grid, &mut random, &mut rooms),
+            Algorithm::Caves => carve_caves(&mut grid, &mut random),
+            Algorithm::BinarySpacePartition => {
+                let area =
+                    Rectangle::new(1, 1, self.size.get_width() - 2, self.size.get_height() - 2);
+                carve_partition(&mut grid, &mut random, area, &mut rooms);
+            }
+        }
+
+        let up = match rooms.first() {
+            Some(room) => center(*room),
+            None => {
+                let floor_points = grid.get_floor_points();
+                if floor_points.is_empty() {
+                    let room = Rectangle::new(
+                        self.size.get_width() as i32 / 2 - 1,
+                        self.size.get_height() as i32 / 2 - 1,
+                        3,
+                        3,
+                    );
+                    grid.carve_room(room);
+                    center(room)
+                } else {
+                    floor_points[random.range(0, floor_points.len() as i32 - 1) as usize]
+                }
+            }
+        };
+        grid.fill_unreachable(up);
+        let down = grid.get_furthest_point(up);
+
+        Layout { grid, up, down }
+    }
+}
+
+/// A generated floor of a dungeon.
+pub struct Dungeon {
+    level: Level,
+    spawn: Point,
+}
+
+impl Dungeon {
+    /// Creates an instance of DungeonBuilder to generate a floor.
+    pub fn new() -> DungeonBuilder {
+        DungeonBuilder::new()
+    }
+    /// Returns a reference to the generated level.
+    pub fn get_level(&self) -> &Level {
+        &self.level
+    }
+    /// Returns where the player should start on this floor.
+    pub fn get_spawn(&self) -> Point {
+        self.spawn
+    }
+    /// Returns the generated level and spawn point, consuming the Dungeon.
+    pub fn release(self) -> (Level, Point) {
+        (self.level, self.spawn)
+    }
+}
+
+/// A carved floor along with where its warps go.
+struct Layout {
+    grid: Grid,
+    up: Point,
+    down: Point,
+}
+
+impl Layout {
+    /// Returns a floor tile next to point, or point if there is none.
+    fn arrival_near(&self, point: Point) -> Point {
+        for (dx, dy) in [(1, 0), (0, 1), (-1, 0), (0, -1)].iter() {
+            let neighbour = Point::new(point.get_x() + dx, point.get_y() + dy);
+            if !self.grid.is_wall(neighbour) && neighbour != self.down && neighbour != self.up {
+                return neighbour;
+            }
+        }
+        point
+    }
+}
+
+/// Which tiles of a floor are walls. Everything outside the grid counts as
+/// a wall.
+struct Grid {
+    size: Dimension,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    fn new(size: Dimension) -> Grid {
+        Grid {
+            size,
+            walls: vec![true; (size.get_width() * size.get_height()) as usize],
+        }
+    }
+    fn index(&self, point: Point) -> Option<usize> {
+        let (x, y) = point.get_coordinates();
+        if x < 0 || y < 0 || x >= self.size.get_width() as i32 || y >= self.size.get_height() as i32
+        {
+            return None;
+        }
+        Some(y as usize * self.size.get_width() as usize + x as usize)
+    }
+    fn is_wall(&self, point: Point) -> bool {
+        match self.index(point) {
+            Some(index) => self.walls[index],
+            None => true,
+        }
+    }
+    fn is_border(&self, point: Point) -> bool {
+        point.get_x() <= 0
+            || point.get_y() <= 0
+            || point.get_x() >= self.size.get_width() as i32 - 1
+            || point.get_y() >= self.size.get_height() as i32 - 1
+    }
+    /// Sets a tile, never touching the outer border so floors stay closed.
+    fn set_wall(&mut self, point: Point, is_wall: bool) {
+        if self.is_border(point) {
+            return;
+        }
+        if let Some(index) = self.index(point) {
+            self.walls[index] = is_wall;
+        }
+    }
+    fn carve_room(&mut self, room: Rectangle) {
+        for y in room.y_min()..=room.y_max() {
+            for x in room.x_min()..=room.x_max() {
+                self.set_wall(Point::new(x, y), false);
+            }
+        }
+    }
+    /// Carves an L shaped corridor, going horizontal or vertical first at
+    /// random.
+    fn carve_corridor(&mut self, random: &mut Random, from: Point, to: Point) {
+        let corner = if random.chance(0.5) {
+            Point::new(to.get_x(), from.get_y())
+        } else {
+            Point::new(from.get_x(), to.get_y())
+        };
+        for (start, end) in [(from, corner), (corner, to)].iter() {
+            let (x_min, x_max) = min_max(start.get_x(), end.get_x());
+            let (y_min, y_max) = min_max(start.get_y(), end.get_y());
+            for y in y_min..=y_max {
+                for x in x_min..=x_max {
+                    self.set_wall(Point::new(x, y), false);
+                }
+            }
+        }
+    }
+    fn count_wall_neighbours(&self, point: Point) -> u32 {
+        let mut count = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx != 0 || dy != 0)
+                    && self.is_wall(Point::new(point.get_x() + dx, point.get_y() + dy))
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+    fn get_floor_points(&self) -> Vec<Point> {
+        let mut points = Vec::new();
+        for y in 0..self.size.get_height() as i32 {
+            for x in 0..self.size.get_width() as i32 {
+                if !self.is_wall(Point::new(x, y)) {
+                    points.push(Point::new(x, y));
+                }
+            }
+        }
+        points
+    }
+    /// Returns how many steps it takes to reach each tile from start, or
+    /// None for tiles that can't be reached.
+    fn get_distances(&self, start: Point) -> Vec<Option<u32>> {
+        let mut distances = vec![None; self.walls.len()];
+        let mut queue = VecDeque::new();
+        if let Some(index) = self.index(start) {
+            distances[index] = Some(0);
+            queue.push_back((start, 0));
+        }
+        while let Some((point, distance)) = queue.pop_front() {
+            for (dx, dy) in [(1, 0), (0, 1), (-1, 0), (0, -1)].iter() {
+                let neighbour = Point::new(point.get_x() + dx, point.get_y() + dy);
+                if self.is_wall(neighbour) {
+                    continue;
+                }
+                if let Some(index) = self.index(neighbour) {
+                    if distances[index].is_none() {
+                        distances[index] = Some(distance + 1);
+                        queue.push_back((neighbour, distance + 1));
+                    }
+                }
+            }
+        }
+        distances
+    }
+    /// Turns every floor tile that can't be reached from start into wall.
+    /// Whatever else happened, this makes sure everything left is reachable.
+    fn fill_unreachable(&mut self, start: Point) {
+        let distances = self.get_distances(start);
+        for (index, distance) in distances.iter().enumerate() {
+            if distance.is_none() {
+                self.walls[index] = true;
+            }
+        }
+    }
+    fn get_furthest_point(&self, start: Point) -> Point {
+        let width = self.size.get_width() as usize;
+        let mut furthest = (start, 0);
+        for (index, distance) in self.get_distances(start).iter().enumerate() {
+            if let Some(distance) = distance {
+                if *distance > furthest.1 {
+                    let point = Point::new((index % width) as i32, (index / width) as i32);
+                    furthest = (point, *distance);
+                }
+            }
+        }
+        furthest.0
+    }
+    /// Turns the walls into as few wall entities as it can. Walls can be
+    /// broken, so the ones deep in the rock are kept for digging into. The
+    /// border is its own indestructible walls so nothing digs out.
+    fn to_wall_entities(&self) -> Vec<Entity> {
+        let width = self.size.get_width() as i32;
+        let height = self.size.get_height() as i32;
+        let mut needed = vec![false; self.walls.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let point = Point::new(x, y);
+                if self.is_wall(point) && !self.is_border(point) {
+                    needed[self.index(point).unwrap()] = true;
+                }
+            }
+        }
+
+        let mut entities = Vec::new();
+        for y in 0..height {
+            let mut x = 0;
+            while x < width {
+                let index = self.index(Point::new(x, y)).unwrap();
+                if !needed[index] {
+                    x += 1;
+                    continue;
+                }
+                let mut run_width = 0;
+                while x + run_width < width
+                    && needed[self.index(Point::new(x + run_width, y)).unwrap()]
+                {
+                    run_width += 1;
+                }
+                let mut run_height = 1;
+                'grow: while y + run_height < height {
+                    for dx in 0..run_width {
+                        if !needed[self.index(Point::new(x + dx, y + run_height)).unwrap()] {
+                            break 'grow;
+                        }
+                    }
+                    run_height += 1;
+                }
+                for dy in 0..run_height {
+                    for dx in 0..run_width {
+                        needed[self.index(Point::new(x + dx, y + dy)).unwrap()] = false;
+                    }
+                }
+                entities.push(Entity::new_wall(x, y, run_width as u32, run_height as u32));
+                x += run_width;
+            }
+        }
+        entities.append(&mut Map::get_boundry_entities(Rectangle::new(
+            0,
+            0,
+            self.size.get_width(),
+            self.size.get_height(),
+        )));
+        entities
+    }
+}
+
+/// Places rooms where they fit and joins each one to the room before it.
+fn carve_rooms(grid: &mut Grid, random: &mut Random, rooms: &mut Vec<Rectangle>) {
+    let width = grid.size.get_width() as i32;
+    let height = grid.size.get_height() as i32;
+    let max_rooms = (width * height / 120).max(2);
+    let max_side_x = MAX_ROOM_SIZE.min(width - 4);
+    let max_side_y = MAX_ROOM_SIZE.min(height - 4);
+
+    for _ in 0..max_rooms * 5 {
+        if rooms.len() as i32 >= max_rooms {
+            break;
+        }
+        let room_width = random.range(MIN_ROOM_SIZE, max_side_x);
+        let room_height = random.range(MIN_ROOM_SIZE, max_side_y);
+        let room = Rectangle::new(
+            random.range(2, width - room_width - 2),
+            random.range(2, height - room_height - 2),
+            room_width as u32,
+            room_height as u32,
+        );
+        let padded = Rectangle::new(
+            room.x() - 1,
+            room.y() - 1,
+            room.width() + 2,
+            room.height() + 2,
+        );
+        if rooms.iter().any(|other| other.is_colliding(padded)) {
+            continue;
+        }
+        grid.carve_room(room);
+        if let Some(previous) = rooms.last() {
+            grid.carve_corridor(random, center(*previous), center(room));
+        }
+        rooms.push(room);
+    }
+}
+
+/// Splits area in two until the pieces are too small, puts a room in every
+/// piece and joins the two halves of every split. Returns the center of a
+/// room in the area so the caller can join to it.
+fn carve_partition(
+    grid: &mut Grid,
+    random: &mut Random,
+    area: Rectangle,
+    rooms: &mut Vec<Rectangle>,
+) -> Point {
+    let width = area.width() as i32;
+    let height = area.height() as i32;
+    let can_split_x = width >= MIN_LEAF_SIZE * 2;
+    let can_split_y = height >= MIN_LEAF_SIZE * 2;
+
+    if !can_split_x && !can_split_y {
+        let room_width = random.range(MIN_ROOM_SIZE, (width - 2).max(MIN_ROOM_SIZE));
+        let room_height = random.range(MIN_ROOM_SIZE, (height - 2).max(MIN_ROOM_SIZE));
+        let room = Rectangle::new(
+            area.x() + random.range(1, (width - room_width - 1).max(1)),
+            area.y() + random.range(1, (height - room_height - 1).max(1)),
+            room_width as u32,
+            room_height as u32,
+        );
+        grid.carve_room(room);
+        rooms.push(room);
+        return center(room);
+    }
+
+    let split_x = if can_split_x && can_split_y {
+        if width == height {
+            random.chance(0.5)
+        } else {
+            width > height
+        }
+    } else {
+        can_split_x
+    };
+    let (first, second) = if split_x {
+        let split = random.range(MIN_LEAF_SIZE, width - MIN_LEAF_SIZE);
+        (
+            Rectangle::new(area.x(), area.y(), split as u32, area.height()),
+            Rectangle::new(
+                area.x() + split,
+                area.y(),
+                (width - split) as u32,
+                area.height(),
+            ),
+        )
+    } else {
+        let split = random.range(MIN_LEAF_SIZE, height - MIN_LEAF_SIZE);
+        (
+            Rectangle::new(area.x(), area.y(), area.width(), split as u32),
+            Rectangle::new(
+                area.x(),
+                area.y() + split,
+                area.width(),
+                (height - split) as u32,
+            ),
+        )
+    };
+    let first_center = carve_partition(grid, random, first, rooms);
+    let second_center = carve_partition(grid, random, second, rooms);
+    grid.carve_corridor(random, first_center, second_center);
+    first_center
+}
+
+/// Fills the grid with random walls, smooths them into caves and tunnels
+/// every smaller cave into the largest one.
+fn carve_caves(grid: &mut Grid, random: &mut Random) {
+    let width = grid.size.get_width() as i32;
+    let height = grid.size.get_height() as i32;
+    for y in 0..height {
+        for x in 0..width {
+            let is_wall = random.chance(CAVE_WALL_CHANCE);
+            grid.set_wall(Point::new(x, y), is_wall);
+        }
+    }
+    for _ in 0..CAVE_STEPS {
+        let mut next = Vec::with_capacity(grid.walls.len());
+        for y in 0..height {
+            for x in 0..width {
+                next.push(grid.count_wall_neighbours(Point::new(x, y)) >= 5);
+            }
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let point = Point::new(x, y);
+                let is_wall = next[grid.index(point).unwrap()];
+                grid.set_wall(point, is_wall);
+            }
+        }
+    }
+
+    let mut caves: Vec<Vec<Point>> = Vec::new();
+    let mut assigned = vec![false; grid.walls.len()];
+    for point in grid.get_floor_points().into_iter() {
+        if assigned[grid.index(point).unwrap()] {
+            continue;
+        }
+        let mut cave = Vec::new();
+        let width = grid.size.get_width() as usize;
+        for (index, distance) in grid.get_distances(point).iter().enumerate() {
+            if distance.is_some() {
+                assigned[index] = true;
+                cave.push(Point::new((index % width) as i32, (index / width) as i32));
+            }
+        }
+        caves.push(cave);
+    }
+    caves.sort_by_key(|cave| std::cmp::Reverse(cave.len()));
+
+    if let Some((largest, others)) = caves.split_first() {
+        for cave in others.iter() {
+            let start = cave[0];
+            let closest = largest
+                .iter()
+                .min_by_key(|point| {
+                    (point.get_x() - start.get_x()).abs() + (point.get_y() - start.get_y()).abs()
+                })
+                .unwrap();
+            grid.carve_corridor(random, start, *closest);
+        }
+    }
+}
+
+fn center(rectangle: Rectangle) -> Point {
+    Point::new(
+        rectangle.x() + rectangle.width() as i32 / 2,
+        rectangle.y() + rectangle.height() as i32 / 2,
+    )
+}
+
+fn min_max(a: i32, b: i32) -> (i32, i32) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const ALGORITHMS: [Algorithm; 3] = [
+        Algorithm::RoomsAndCorridors,
+        Algorithm::Caves,
+        Algorithm::BinarySpacePartition,
+    ];
+    const SEEDS: [u64; 4] = [0, 1, 42, 9_876_543_210];
+
+    /// The middle floor of a three floor dungeon, so it has warps both ways.
+    fn floor(algorithm: Algorithm, seed: u64) -> Dungeon {
+        Dungeon::new()
+            .set_algorithm(algorithm)
+            .set_seed(seed)
+            .set_floor_count(3)
+            .set_floor(2)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_floor() {
+        for algorithm in ALGORITHMS.iter() {
+            for seed in SEEDS.iter() {
+                let (first, second) = (floor(*algorithm, *seed), floor(*algorithm, *seed));
+                assert_eq!(first.get_spawn(), second.get_spawn());
+                assert_eq!(
+                    first.get_level().get_level_entities(),
+                    second.get_level().get_level_entities()
+                );
+                assert_eq!(
+                    first.get_level().get_entrances(),
+                    second.get_level().get_entrances()
+                );
+            }
+        }
+        let (first, second) = (floor(Algorithm::Caves, 1), floor(Algorithm::Caves, 2));
+        assert_ne!(
+            first.get_level().get_level_entities(),
+            second.get_level().get_level_entities()
+        );
+    }
+
+    #[test]
+    fn every_open_tile_can_be_reached() {
+        for algorithm in ALGORITHMS.iter() {
+            for seed in SEEDS.iter() {
+                let dungeon = floor(*algorithm, *seed);
+                let level = dungeon.get_level();
+                let walls: HashSet<Point> = level
+                    .get_level_entities()
+                    .iter()
+                    .filter(|entity| entity.get_abilities().is_opaque())
+                    .flat_map(|entity| entity.get_rectangle().get_points())
+                    .collect();
+                let open: HashSet<Point> = Rectangle::new(0, 0, 60, 40)
+                    .get_points()
+                    .filter(|point| !walls.contains(point))
+                    .collect();
+
+                let mut reached = HashSet::new();
+                let mut queue = VecDeque::new();
+                queue.push_back(dungeon.get_spawn());
+                while let Some(point) = queue.pop_front() {
+                    if !open.contains(&point) || !reached.insert(point) {
+                        continue;
+                    }
+                    for (dx, dy) in [(1, 0), (0, 1), (-1, 0), (0, -1)].iter() {
+                        queue.push_back(Point::new(point.get_x() + dx, point.get_y() + dy));
+                    }
+                }
+
+                assert_eq!(reached, open, "{:?} with seed {}", algorithm, seed);
+                for entity in level.get_level_entities().iter() {
+                    if !entity.get_abilities().is_opaque() {
+                        assert!(reached.contains(&entity.get_position()));
+                    }
+                }
+                for (_, entrance) in level.get_entrances().iter() {
+                    assert!(reached.contains(entrance));
+                }
+            }
+        }
+    }
+}
@@ -14,10 +14,24 @@
 //! - Find system for abilities with counters
 //! - Create LevelPoint of some kind or Rc and / or RefCell
 
+use std::time::Duration;
+
+use crate::animation::{AnimationState, Clip};
 use crate::data::Data;
+use crate::fov;
 use crate::helper::{Builder, BuilderError};
-use crate::maps::Level;
+use crate::maps::{Arrival, LevelId};
 use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+use crate::messages::MessageCategory;
+use crate::screens::MenuOption;
+use crate::tween::Tween;
+
+/// How much health the player starts with.
+pub const PLAYER_HEALTH: u32 = 10;
+/// How much health a slimer starts with.
+pub const SLIMER_HEALTH: u32 = 4;
+/// How many hits each tile of a wall takes to break.
+pub const WALL_DURABILITY: u32 = 3;
 
 /// Holds any currencies in the game. For your own games, it
 /// wouldn't be a bad idea to create your own struct if there
@@ -34,8 +48,22 @@ struct Inventory {}
 /// strength, etc. along with currencies, may become a trait,
 /// That other things take T: impl Stats to allow users to
 /// Create their own.
-#[derive(Debug, Clone, PartialEq)]
-struct Stats {}
+///
+/// Health is the current and most health. Entities without it can't be
+/// hurt. For walls it is their durability.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Stats {
+    health: Option<(u32, u32)>,
+}
+
+impl Stats {
+    /// Creates stats with full health.
+    fn with_health(health: u32) -> Stats {
+        Stats {
+            health: Some((health, health)),
+        }
+    }
+}
 
 /// A structure that holds a bunch of abilities that entities can
 /// perform. These abilities need to be checked for somewhere. Again
@@ -61,11 +89,20 @@ impl Abilities {
     /// Checks if the Ability Warp exists and if so returns
     /// The data of where something is supposed to teleport.
     /// Else returns None.
-    // might convert is warp to i32 instead of level
-    pub fn is_warp(&self) -> Option<(Level, Point)> {
+    pub fn is_warp(&self) -> Option<(LevelId, Arrival)> {
+        for owned_ability in self.0.iter() {
+            if let Ability::Warp(l, a) = owned_ability {
+                return Some((*l, a.clone()));
+            }
+        }
+        None
+    }
+    /// Returns the menu option of the entity if it has one. Walking into
+    /// an entity with a menu option chooses it.
+    pub fn get_menu_option(&self) -> Option<MenuOption> {
         for owned_ability in self.0.iter() {
-            if let Ability::Warp(l, p) = owned_ability.clone() {
-                return Some((l, p));
+            if let Ability::Menu(option) = owned_ability {
+                return Some(*option);
             }
         }
         None
@@ -92,6 +129,14 @@ impl Abilities {
     pub fn can_not_move(&self) -> bool {
         !self.can_move()
     }
+    /// Checks if Ability list includes being opaque.
+    pub fn is_opaque(&self) -> bool {
+        self.check_for_ability(&Ability::Opaque)
+    }
+    /// Checks if Ability list includes being indestructible.
+    pub fn is_indestructible(&self) -> bool {
+        self.check_for_ability(&Ability::Indestructible)
+    }
     /// Add an ability to the list of Abilities.
     pub fn add_ability(&mut self, ability: Ability) {
         if !self.check_for_ability(&ability) {
@@ -122,14 +167,76 @@ pub enum Ability {
     /// Can the Entity speak? Should be for things such as communicating
     /// with the player.
     Speak,
-    /// Interacting with this entity warps targets to another place.
-    Warp(Level, Point),
+    /// Interacting with this entity warps targets to a point or entrance on
+    /// another level.
+    Warp(LevelId, Arrival),
     /// You do not go through the object when interacting.
     Physical,
     /// Can clone itself
     Clone,
     /// Lasts u32 turns.
     Temporary(u32),
+    /// Blocks the sight of anything looking through it. Walls are opaque.
+    Opaque,
+    /// Walking into this entity chooses the menu option. Used to build menu
+    /// levels.
+    Menu(MenuOption),
+    /// Can never be damaged, even with health. The edges of levels are
+    /// indestructible so nothing can leave them.
+    Indestructible,
+}
+
+impl Ability {
+    /// Writes the ability as a word for saves and level files.
+    pub fn to_record(&self) -> String {
+        match self {
+            Ability::Move => "move".to_string(),
+            Ability::Speak => "speak".to_string(),
+            Ability::Warp(level, Arrival::At(point)) => {
+                format!("warp:{}:{}:{}", level, point.get_x(), point.get_y())
+            }
+            Ability::Warp(level, Arrival::Entrance(name)) => format!("warp:{}:{}", level, name),
+            Ability::Physical => "physical".to_string(),
+            Ability::Clone => "clone".to_string(),
+            Ability::Temporary(turns) => format!("temporary:{}", turns),
+            Ability::Opaque => "opaque".to_string(),
+            Ability::Menu(option) => format!("menu:{}", option.get_name()),
+            Ability::Indestructible => "indestructible".to_string(),
+        }
+    }
+    /// Reads an ability written by to_record.
+    pub fn from_record(record: &str) -> Result<Ability, &'static str> {
+        let parts: Vec<&str> = record.split(':').collect();
+        match parts.as_slice() {
+            ["move"] => Ok(Ability::Move),
+            ["speak"] => Ok(Ability::Speak),
+            ["warp", level, x, y] => Ok(Ability::Warp(
+                LevelId::new(level.parse().map_err(|_| "Warp level is not a number.")?),
+                Arrival::At(Point::new(
+                    x.parse().map_err(|_| "Warp x is not a number.")?,
+                    y.parse().map_err(|_| "Warp y is not a number.")?,
+                )),
+            )),
+            ["warp", level, entrance] if !entrance.is_empty() => Ok(Ability::Warp(
+                LevelId::new(level.parse().map_err(|_| "Warp level is not a number.")?),
+                Arrival::Entrance(entrance.to_string()),
+            )),
+            ["physical"] => Ok(Ability::Physical),
+            ["clone"] => Ok(Ability::Clone),
+            ["temporary", turns] => Ok(Ability::Temporary(
+                turns
+                    .parse()
+                    .map_err(|_| "Temporary turns is not a number.")?,
+            )),
+            ["opaque"] => Ok(Ability::Opaque),
+            ["indestructible"] => Ok(Ability::Indestructible),
+            ["menu", option @ ..] => match MenuOption::from_name(&option.join(":")) {
+                Some(option) => Ok(Ability::Menu(option)),
+                None => Err("Unknown menu option."),
+            },
+            _ => Err("Unknown ability."),
+        }
+    }
 }
 
 /// Sets alignment, used with Option for neutral. Can
@@ -147,13 +254,26 @@ enum Alignment {
 }
 
 /// The possible skins in the game, again could be a trait.
-/// For now only has RGB with plans for RGBA very soon.
-// future includes rgba and images
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// Sprites, tiled and animated skins name an atlas loaded by assets::Assets.
+/// If the atlas or frame is missing they are drawn as a magenta rectangle.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Skin {
     /// RGB rectangle. uses a tuple of (r, g, b) to create
     /// rectangles with colour.
     RGB(u8, u8, u8),
+    /// RGBA rectangle. The same as RGB, but a is how see through it is,
+    /// 0 being invisible and 255 solid.
+    RGBA(u8, u8, u8, u8),
+    /// A frame of an atlas by the atlas name and frame index, stretched over
+    /// the whole entity.
+    Sprite(String, u32),
+    /// A frame of an atlas by the atlas name and frame index, repeated on
+    /// every tile of the entity. Best for big entities like walls.
+    Tiled(String, u32),
+    /// The clips of an atlas, stretched over the whole entity. Which frame
+    /// shows depends on the clip the entity is playing, see animation.
+    Animated(String),
 }
 
 /// Implementations of Skin related to rgb and rgba.
@@ -162,17 +282,70 @@ impl Skin {
     pub fn get_rgb(&self) -> Result<Skin, &'static str> {
         match self {
             Skin::RGB(r, g, b) => Ok(Skin::RGB(*r, *g, *b)),
-            // _ => Err("Skin enum value not an RGB value."),
+            _ => Err("Skin enum value not an RGB value."),
+        }
+    }
+    /// Writes the skin as a word for saves and level files.
+    pub fn to_record(&self) -> String {
+        match self {
+            Skin::RGB(r, g, b) => format!("rgb:{},{},{}", r, g, b),
+            Skin::RGBA(r, g, b, a) => format!("rgba:{},{},{},{}", r, g, b, a),
+            Skin::Sprite(atlas, frame) => format!("sprite:{}:{}", atlas, frame),
+            Skin::Tiled(atlas, frame) => format!("tiled:{}:{}", atlas, frame),
+            Skin::Animated(atlas) => format!("animated:{}", atlas),
+        }
+    }
+    /// Reads a skin written by to_record.
+    pub fn from_record(record: &str) -> Result<Skin, &'static str> {
+        let parts: Vec<&str> = record.split(':').collect();
+        match parts.as_slice() {
+            ["rgb", colors] => match parse_colors(colors)?.as_slice() {
+                [r, g, b] => Ok(Skin::RGB(*r, *g, *b)),
+                _ => Err("An rgb skin needs three colours."),
+            },
+            ["rgba", colors] => match parse_colors(colors)?.as_slice() {
+                [r, g, b, a] => Ok(Skin::RGBA(*r, *g, *b, *a)),
+                _ => Err("An rgba skin needs four colours."),
+            },
+            ["sprite", atlas, frame] if !atlas.is_empty() => Ok(Skin::Sprite(
+                atlas.to_string(),
+                frame.parse().map_err(|_| "Sprite frame is not a number.")?,
+            )),
+            ["tiled", atlas, frame] if !atlas.is_empty() => Ok(Skin::Tiled(
+                atlas.to_string(),
+                frame.parse().map_err(|_| "Tiled frame is not a number.")?,
+            )),
+            ["animated", atlas] if !atlas.is_empty() => Ok(Skin::Animated(atlas.to_string())),
+            _ => Err("Unknown skin."),
         }
     }
     /// Just gets the pure values from the rgb as a tuple instead of surrounding
-    /// it in a skin like get_rgb. Again, results in an error if not rgb.
+    /// it in a skin like get_rgb. RGBA gives its colour without the alpha.
+    /// Results in an error for sprites.
     pub fn get_rgb_colors(&self) -> Result<(u8, u8, u8), &'static str> {
         match self {
-            Skin::RGB(r, g, b) => Ok((*r, *g, *b)),
-            // _ => Err("Skin enum value not an RGB value."),
+            Skin::RGB(r, g, b) | Skin::RGBA(r, g, b, _) => Ok((*r, *g, *b)),
+            _ => Err("Skin enum value not an RGB value."),
         }
     }
+    /// Gets the colour with alpha. RGB is fully solid. Results in an error
+    /// for sprites.
+    pub fn get_rgba_colors(&self) -> Result<(u8, u8, u8, u8), &'static str> {
+        match self {
+            Skin::RGB(r, g, b) => Ok((*r, *g, *b, 255)),
+            Skin::RGBA(r, g, b, a) => Ok((*r, *g, *b, *a)),
+            _ => Err("Skin enum value not an RGB value."),
+        }
+    }
+}
+
+/// Reads colours like 255,0,0 from a skin record.
+fn parse_colors(colors: &str) -> Result<Vec<u8>, &'static str> {
+    colors
+        .split(',')
+        .map(|color| color.parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| "Skin colour is not a number from 0 to 255.")
 }
 
 /// Builds an instance of Entity. Requires a position to be set, in order
@@ -202,6 +375,11 @@ impl EntityBuilder {
         self.position = Some(Point::new(x, y));
         self
     }
+    /// Gives the entity full health, so it can be hurt.
+    pub fn set_health(mut self, health: u32) -> Self {
+        self.stats = Stats::with_health(health);
+        self
+    }
     /// Set the width and height of an entity by a Dimension.
     pub fn set_dimension(mut self, dimension: Dimension) -> Self {
         self.size = dimension;
@@ -212,6 +390,11 @@ impl EntityBuilder {
         self.size = Dimension::new(width, height);
         self
     }
+    /// Set the skin the entity is drawn with.
+    pub fn set_skin(mut self, skin: Skin) -> Self {
+        self.skin = skin;
+        self
+    }
     /// Give the entity builder a vector of abilities to have.
     pub fn set_abilities(mut self, abilities: Vec<Ability>) -> Self {
         for ability in abilities.into_iter() {
@@ -232,7 +415,7 @@ impl Builder for EntityBuilder {
             size: Dimension::new(1, 1),
             skin: Skin::RGB(255, 255, 0),
             inventory: None,
-            stats: Stats {},
+            stats: Stats::default(),
             abilities: Abilities(Vec::new()),
             alignment: None,
         }
@@ -257,6 +440,8 @@ impl Builder for EntityBuilder {
             stats: self.stats,
             abilities: self.abilities,
             alignment: self.alignment,
+            animation: AnimationState::new(),
+            tween: None,
         })
     }
 }
@@ -272,6 +457,8 @@ pub struct Entity {
     abilities: Abilities,
     inventory: Option<Inventory>,
     alignment: Option<Alignment>,
+    animation: AnimationState,
+    tween: Option<Tween>,
 }
 
 /// Implementations for Entity that create standard default entities, such as,
@@ -285,31 +472,97 @@ impl Entity {
             placement: Rectangle::new(x, y, 1, 1),
             skin: Skin::RGB(255, 0, 0),
             inventory: None,
-            stats: Stats {},
+            stats: Stats::with_health(PLAYER_HEALTH),
             abilities: Abilities(Vec::new()),
             alignment: Some(Alignment::Good),
+            animation: AnimationState::new(),
+            tween: None,
         }
     }
-    /// Creates an evil slimer entity.
+    /// Creates an evil slimer entity. It can move, so it wanders and chases
+    /// the player once it sees them, see perform_turn.
     pub fn new_slimer_entity(x: i32, y: i32) -> Entity {
         Entity {
             placement: Rectangle::new(x, y, 1, 1),
             skin: Skin::RGB(30, 215, 30),
             inventory: None,
-            stats: Stats {},
-            abilities: Abilities(Vec::new()),
+            stats: Stats::with_health(SLIMER_HEALTH),
+            abilities: Abilities(vec![Ability::Move]),
             alignment: Some(Alignment::Evil),
+            animation: AnimationState::new(),
+            tween: None,
+        }
+    }
+    /// Creates a warp that sends whatever interacts with it to a point or
+    /// entrance on the given level.
+    pub fn new_warp(x: i32, y: i32, level: LevelId, arrival: Arrival) -> Entity {
+        Entity {
+            placement: Rectangle::new(x, y, 1, 1),
+            skin: Skin::RGB(140, 60, 220),
+            inventory: None,
+            stats: Stats::default(),
+            abilities: Abilities(vec![Ability::Warp(level, arrival)]),
+            alignment: None,
+            animation: AnimationState::new(),
+            tween: None,
+        }
+    }
+    /// Creates an item lying on the ground. WIP items can not be picked up
+    /// until inventories are filled in.
+    pub fn new_item(x: i32, y: i32) -> Entity {
+        Entity {
+            placement: Rectangle::new(x, y, 1, 1),
+            skin: Skin::RGB(230, 190, 40),
+            inventory: None,
+            stats: Stats::default(),
+            abilities: Abilities(Vec::new()),
+            alignment: None,
+            animation: AnimationState::new(),
+            tween: None,
+        }
+    }
+    /// Creates a menu option of the given size. Its colour comes from the
+    /// option.
+    pub fn new_menu_option(x: i32, y: i32, width: u32, height: u32, option: MenuOption) -> Entity {
+        Entity {
+            placement: Rectangle::new(x, y, width, height),
+            skin: option.get_skin(),
+            inventory: None,
+            stats: Stats::default(),
+            abilities: Abilities(vec![Ability::Menu(option)]),
+            alignment: None,
+            animation: AnimationState::new(),
+            tween: None,
         }
     }
     /// Creates a wall where you specify height, width and position.
+    ///
+    /// Each tile of the wall can take WALL_DURABILITY hits before it
+    /// breaks.
     pub fn new_wall(x: i32, y: i32, height: u32, width: u32) -> Entity {
         Entity {
             placement: Rectangle::new(x, y, height, width),
             skin: Skin::RGB(90, 90, 90),
             inventory: None,
-            stats: Stats {},
-            abilities: Abilities(Vec::new()),
+            stats: Stats::with_health(WALL_DURABILITY),
+            abilities: Abilities(vec![Ability::Opaque]),
+            alignment: None,
+            animation: AnimationState::new(),
+            tween: None,
+        }
+    }
+    /// Creates a wall that can't be broken, for the edges of levels. A bit
+    /// darker so the player can tell.
+    pub fn new_indestructible_wall(x: i32, y: i32, width: u32, height: u32) -> Entity {
+        Entity {
+            placement: Rectangle::new(x, y, width, height),
+            skin: Skin::RGB(60, 60, 65),
+            inventory: None,
+            stats: Stats::default(),
+            abilities: Abilities(vec![Ability::Opaque, Ability::Indestructible]),
             alignment: None,
+            animation: AnimationState::new(),
+            tween: None,
         }
     }
 }
@@ -342,22 +595,37 @@ impl Entity {
     /// May be removed in future versions.
     // move will soon be based on stats and validated with amount
     pub fn move_left(&mut self) {
-        self.placement.move_x(-1);
+        self.step_to(self.get_move_left());
     }
     /// Moves the entity one right. Should usually be used with get_move_right first.
     /// May be removed in future versions.
     pub fn move_right(&mut self) {
-        self.placement.move_x(1);
+        self.step_to(self.get_move_right());
     }
     /// Moves the entity one up. Should usually be used with get_move_up first.
     /// May be removed in future versions.
     pub fn move_up(&mut self) {
-        self.placement.move_y(-1);
+        self.step_to(self.get_move_up());
     }
     /// Moves the entity one down. Should usually be used with get_move_down first.
     /// May be removed in future versions.
     pub fn move_down(&mut self) {
-        self.placement.move_y(1);
+        self.step_to(self.get_move_down());
+    }
+    /// Puts the entity on new_rectangle, sliding there from where it was
+    /// and playing the walk clip for the way it went. Moving twice in one
+    /// turn slides all the way from where it was at the start of the turn.
+    fn step_to(&mut self, new_rectangle: Rectangle) {
+        let from = match self.tween {
+            Some(tween) if tween.is_pending() => tween.get_from(),
+            _ => self.placement,
+        };
+        self.tween = Some(Tween::new(from));
+        self.animation.play(Clip::walk(
+            new_rectangle.x() - self.placement.x(),
+            new_rectangle.y() - self.placement.y(),
+        ));
+        self.placement = new_rectangle;
     }
 }
 
@@ -370,6 +638,17 @@ impl Entity {
     pub fn get_position(&self) -> Point {
         self.placement.get_point()
     }
+    /// Places the entity at a new position without checking what is there.
+    /// It jumps there rather than sliding.
+    pub fn set_position(&mut self, position: Point) {
+        self.tween = None;
+        self.placement = Rectangle::new(
+            position.get_x(),
+            position.get_y(),
+            self.placement.width(),
+            self.placement.height(),
+        );
+    }
     /// Returns the size of the entity.
     pub fn get_size(&self) -> Dimension {
         self.placement.get_dimension()
@@ -378,38 +657,294 @@ impl Entity {
     pub fn get_rectangle(&self) -> Rectangle {
         self.placement
     }
-    /// Returns the skin of the entity.
-    pub fn get_skin(&self) -> Skin {
-        self.skin
+    /// Returns a reference to the skin of the entity.
+    pub fn get_skin(&self) -> &Skin {
+        &self.skin
     }
     /// Returns a reference to the abilities of an entity.
     pub fn get_abilities(&self) -> &Abilities {
         &self.abilities
     }
+    /// Returns the current and most health of the entity, if it can be
+    /// hurt.
+    pub fn get_health(&self) -> Option<(u32, u32)> {
+        self.stats.health
+    }
+    /// Returns true if the entity can be damaged, it has health and isn't
+    /// indestructible.
+    pub fn is_destructible(&self) -> bool {
+        self.stats.health.is_some() && !self.abilities.is_indestructible()
+    }
+    /// Takes damage off the entity's health. Returns true if that broke or
+    /// killed it. Entities that can't be damaged are left alone.
+    pub fn take_damage(&mut self, damage: u32) -> bool {
+        if !self.is_destructible() {
+            return false;
+        }
+        match self.stats.health.as_mut() {
+            Some((health, _)) => {
+                *health = health.saturating_sub(damage);
+                *health == 0
+            }
+            None => false,
+        }
+    }
+    /// Shrinks the entity down to the tile at point and returns the rest of
+    /// it as copies, with the same health. Used so only one tile of a long
+    /// wall is damaged. Returns nothing if point isn't on the entity.
+    pub fn split_off(&mut self, point: Point) -> Vec<Entity> {
+        let tile = Rectangle::new(point.get_x(), point.get_y(), 1, 1);
+        if self.placement.is_not_colliding(tile) {
+            return Vec::new();
+        }
+        let rest = self
+            .placement
+            .subtract(tile)
+            .into_iter()
+            .map(|placement| Entity {
+                placement,
+                tween: None,
+                ..self.clone()
+            })
+            .collect();
+        self.placement = tile;
+        self.tween = None;
+        rest
+    }
+    /// Returns a short description of the entity for the player, like
+    /// "A hostile creature, 3/4 health." Entities don't have names yet, so
+    /// it is worked out from what they can do.
+    pub fn get_description(&self) -> String {
+        let what = if self.abilities.is_warp().is_some() {
+            "A way to somewhere else".to_string()
+        } else if let Some(option) = self.abilities.get_menu_option() {
+            option.get_label()
+        } else {
+            match (&self.alignment, self.abilities.can_move()) {
+                (Some(Alignment::Evil), _) => "A hostile creature".to_string(),
+                (Some(Alignment::Good), _) => "A friendly creature".to_string(),
+                (None, true) => "A creature".to_string(),
+                (None, false) if self.abilities.is_indestructible() => {
+                    "A wall that can't be broken".to_string()
+                }
+                (None, false) if self.abilities.is_opaque() => "A wall".to_string(),
+                (None, false) => "Something lying here".to_string(),
+            }
+        };
+        match self.stats.health {
+            Some((health, most)) if self.abilities.can_not_move() => {
+                format!("{}, {}/{} durability.", what, health, most)
+            }
+            Some((health, most)) => format!("{}, {}/{} health.", what, health, most),
+            None => format!("{}.", what),
+        }
+    }
+    /// Returns the clip the entity is playing and when it started.
+    pub fn get_animation(&self) -> &AnimationState {
+        &self.animation
+    }
+    /// Plays a clip from the start. Moving plays the walk clips by itself,
+    /// this is for everything else, like attacks and deaths.
+    pub fn play_clip(&mut self, clip: Clip) {
+        self.animation.play(clip);
+    }
+    /// Stamps a clip that was just played with the render clock.
+    pub fn start_clip(&mut self, clock: Duration) {
+        self.animation.start(clock);
+    }
+    /// Returns the slide from the entity's last move, if it had one.
+    pub fn get_tween(&self) -> Option<&Tween> {
+        self.tween.as_ref()
+    }
+    /// Stamps a slide from a move this turn with the render clock. A
+    /// duration of 0 puts the entity straight on its tile.
+    pub fn start_tween(&mut self, clock: Duration, duration: Duration) {
+        match self.tween.as_mut() {
+            Some(_) if duration.as_nanos() == 0 => self.tween = None,
+            Some(tween) => tween.start(clock, duration),
+            None => {}
+        }
+    }
+    /// Jumps to the end of any slide.
+    pub fn finish_tween(&mut self) {
+        self.tween = None;
+    }
+    /// Returns true if the entity is still sliding at clock.
+    pub fn is_tweening(&self, clock: Duration) -> bool {
+        matches!(self.tween, Some(tween) if !tween.is_done(clock))
+    }
+    /// Returns where the top left of the entity is drawn at clock, in
+    /// tiles. Between tiles while it slides.
+    pub fn get_drawn_position(&self, clock: Duration) -> (f32, f32) {
+        match self.tween {
+            Some(tween) => tween.get_position(self.placement, clock),
+            None => (self.placement.x() as f32, self.placement.y() as f32),
+        }
+    }
+}
+
+/// Implementations for writing an entity as a single line of text and reading it back.
+/// Used by saves and level files.
+///
+/// A record is a list of key=value words, such as
+/// `x=3 y=4 width=1 height=1 skin=rgb:255,0,0 health=4/4 abilities=move alignment=evil`.
+/// Anything left out uses the same defaults as EntityBuilder.
+impl Entity {
+    /// Writes the entity as a record.
+    pub fn to_record(&self) -> String {
+        let mut words = vec![
+            format!("x={}", self.placement.x()),
+            format!("y={}", self.placement.y()),
+            format!("width={}", self.placement.width()),
+            format!("height={}", self.placement.height()),
+            format!("skin={}", self.skin.to_record()),
+        ];
+        if let Some((health, most)) = self.stats.health {
+            words.push(format!("health={}/{}", health, most));
+        }
+        if !self.abilities.0.is_empty() {
+            let abilities: Vec<String> = self.abilities.0.iter().map(|a| a.to_record()).collect();
+            words.push(format!("abilities={}", abilities.join(",")));
+        }
+        match self.alignment {
+            Some(Alignment::Good) => words.push("alignment=good".to_string()),
+            Some(Alignment::Evil) => words.push("alignment=evil".to_string()),
+            None => {}
+        }
+        words.join(" ")
+    }
+    /// Reads an entity written by to_record. The position is required.
+    pub fn from_record(record: &str) -> Result<Entity, &'static str> {
+        let mut builder = EntityBuilder::new();
+        let mut x = None;
+        let mut y = None;
+        for word in record.split_whitespace() {
+            let mut pair = word.splitn(2, '=');
+            let (key, value) = match (pair.next(), pair.next()) {
+                (Some(key), Some(value)) => (key, value),
+                _ => return Err("Entity records are made of key=value words."),
+            };
+            match key {
+                "x" => x = Some(value.parse().map_err(|_| "Entity x is not a number.")?),
+                "y" => y = Some(value.parse().map_err(|_| "Entity y is not a number.")?),
+                "width" => builder
+                    .size
+                    .set_width(value.parse().map_err(|_| "Entity width is not a number.")?),
+                "height" => builder.size.set_height(
+                    value
+                        .parse()
+                        .map_err(|_| "Entity height is not a number.")?,
+                ),
+                "skin" => builder.skin = Skin::from_record(value)?,
+                "health" => {
+                    let error = "Entity health is not like 3/10.";
+                    let (health, most) = value.split_once('/').ok_or(error)?;
+                    let (health, most) = (
+                        health.parse().map_err(|_| error)?,
+                        most.parse().map_err(|_| error)?,
+                    );
+                    if health > most {
+                        return Err("Entity health is above its most health.");
+                    }
+                    builder.stats.health = Some((health, most));
+                }
+                "abilities" => {
+                    for ability in value.split(',') {
+                        builder
+                            .abilities
+                            .add_ability(Ability::from_record(ability)?);
+                    }
+                }
+                "alignment" => {
+                    builder.alignment = match value {
+                        "good" => Some(Alignment::Good),
+                        "evil" => Some(Alignment::Evil),
+                        "none" => None,
+                        _ => return Err("Unknown alignment."),
+                    }
+                }
+                _ => return Err("Unknown entity key."),
+            }
+        }
+        match (x, y) {
+            (Some(x), Some(y)) => builder
+                .set_xy(x, y)
+                .build()
+                .map_err(|_| "Could not build entity."),
+            _ => Err("Entity records need an x and y."),
+        }
+    }
 }
 
 /// AI logic here. Should only need to call perform_turn for the object to do what it thinks is best. Almost
 /// certainly will become a trait.
 impl Entity {
     /// The entity performs an action based on its environment.
+    ///
+    /// Evil entities chase the player when they can see them, otherwise
+    /// entities that can move wander around.
     pub fn perform_turn(&mut self, data: &mut Data) {
-        if self.abilities.can_move() {
-            let new_position = match data
-                .get_randomness_with(self.get_rectangle().x() * self.get_rectangle().y())
-            {
+        if self.abilities.can_not_move() {
+            return;
+        }
+        if self.alignment == Some(Alignment::Evil) {
+            let target = data.get_player().get_position();
+            if data.can_see(self.get_position(), target, fov::DEFAULT_SIGHT_RADIUS) {
+                self.chase(data, target);
+                return;
+            }
+        }
+        let new_position =
+            match data.get_randomness_with(self.get_rectangle().x() * self.get_rectangle().y()) {
                 v if v > 0.75 => self.get_move_right(),
                 v if v > 0.5 => self.get_move_left(),
                 v if v > 0.25 => self.get_move_up(),
                 _v => self.get_move_down(),
             };
-            self.move_direction(data, new_position);
+        self.move_direction(data, new_position);
+    }
+    /// Takes a step towards target along the axis that is furthest away,
+    /// trying the other axis if the first is blocked.
+    fn chase(&mut self, data: &mut Data, target: Point) {
+        let dx = target.get_x() - self.get_position().get_x();
+        let dy = target.get_y() - self.get_position().get_y();
+        let horizontal = match dx {
+            v if v > 0 => Some(self.get_move_right()),
+            v if v < 0 => Some(self.get_move_left()),
+            _ => None,
+        };
+        let vertical = match dy {
+            v if v > 0 => Some(self.get_move_down()),
+            v if v < 0 => Some(self.get_move_up()),
+            _ => None,
+        };
+        let steps = if dx.abs() >= dy.abs() {
+            [horizontal, vertical]
+        } else {
+            [vertical, horizontal]
+        };
+        for step in steps.iter().flatten() {
+            if self.move_direction(data, *step) {
+                return;
+            }
+        }
+        // blocked by the player, so it lashes out. Only looks like an
+        // attack until there is combat.
+        let player = data.get_player().get_rectangle();
+        if steps.iter().flatten().any(|step| step.is_colliding(player)) {
+            self.play_clip(Clip::Attack);
+            data.add_message(MessageCategory::Combat, "Something lashes out at you.");
         }
     }
-    fn move_direction(&mut self, data: &mut Data, new_rectangle: Rectangle) {
+    fn move_direction(&mut self, data: &mut Data, new_rectangle: Rectangle) -> bool {
         match data.check_position_both(new_rectangle) {
-            Some(_e) => {}
-            None => self.placement = new_rectangle,
-        };
+            Some(_e) => false,
+            None if data.get_move_cost(new_rectangle).is_none() => false,
+            None => {
+                self.step_to(new_rectangle);
+                true
+            }
+        }
     }
 }
 
@@ -0,0 +1,578 @@
+//! The options the game starts with, from the command line and config file.
+//!
+//! Every option has a default, which a line in the config file can change,
+//! which a command line argument can change again. Both are read by name
+//! through EnvironmentBuilder::set_option. Nothing here needs SDL, start.rs
+//! turns the Environment into a window.
+//!
+//! # Planned changes:
+//!
+//! - Write the config file back out from the settings screen.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sdl")]
+use sdl2::video::FullscreenType;
+
+use crate::helper::{Builder, BuilderError, FileError};
+use crate::hud::Hud;
+use crate::math;
+use crate::save;
+
+/// Where the config file is looked for when --config isn't given. It is
+/// fine for it to not exist.
+pub const CONFIG_PATH: &str = "config.txt";
+
+/// Printed for --help.
+pub const HELP: &str = "\
+Usage: tiny_rpg [OPTIONS] [CAMPAIGN]
+
+Options:
+  -h, --help              Print this and exit.
+      --config FILE       Read options from FILE instead of config.txt.
+      --resolution WxH    Size of the window in pixels, like 960x640.
+      --tile-size PIXELS  Size of a tile in pixels.
+      --fullscreen        Start in fullscreen.
+      --windowed          Start in a window.
+      --fullscreen-mode MODE
+                          borderless or exclusive, used by --fullscreen
+                          and F11.
+      --scaling MODE      fixed keeps tiles the same size as the window
+                          grows, integer grows them by whole multiples.
+      --vsync             Wait for the screen before showing a frame.
+      --no-vsync          Show frames as soon as they are drawn.
+      --campaign DIR      The campaign directory to play.
+      --save-dir DIR      The directory saves and settings are kept in.
+      --assets DIR        The directory with atlases.txt and the images
+                          sprites use.
+      --seed NUMBER       Mixed into every dungeon seed, 0 keeps the
+                          campaigns own seeds.
+      --renderer NAME     The SDL render driver to use, like opengl or
+                          software.
+      --hud LAYOUT        What the HUD shows and where, like
+                          \"health:top_left turn:top_right\", or off.
+
+The config file takes the same options without the dashes, one per line:
+
+  resolution 1280x720
+  fullscreen true
+
+Options given here win over the config file, which wins over the defaults.";
+
+/// How tiles are scaled when the window changes size.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Scaling {
+    /// Tiles keep their size and more of the level is shown as the window
+    /// grows.
+    Fixed,
+    /// Tiles grow by whole multiples of their size each time the window
+    /// fits the starting resolution again, so pixels stay crisp.
+    Integer,
+}
+
+impl Scaling {
+    /// Returns the scaling with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Scaling> {
+        match name {
+            "fixed" => Some(Scaling::Fixed),
+            "integer" => Some(Scaling::Integer),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of fullscreen used when the game is fullscreen.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FullscreenMode {
+    /// A borderless window the size of the screen.
+    Borderless,
+    /// Changes the resolution of the screen.
+    Exclusive,
+}
+
+impl FullscreenMode {
+    /// Returns the mode with the given name, borderless or exclusive.
+    pub fn from_name(name: &str) -> Option<FullscreenMode> {
+        match name {
+            "borderless" => Some(FullscreenMode::Borderless),
+            "exclusive" => Some(FullscreenMode::Exclusive),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl From<FullscreenMode> for FullscreenType {
+    fn from(mode: FullscreenMode) -> FullscreenType {
+        match mode {
+            FullscreenMode::Borderless => FullscreenType::Desktop,
+            FullscreenMode::Exclusive => FullscreenType::True,
+        }
+    }
+}
+
+/// The command line arguments, read into the same options the config file
+/// uses so both go through EnvironmentBuilder::set_option.
+pub struct Arguments {
+    help: bool,
+    config: Option<PathBuf>,
+    options: Vec<(&'static str, String)>,
+}
+
+impl Arguments {
+    /// Reads the arguments, not including the program name. A lone
+    /// argument without dashes is the campaign directory.
+    pub fn parse<I: IntoIterator<Item = String>>(arguments: I) -> Result<Arguments, String> {
+        let mut parsed = Arguments {
+            help: false,
+            config: None,
+            options: Vec::new(),
+        };
+        let mut arguments = arguments.into_iter();
+        while let Some(argument) = arguments.next() {
+            let option = match argument.as_str() {
+                "-h" | "--help" => {
+                    parsed.help = true;
+                    continue;
+                }
+                "--fullscreen" => ("fullscreen", "true".to_string()),
+                "--windowed" => ("fullscreen", "false".to_string()),
+                "--vsync" => ("vsync", "true".to_string()),
+                "--no-vsync" => ("vsync", "false".to_string()),
+                flag if flag.starts_with('-') => {
+                    let name = match flag {
+                        "--config" => "config",
+                        "--resolution" => "resolution",
+                        "--tile-size" => "tile_size",
+                        "--fullscreen-mode" => "fullscreen_mode",
+                        "--scaling" => "scaling",
+                        "--campaign" => "campaign",
+                        "--save-dir" => "save_dir",
+                        "--assets" => "assets",
+                        "--seed" => "seed",
+                        "--renderer" => "renderer",
+                        "--hud" => "hud",
+                        _ => return Err(format!("Unknown option {}.", flag)),
+                    };
+                    let value = arguments
+                        .next()
+                        .ok_or_else(|| format!("{} needs a value.", flag))?;
+                    if name == "config" {
+                        parsed.config = Some(PathBuf::from(value));
+                        continue;
+                    }
+                    (name, value)
+                }
+                _ => ("campaign", argument),
+            };
+            parsed.options.push(option);
+        }
+        Ok(parsed)
+    }
+    /// Returns true if --help was given.
+    pub fn is_help(&self) -> bool {
+        self.help
+    }
+    /// Returns the config file given with --config, if there was one.
+    pub fn get_config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+    /// Builds the Environment from the defaults, then the config file, then
+    /// these arguments, each winning over the last.
+    ///
+    /// A missing config.txt is fine, but a missing file given with --config
+    /// is an error.
+    pub fn to_environment(&self) -> Result<Environment, String> {
+        let mut builder = Environment::new().set_title("An RPG");
+        let config = self.get_config().unwrap_or_else(|| Path::new(CONFIG_PATH));
+        if self.config.is_some() || config.exists() {
+            builder = builder
+                .read_config(config)
+                .map_err(|error| format!("{}: {}", config.display(), error))?;
+        }
+        for (name, value) in self.options.iter() {
+            builder = builder
+                .set_option(name, value)
+                .map_err(|error| format!("{} {}: {}", name, value, error))?;
+        }
+        builder.build().map_err(|error| error.to_string())
+    }
+}
+
+/// Builds the environment.
+///
+/// Options can be set one by one, or by name with set_option, which is how
+/// the config file and command line arguments are read.
+pub struct EnvironmentBuilder {
+    window_title: &'static str,
+    window_size: math::Dimension,
+    tile_size: u32,
+    fullscreen: bool,
+    fullscreen_mode: FullscreenMode,
+    scaling: Scaling,
+    vsync: bool,
+    campaign: Option<PathBuf>,
+    save_directory: PathBuf,
+    assets_directory: PathBuf,
+    seed: u64,
+    renderer: Option<String>,
+    hud: Hud,
+}
+
+impl EnvironmentBuilder {
+    /// Sets the window height.
+    pub fn set_window_height(mut self, height: u32) -> Self {
+        self.window_size.set_height(height);
+        self
+    }
+    /// Sets the window width.
+    pub fn set_window_width(mut self, width: u32) -> Self {
+        self.window_size.set_width(width);
+        self
+    }
+    /// Sets the window height and width using a Dimension.
+    pub fn set_window_dimensions(mut self, dimensions: math::Dimension) -> Self {
+        self.window_size = dimensions;
+        self
+    }
+    /// Sets the title of the window.
+    pub fn set_window_title(mut self, title: &'static str) -> Self {
+        self.window_title = title;
+        self
+    }
+    /// Short hand for set_window_title.
+    ///
+    /// Probably will be removed or changed.
+    pub fn set_title(mut self, title: &'static str) -> Self {
+        self.window_title = title;
+        self
+    }
+    /// Set the size of tile, smallest entities, etc. in pixels.
+    pub fn set_tile_size(mut self, pixels: u32) -> Self {
+        self.tile_size = pixels;
+        self
+    }
+    /// Sets if the game starts in fullscreen.
+    pub fn set_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+    /// Sets the kind of fullscreen used, a borderless window the size of
+    /// the screen or changing the screens resolution.
+    pub fn set_fullscreen_mode(mut self, fullscreen_mode: FullscreenMode) -> Self {
+        self.fullscreen_mode = fullscreen_mode;
+        self
+    }
+    /// Sets how tiles are scaled when the window changes size.
+    pub fn set_scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+    /// Sets if frames wait for the screen before being shown.
+    pub fn set_vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+    /// Sets the directory of the campaign to play. Without one the built in
+    /// map is played.
+    pub fn set_campaign(mut self, directory: PathBuf) -> Self {
+        self.campaign = Some(directory);
+        self
+    }
+    /// Sets the directory saves are kept in.
+    pub fn set_save_directory(mut self, directory: PathBuf) -> Self {
+        self.save_directory = directory;
+        self
+    }
+    /// Sets the directory atlases are loaded from.
+    pub fn set_assets_directory(mut self, directory: PathBuf) -> Self {
+        self.assets_directory = directory;
+        self
+    }
+    /// Sets the seed mixed into every dungeon seed. 0 leaves them alone.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    /// Sets the name of the SDL render driver to use.
+    pub fn set_renderer(mut self, renderer: &str) -> Self {
+        self.renderer = Some(renderer.to_string());
+        self
+    }
+    /// Sets what the HUD shows and where.
+    pub fn set_hud(mut self, hud: Hud) -> Self {
+        self.hud = hud;
+        self
+    }
+    /// Sets an option by the name used in the config file.
+    pub fn set_option(self, name: &str, value: &str) -> Result<Self, &'static str> {
+        Ok(match name {
+            "resolution" => self.set_window_dimensions(parse_resolution(value)?),
+            "tile_size" => match value.parse() {
+                Ok(pixels) if pixels > 0 => self.set_tile_size(pixels),
+                _ => return Err("Expected a tile size above 0."),
+            },
+            "fullscreen" => self.set_fullscreen(parse_bool(value)?),
+            "fullscreen_mode" => self.set_fullscreen_mode(
+                FullscreenMode::from_name(value).ok_or("Expected borderless or exclusive.")?,
+            ),
+            "scaling" => {
+                self.set_scaling(Scaling::from_name(value).ok_or("Expected fixed or integer.")?)
+            }
+            "vsync" => self.set_vsync(parse_bool(value)?),
+            "campaign" => self.set_campaign(PathBuf::from(value)),
+            "save_dir" => self.set_save_directory(PathBuf::from(value)),
+            "assets" => self.set_assets_directory(PathBuf::from(value)),
+            "seed" => self.set_seed(value.parse().map_err(|_| "Expected a whole number.")?),
+            "renderer" => self.set_renderer(value),
+            "hud" => self.set_hud(Hud::from_record(value)?),
+            _ => return Err("Unknown option."),
+        })
+    }
+    /// Reads a config file, setting each option in it. Every line is an
+    /// option name and its value, and `#` starts a comment.
+    pub fn read_config(mut self, path: &Path) -> Result<Self, FileError> {
+        let contents = fs::read_to_string(path)?;
+        for (index, line) in contents.lines().enumerate() {
+            let (name, value) = save::split_record(save::strip_comment(line));
+            if name.is_empty() {
+                continue;
+            }
+            self = self
+                .set_option(name, value)
+                .map_err(|error| FileError::on_line(index + 1, error))?;
+        }
+        Ok(self)
+    }
+}
+
+impl Builder for EnvironmentBuilder {
+    type product = Environment;
+
+    /// Creates a new instance of EnvironmentBuilder with the defaults.
+    fn new() -> EnvironmentBuilder {
+        let tile_size = 32;
+        EnvironmentBuilder {
+            window_size: math::Dimension::new(tile_size * 30, tile_size * 20),
+            window_title: "Application",
+            tile_size,
+            fullscreen: false,
+            fullscreen_mode: FullscreenMode::Borderless,
+            scaling: Scaling::Fixed,
+            vsync: true,
+            campaign: None,
+            save_directory: PathBuf::from("."),
+            assets_directory: PathBuf::from("assets"),
+            seed: 0,
+            renderer: None,
+            hud: Hud::default(),
+        }
+    }
+    /// Build Environment from EnvironmentBuilder.
+    fn build(self) -> Result<Environment, BuilderError> {
+        if self.window_size.get_width() == 0 || self.window_size.get_height() == 0 {
+            return Err(BuilderError::new("The window needs a width and height."));
+        }
+        Ok(Environment {
+            window_size: self.window_size,
+            window_title: self.window_title,
+            tile_size: self.tile_size,
+            fullscreen: self.fullscreen,
+            fullscreen_mode: self.fullscreen_mode,
+            scaling: self.scaling,
+            vsync: self.vsync,
+            campaign: self.campaign,
+            save_directory: self.save_directory,
+            assets_directory: self.assets_directory,
+            seed: self.seed,
+            renderer: self.renderer,
+            hud: self.hud,
+        })
+    }
+}
+
+/// The Environment is used to get information from the environment surrounding the game.
+///
+/// Some of this may get merged with Settings from data.rs.
+pub struct Environment {
+    window_size: math::Dimension,
+    window_title: &'static str,
+    tile_size: u32,
+    fullscreen: bool,
+    fullscreen_mode: FullscreenMode,
+    scaling: Scaling,
+    vsync: bool,
+    campaign: Option<PathBuf>,
+    save_directory: PathBuf,
+    assets_directory: PathBuf,
+    seed: u64,
+    renderer: Option<String>,
+    hud: Hud,
+}
+
+impl Environment {
+    /// Creates an instance of EnvironmentBuilder to build Environment
+    pub fn new() -> EnvironmentBuilder {
+        EnvironmentBuilder::new()
+    }
+    /// Returns the window size.
+    pub fn get_window_size(&self) -> math::Dimension {
+        self.window_size
+    }
+    /// Returns the window width.
+    pub fn get_window_width(&self) -> u32 {
+        self.window_size.get_width()
+    }
+    /// Returns the window height.
+    pub fn get_window_height(&self) -> u32 {
+        self.window_size.get_height()
+    }
+    /// Returns the window title.
+    pub fn get_window_title(&self) -> &'static str {
+        self.window_title
+    }
+    /// Returns the tile size.
+    pub fn get_tile_size(&self) -> u32 {
+        self.tile_size
+    }
+    /// Returns true if the game starts in fullscreen.
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+    /// Returns the kind of fullscreen used when the game is fullscreen.
+    pub fn get_fullscreen_mode(&self) -> FullscreenMode {
+        self.fullscreen_mode
+    }
+    /// Returns how tiles are scaled when the window changes size.
+    pub fn get_scaling(&self) -> Scaling {
+        self.scaling
+    }
+    /// Returns true if frames wait for the screen.
+    pub fn is_vsync(&self) -> bool {
+        self.vsync
+    }
+    /// Returns the directory of the campaign to play, if one was given.
+    pub fn get_campaign(&self) -> Option<&Path> {
+        self.campaign.as_deref()
+    }
+    /// Returns the directory saves are kept in.
+    pub fn get_save_directory(&self) -> &Path {
+        &self.save_directory
+    }
+    /// Returns the directory atlases are loaded from.
+    pub fn get_assets_directory(&self) -> &Path {
+        &self.assets_directory
+    }
+    /// Returns the seed mixed into dungeon seeds.
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+    /// Returns the name of the SDL render driver to use, if one was given.
+    pub fn get_renderer(&self) -> Option<&str> {
+        self.renderer.as_deref()
+    }
+    /// Returns what the HUD shows and where.
+    pub fn get_hud(&self) -> &Hud {
+        &self.hud
+    }
+}
+
+/// Parses a resolution like 960x640.
+fn parse_resolution(value: &str) -> Result<math::Dimension, &'static str> {
+    let error = "Expected a resolution like 960x640.";
+    let mut sides = value.splitn(2, 'x');
+    let width = sides
+        .next()
+        .and_then(|width| width.parse().ok())
+        .ok_or(error)?;
+    let height = sides
+        .next()
+        .and_then(|height| height.parse().ok())
+        .ok_or(error)?;
+    Ok(math::Dimension::new(width, height))
+}
+
+fn parse_bool(value: &str) -> Result<bool, &'static str> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err("Expected true or false."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(words: &[&str]) -> Result<Arguments, String> {
+        Arguments::parse(words.iter().map(|word| word.to_string()))
+    }
+
+    #[test]
+    fn help_is_found_anywhere() {
+        assert!(arguments(&["--help"]).unwrap().is_help());
+        assert!(arguments(&["--vsync", "-h"]).unwrap().is_help());
+        assert!(!arguments(&["--vsync"]).unwrap().is_help());
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert!(arguments(&["--bogus"]).is_err());
+        assert!(arguments(&["--seed"]).is_err());
+        let bad_values = [
+            ["--resolution", "960"],
+            ["--resolution", "wide"],
+            ["--tile-size", "0"],
+            ["--fullscreen-mode", "sideways"],
+            ["--scaling", "huge"],
+            ["--seed", "-1"],
+            ["--hud", "health:middle"],
+        ];
+        for words in bad_values.iter() {
+            let environment = arguments(words).unwrap().to_environment();
+            assert!(environment.is_err(), "{:?} was accepted", words);
+        }
+        assert!(Environment::new().set_option("vsync", "maybe").is_err());
+        assert!(Environment::new().set_option("colour", "red").is_err());
+    }
+
+    #[test]
+    fn arguments_win_over_config_which_wins_over_defaults() {
+        let path = std::env::temp_dir().join("tiny_rpg_test_config.txt");
+        fs::write(
+            &path,
+            "# a comment\ntile_size 16\nvsync false # trailing\n\nfullscreen_mode exclusive\n",
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+        let environment = arguments(&["--config", config, "--tile-size", "24", "dungeon"])
+            .unwrap()
+            .to_environment()
+            .unwrap();
+        // from the arguments
+        assert_eq!(environment.get_tile_size(), 24);
+        assert_eq!(environment.get_campaign(), Some(Path::new("dungeon")));
+        // from the config file
+        assert!(!environment.is_vsync());
+        assert_eq!(environment.get_fullscreen_mode(), FullscreenMode::Exclusive);
+        // from the defaults
+        assert_eq!(
+            environment.get_window_size(),
+            math::Dimension::new(960, 640)
+        );
+        assert_eq!(environment.get_scaling(), Scaling::Fixed);
+
+        fs::write(&path, "tile_size 16\nvsync sometimes\n").unwrap();
+        let error = match arguments(&["--config", config]).unwrap().to_environment() {
+            Ok(_) => panic!("a bad config line was accepted"),
+            Err(error) => error,
+        };
+        assert!(error.contains("line 2"), "{}", error);
+        fs::remove_file(&path).unwrap();
+        assert!(arguments(&["--config", config])
+            .unwrap()
+            .to_environment()
+            .is_err());
+    }
+}
@@ -4,46 +4,62 @@
 //! entities will change as well. Hoping to make it so Entities are
 //! the only thing that makes changes to the game.
 
-use crate::data::Data;
+use std::time::Duration;
 
-use sdl2::event::Event;
+use crate::animation::Clip;
+use crate::data::Data;
+use crate::entity::Entity;
+use crate::input::DeviceEvent;
+use crate::math::{Point, Rectangle};
+use crate::messages::MessageCategory;
+use crate::save::Save;
+use crate::screens;
 
-/// Handles events. Likely handle_key_events will be moved here as it
-/// is unlikey any event will occur without it.
+/// Handles events by reading presses and releases into the input state.
+/// Nothing happens in the game until update_input runs.
 ///
-/// Takes a reference to the event and mutable data. This along with
-/// handle_key_events may be moved to render.rs to make the program
-/// less dependent on sdl2.
-pub fn handle_events(event: &sdl2::event::Event, data: &mut Data) {
-    handle_key_events(event, data);
+/// Takes a reference to the event and mutable data. Keys and controllers
+/// are turned into the same inputs by input::InputState, and the
+/// bindings in Settings turn those into actions.
+pub fn handle_events(event: &DeviceEvent, data: &mut Data) {
+    data.get_mut_input_state().handle_event(event);
 }
 
-/// Handles keypress events. Reads the keycode from the given event,
-/// then performs a player action based on the code.
+/// Moves held inputs forward by elapsed, then gives the buffered input, if
+/// there is one, to the screen on top of the screen stack. Called once a
+/// frame, so at most one command happens each frame.
 ///
-/// The binding attribute of the Settings enum allows the keycodes to be
-/// independent of the action and can be changed.
-pub fn handle_key_events(event: &sdl2::event::Event, data: &mut Data) {
-    let mut action: Option<PlayerAction> = None;
-
-    for binding in data.get_settings().get_bindings().iter() {
-        let keycode = (*binding).0;
-        match event {
-            Event::KeyDown {
-                keycode: Some(v), ..
-            } => {
-                if v == &keycode {
-                    action = Some((*binding).1);
-                    break;
-                }
-            }
-            _ => {}
-        };
+/// The mouse and typed text go to the top screen first, so the letter of a
+/// key that opens a screen isn't typed into it.
+///
+/// While the player walks to where the mouse clicked, a step is taken
+/// instead, and pressing anything stops the walk.
+///
+/// Movement while the player is sliding waits in the buffer or skips the
+/// slide, depending on the tweening in settings.
+pub fn update_input(data: &mut Data, elapsed: Duration) {
+    data.update_input(elapsed);
+    for pointer in data.get_mut_input_state().take_pointer_events() {
+        screens::handle_pointer(pointer, data);
     }
-
-    match action {
-        Some(a) => a.perform_action(data),
-        None => {}
+    let typed = data.get_mut_input_state().take_typed();
+    if !typed.is_empty() {
+        screens::handle_text(&typed, data);
+    }
+    if data.is_travelling() {
+        if data.get_mut_input_state().take_buffered().is_some() {
+            data.cancel_travel();
+        } else if let Some(action) = data.take_travel_step() {
+            screens::handle_action(action, data);
+        }
+        return;
+    }
+    if data.is_waiting_for_tween() {
+        return;
+    }
+    data.fast_forward_tweens();
+    if let Some(input_event) = data.get_mut_input_state().take_buffered() {
+        screens::handle_input(&input_event, data);
     }
 }
 
@@ -52,6 +68,59 @@ pub fn handle_key_events(event: &sdl2::event::Event, data: &mut Data) {
 pub fn move_world_forward(data: &mut Data) {
     data.add_one_time();
     data.entities_act();
+    data.update_field_of_view();
+}
+
+/// How much a strike from the player damages.
+pub const STRIKE_DAMAGE: u32 = 1;
+
+/// The player hits whatever is at point, which takes a turn. Walls break a
+/// tile at a time.
+pub fn strike(data: &mut Data, point: Point) {
+    match data.damage_tile(point, STRIKE_DAMAGE) {
+        Ok(true) => data.add_message(MessageCategory::Combat, "It breaks apart."),
+        Ok(false) => data.add_message(MessageCategory::Combat, "You strike it."),
+        Err(error) => data.add_message(MessageCategory::Combat, error),
+    }
+    data.get_mut_player().play_clip(Clip::Attack);
+    move_world_forward(data);
+}
+
+/// Moves the player to destination with the given move, unless something is
+/// in the way. Moving into a warp sends the player to the warps level. When
+/// the player is striking it hits what is there instead.
+///
+/// Slow terrain takes more than one turn to step onto, and the world moves
+/// forward once for each.
+fn move_player(data: &mut Data, destination: Rectangle, move_entity: fn(&mut Entity)) {
+    if data.take_striking() {
+        strike(data, destination.get_point());
+        return;
+    }
+    // soon do collision based on entity type, for now all the same.
+    let mut turns = 1;
+    let warp = match data.check_position(destination) {
+        Some(entity) => entity.get_abilities().is_warp(),
+        None => {
+            match data.get_move_cost(destination) {
+                Some(cost) => {
+                    move_entity(data.get_mut_player());
+                    turns = cost;
+                }
+                None => data.add_message(MessageCategory::Travel, "You can't cross that."),
+            }
+            None
+        }
+    };
+    if let Some((level, arrival)) = warp {
+        if let Err(error) = data.warp(level, &arrival) {
+            data.add_error(error);
+        }
+    }
+
+    for _turn in 0..turns {
+        move_world_forward(data);
+    }
 }
 
 /// An enum of the players possible actions. These should be binded to
@@ -70,9 +139,93 @@ pub enum PlayerAction {
     Inventory,
     /// Allows the player to check their settings.
     Settings,
+    /// Saves the game to the default save.
+    QuickSave,
+    /// Loads the game from the default save.
+    QuickLoad,
+    /// Chooses the selected option in menus.
+    Confirm,
+    /// Leaves the current screen, or pauses the game.
+    Back,
+    /// Switches between a window and fullscreen, on any screen.
+    ToggleFullscreen,
+    /// Opens the history of messages.
+    History,
+    /// Shows or hides the minimap.
+    ToggleMinimap,
+    /// Makes the next move hit what is that way instead, like a wall.
+    Strike,
 }
 
 impl PlayerAction {
+    /// Every action, in the order they are shown to the player.
+    pub const ALL: [PlayerAction; 14] = [
+        PlayerAction::MoveLeft,
+        PlayerAction::MoveRight,
+        PlayerAction::MoveUp,
+        PlayerAction::MoveDown,
+        PlayerAction::Inventory,
+        PlayerAction::Settings,
+        PlayerAction::QuickSave,
+        PlayerAction::QuickLoad,
+        PlayerAction::Confirm,
+        PlayerAction::Back,
+        PlayerAction::ToggleFullscreen,
+        PlayerAction::History,
+        PlayerAction::ToggleMinimap,
+        PlayerAction::Strike,
+    ];
+
+    /// Returns true if the action moves the player. Only movement repeats
+    /// while held.
+    pub fn is_movement(&self) -> bool {
+        matches!(
+            self,
+            PlayerAction::MoveLeft
+                | PlayerAction::MoveRight
+                | PlayerAction::MoveUp
+                | PlayerAction::MoveDown
+        )
+    }
+    /// Returns true if the action is needed to get around menus, so it must
+    /// always have a key.
+    pub fn is_required(&self) -> bool {
+        matches!(
+            self,
+            PlayerAction::MoveLeft
+                | PlayerAction::MoveRight
+                | PlayerAction::MoveUp
+                | PlayerAction::MoveDown
+                | PlayerAction::Confirm
+                | PlayerAction::Back
+        )
+    }
+    /// Returns the name of the action, used when writing files.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PlayerAction::MoveLeft => "move_left",
+            PlayerAction::MoveRight => "move_right",
+            PlayerAction::MoveUp => "move_up",
+            PlayerAction::MoveDown => "move_down",
+            PlayerAction::Inventory => "inventory",
+            PlayerAction::Settings => "settings",
+            PlayerAction::QuickSave => "quick_save",
+            PlayerAction::QuickLoad => "quick_load",
+            PlayerAction::Confirm => "confirm",
+            PlayerAction::Back => "back",
+            PlayerAction::ToggleFullscreen => "toggle_fullscreen",
+            PlayerAction::History => "history",
+            PlayerAction::ToggleMinimap => "toggle_minimap",
+            PlayerAction::Strike => "strike",
+        }
+    }
+    /// Returns the action with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<PlayerAction> {
+        PlayerAction::ALL
+            .iter()
+            .find(|action| action.get_name() == name)
+            .copied()
+    }
     /// The action is read and a method is performed based on it.
     ///
     /// data is required because most actions will need to know about
@@ -85,51 +238,53 @@ impl PlayerAction {
             PlayerAction::MoveDown => self.move_down(data),
             PlayerAction::Inventory => self.inventory(data),
             PlayerAction::Settings => self.settings(data),
+            PlayerAction::QuickSave => self.quick_save(data),
+            PlayerAction::QuickLoad => self.quick_load(data),
+            PlayerAction::ToggleFullscreen => data.toggle_fullscreen(),
+            PlayerAction::ToggleMinimap => data.toggle_minimap(),
+            PlayerAction::Strike => self.strike(data),
+            // only mean something to screens
+            PlayerAction::Confirm | PlayerAction::Back | PlayerAction::History => {}
         }
     }
     // validation will soon be done here for player movement
     // events will also happen when player moves
     fn move_left(&self, data: &mut Data) {
-        // soon do collision based on entity type, for now all the same.
-        if data
-            .check_position(data.get_player().get_move_left())
-            .is_none()
-        {
-            data.get_mut_player().move_left();
-        }
-
-        move_world_forward(data);
+        let destination = data.get_player().get_move_left();
+        move_player(data, destination, Entity::move_left);
     }
     fn move_right(&self, data: &mut Data) {
-        if data
-            .check_position(data.get_player().get_move_right())
-            .is_none()
-        {
-            data.get_mut_player().move_right();
-        }
-
-        move_world_forward(data);
+        let destination = data.get_player().get_move_right();
+        move_player(data, destination, Entity::move_right);
     }
     fn move_up(&self, data: &mut Data) {
-        if data
-            .check_position(data.get_player().get_move_up())
-            .is_none()
-        {
-            data.get_mut_player().move_up();
-        }
-
-        move_world_forward(data);
+        let destination = data.get_player().get_move_up();
+        move_player(data, destination, Entity::move_up);
     }
     fn move_down(&self, data: &mut Data) {
-        if data
-            .check_position(data.get_player().get_move_down())
-            .is_none()
-        {
-            data.get_mut_player().move_down();
-        };
-
-        move_world_forward(data);
+        let destination = data.get_player().get_move_down();
+        move_player(data, destination, Entity::move_down);
+    }
+    fn strike(&self, data: &mut Data) {
+        data.toggle_striking();
+        if data.is_striking() {
+            data.add_message(MessageCategory::Combat, "Strike which way?");
+        }
     }
     fn inventory(&self, data: &mut Data) {}
     fn settings(&self, data: &mut Data) {}
+    fn quick_save(&self, data: &mut Data) {
+        let path = data.get_save_path();
+        match Save::from_data(data).write(&path) {
+            Ok(()) => data.add_message(MessageCategory::System, "Game saved."),
+            Err(error) => data.add_error(&error.to_string()),
+        }
+    }
+    fn quick_load(&self, data: &mut Data) {
+        let path = data.get_save_path();
+        match Save::read(&path).and_then(|save| save.apply(data)) {
+            Ok(()) => data.add_message(MessageCategory::System, "Game loaded."),
+            Err(error) => data.add_error(&error.to_string()),
+        }
+    }
 }
@@ -0,0 +1,350 @@
+//! Field of view and line of sight.
+//!
+//! Uses symmetric shadow casting over the four quadrants around an origin to
+//! work out which tiles can be seen. Slopes are kept as exact fractions, so
+//! if one open tile can see another, the other can see it back. Anything
+//! that blocks sight is given as a list of Rectangles, usually the entities
+//! with the Opaque ability.
+//!
+//! # Planned changes:
+//!
+//! - Let light sources other than the viewer light up tiles.
+//! - Possibly cache the opaque grid between turns.
+
+use crate::math::{Point, Rectangle, TwoDimensional};
+
+/// How far entities can see by default in tiles.
+pub const DEFAULT_SIGHT_RADIUS: u32 = 10;
+
+/// Multipliers that turn a column and a row of a quadrant into x and y,
+/// for the quadrants above, below, right and left of the origin.
+const QUADRANTS: [(i32, i32, i32, i32); 4] =
+    [(1, 0, 0, -1), (1, 0, 0, 1), (0, 1, 1, 0), (0, -1, 1, 0)];
+
+/// A slope as a numerator and a positive denominator, so rows of tiles can
+/// be compared exactly.
+type Slope = (i32, i32);
+
+/// The tiles that can be seen from an origin within a radius.
+///
+/// Only the square around the origin that the radius covers is stored, so
+/// anything outside of it is never visible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldOfView {
+    origin: Point,
+    radius: u32,
+    area: Rectangle,
+    opaque: Vec<bool>,
+    visible: Vec<bool>,
+}
+
+impl FieldOfView {
+    /// Computes the field of view from origin. Tiles covered by any of the
+    /// opaque rectangles block sight, but are themselves visible if light
+    /// reaches them.
+    pub fn compute(origin: Point, radius: u32, opaque: &[Rectangle]) -> FieldOfView {
+        let side = radius * 2 + 1;
+        let area = Rectangle::new(
+            origin.get_x() - radius as i32,
+            origin.get_y() - radius as i32,
+            side,
+            side,
+        );
+        let mut field_of_view = FieldOfView {
+            origin,
+            radius,
+            area,
+            opaque: vec![false; (side * side) as usize],
+            visible: vec![false; (side * side) as usize],
+        };
+        for rectangle in opaque.iter() {
+            field_of_view.mark_opaque(*rectangle);
+        }
+
+        field_of_view.set_visible(origin);
+        for quadrant in QUADRANTS.iter() {
+            field_of_view.scan(1, (-1, 1), (1, 1), *quadrant);
+        }
+        field_of_view
+    }
+    /// Returns where the field of view was computed from.
+    pub fn get_origin(&self) -> Point {
+        self.origin
+    }
+    /// Returns how far the field of view reaches.
+    pub fn get_radius(&self) -> u32 {
+        self.radius
+    }
+    /// Returns the square of tiles that the field of view covers.
+    pub fn get_area(&self) -> Rectangle {
+        self.area
+    }
+    /// Returns true if the tile at point can be seen.
+    pub fn is_visible(&self, point: Point) -> bool {
+        match self.index(point) {
+            Some(index) => self.visible[index],
+            None => false,
+        }
+    }
+    /// Returns true if any tile of the rectangle can be seen.
+    pub fn is_rectangle_visible(&self, rectangle: Rectangle) -> bool {
+        if self.area.is_not_colliding(rectangle) {
+            return false;
+        }
+        let x_min = rectangle.x_min().max(self.area.x_min());
+        let x_max = rectangle.x_max().min(self.area.x_max());
+        let y_min = rectangle.y_min().max(self.area.y_min());
+        let y_max = rectangle.y_max().min(self.area.y_max());
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                if self.is_visible(Point::new(x, y)) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    /// Returns every tile that can be seen.
+    pub fn get_visible_points(&self) -> Vec<Point> {
+        let mut points = Vec::new();
+        for (index, visible) in self.visible.iter().enumerate() {
+            if *visible {
+                let width = self.area.width() as usize;
+                points.push(Point::new(
+                    self.area.x() + (index % width) as i32,
+                    self.area.y() + (index / width) as i32,
+                ));
+            }
+        }
+        points
+    }
+    fn index(&self, point: Point) -> Option<usize> {
+        let (x, y) = point.get_coordinates();
+        if x < self.area.x_min()
+            || x > self.area.x_max()
+            || y < self.area.y_min()
+            || y > self.area.y_max()
+        {
+            return None;
+        }
+        let column = (x - self.area.x()) as usize;
+        let row = (y - self.area.y()) as usize;
+        Some(row * self.area.width() as usize + column)
+    }
+    fn mark_opaque(&mut self, rectangle: Rectangle) {
+        if self.area.is_not_colliding(rectangle) {
+            return;
+        }
+        let x_min = rectangle.x_min().max(self.area.x_min());
+        let x_max = rectangle.x_max().min(self.area.x_max());
+        let y_min = rectangle.y_min().max(self.area.y_min());
+        let y_max = rectangle.y_max().min(self.area.y_max());
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                if let Some(index) = self.index(Point::new(x, y)) {
+                    self.opaque[index] = true;
+                }
+            }
+        }
+    }
+    fn is_opaque(&self, point: Point) -> bool {
+        match self.index(point) {
+            Some(index) => self.opaque[index],
+            None => true,
+        }
+    }
+    fn set_visible(&mut self, point: Point) {
+        if let Some(index) = self.index(point) {
+            self.visible[index] = true;
+        }
+    }
+    /// Lights up the row of a quadrant at depth between the start and end
+    /// slopes, then the rows behind it, splitting the light in two whenever
+    /// an opaque tile is in the way.
+    ///
+    /// Opaque tiles are lit if any of them is in the light, open tiles only
+    /// if their centre is, which is what keeps sight symmetric.
+    fn scan(&mut self, depth: i32, mut start: Slope, end: Slope, quadrant: (i32, i32, i32, i32)) {
+        let radius = self.radius as i32;
+        if depth > radius {
+            return;
+        }
+        let (column_x, depth_x, column_y, depth_y) = quadrant;
+        // the columns whose centres are closest to the slopes, ties going inwards
+        let min_column = (2 * depth * start.0 + start.1).div_euclid(2 * start.1);
+        let max_column = -(end.1 - 2 * depth * end.0).div_euclid(2 * end.1);
+        let mut previous_opaque = None;
+
+        for column in min_column..=max_column {
+            let point = Point::new(
+                self.origin.get_x() + column * column_x + depth * depth_x,
+                self.origin.get_y() + column * column_y + depth * depth_y,
+            );
+            let opaque = self.is_opaque(point);
+            let centre_lit = column * start.1 >= depth * start.0 && column * end.1 <= depth * end.0;
+            if (opaque || centre_lit) && column * column + depth * depth <= radius * radius {
+                self.set_visible(point);
+            }
+            // the left edge of this tile
+            let edge = (2 * column - 1, 2 * depth);
+            match previous_opaque {
+                Some(true) if !opaque => start = edge,
+                Some(false) if opaque => self.scan(depth + 1, start, edge, quadrant),
+                _ => {}
+            }
+            previous_opaque = Some(opaque);
+        }
+        if previous_opaque == Some(false) {
+            self.scan(depth + 1, start, end, quadrant);
+        }
+    }
+}
+
+/// Returns true if nothing opaque sits on the line between from and to.
+///
+/// The tiles at from and to are not checked, so a viewer standing next to a
+/// wall can still see the wall. The line is always drawn from the same end,
+/// so from can see to exactly when to can see from.
+pub fn line_of_sight<F>(from: Point, to: Point, is_opaque: F) -> bool
+where
+    F: Fn(Point) -> bool,
+{
+    let (start, end) = if (from.get_y(), from.get_x()) <= (to.get_y(), to.get_x()) {
+        (from, to)
+    } else {
+        (to, from)
+    };
+    for point in get_line(start, end).iter() {
+        if *point != from && *point != to && is_opaque(*point) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns true if to is within radius of from and in line of sight.
+pub fn can_see<F>(from: Point, to: Point, radius: u32, is_opaque: F) -> bool
+where
+    F: Fn(Point) -> bool,
+{
+    let dx = (to.get_x() - from.get_x()) as i64;
+    let dy = (to.get_y() - from.get_y()) as i64;
+    if dx * dx + dy * dy > radius as i64 * radius as i64 {
+        return false;
+    }
+    line_of_sight(from, to, is_opaque)
+}
+
+/// Returns every tile on the line from one point to another, including both
+/// ends, using Bresenham's line algorithm.
+pub fn get_line(from: Point, to: Point) -> Vec<Point> {
+    let (mut x, mut y) = from.get_coordinates();
+    let (x_end, y_end) = to.get_coordinates();
+    let dx = (x_end - x).abs();
+    let dy = -(y_end - y).abs();
+    let step_x = if x < x_end { 1 } else { -1 };
+    let step_y = if y < y_end { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut points = Vec::new();
+
+    loop {
+        points.push(Point::new(x, y));
+        if x == x_end && y == y_end {
+            break;
+        }
+        let doubled = error * 2;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 15 by 15 room with every fifth tile or so a pillar, picked the same
+    /// way every run.
+    fn pillars() -> Vec<Rectangle> {
+        let mut state: u64 = 7;
+        let mut pillars = Vec::new();
+        for y in 0..15 {
+            for x in 0..15 {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                if (state >> 33).is_multiple_of(5) {
+                    pillars.push(Rectangle::new(x, y, 1, 1));
+                }
+            }
+        }
+        pillars
+    }
+
+    fn is_in(rectangles: &[Rectangle], point: Point) -> bool {
+        rectangles
+            .iter()
+            .any(|rectangle| rectangle.contains_point(point))
+    }
+
+    #[test]
+    fn sight_is_symmetric() {
+        let pillars = pillars();
+        let open: Vec<Point> = Rectangle::new(0, 0, 15, 15)
+            .get_points()
+            .filter(|point| !is_in(&pillars, *point))
+            .collect();
+        let fields: Vec<FieldOfView> = open
+            .iter()
+            .map(|point| FieldOfView::compute(*point, 6, &pillars))
+            .collect();
+        for (a, field_a) in open.iter().zip(fields.iter()) {
+            for (b, field_b) in open.iter().zip(fields.iter()) {
+                assert_eq!(field_a.is_visible(*b), field_b.is_visible(*a));
+                assert_eq!(
+                    line_of_sight(*a, *b, |point| is_in(&pillars, point)),
+                    line_of_sight(*b, *a, |point| is_in(&pillars, point))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn walls_block_sight() {
+        let wall = [Rectangle::new(5, 0, 1, 11)];
+        let field = FieldOfView::compute(Point::new(2, 5), 8, &wall);
+        assert!(field.is_visible(Point::new(4, 5)));
+        // the wall itself is seen, what is behind it isn't
+        assert!(field.is_visible(Point::new(5, 5)));
+        assert!(!field.is_visible(Point::new(6, 5)));
+        assert!(!field.is_visible(Point::new(8, 3)));
+        let is_opaque = |point| is_in(&wall, point);
+        assert!(line_of_sight(Point::new(2, 5), Point::new(5, 5), is_opaque));
+        assert!(!line_of_sight(
+            Point::new(2, 5),
+            Point::new(8, 5),
+            is_opaque
+        ));
+        assert!(line_of_sight(Point::new(2, 5), Point::new(2, 9), is_opaque));
+    }
+
+    #[test]
+    fn radius_is_the_edge_of_sight() {
+        let origin = Point::new(0, 0);
+        let field = FieldOfView::compute(origin, 5, &[]);
+        assert!(field.is_visible(origin));
+        assert!(field.is_visible(Point::new(5, 0)));
+        assert!(field.is_visible(Point::new(3, -4)));
+        assert!(!field.is_visible(Point::new(6, 0)));
+        // in the square the field covers, but outside the circle
+        assert!(!field.is_visible(Point::new(4, 4)));
+        assert!(can_see(origin, Point::new(0, -5), 5, |_| false));
+        assert!(!can_see(origin, Point::new(4, 4), 5, |_| false));
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 /// Anything using the Builder trait should be a builder object used to
 /// build another object of type product.
@@ -53,3 +54,96 @@ impl Error for BuilderError {
         None
     }
 }
+
+/// FileError is an error for when reading, writing or parsing a file such
+/// as a save fails.
+///
+/// Like BuilderError, may be replaced or changed.
+#[derive(Debug)]
+pub struct FileError {
+    error_message: String,
+}
+
+impl FileError {
+    /// Returns an instance of FileError.
+    pub fn new(error_message: &str) -> FileError {
+        FileError {
+            error_message: error_message.to_string(),
+        }
+    }
+    /// Returns an instance of FileError that points at the line that
+    /// could not be parsed. Lines start at 1.
+    pub fn on_line(line: usize, error_message: &str) -> FileError {
+        FileError {
+            error_message: format!("line {}: {}", line, error_message),
+        }
+    }
+}
+
+impl From<io::Error> for FileError {
+    fn from(error: io::Error) -> FileError {
+        FileError {
+            error_message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FILE ERROR: {}", self.error_message)
+    }
+}
+
+impl Error for FileError {
+    fn description(&self) -> &str {
+        "An error reading or writing a file occured."
+    }
+
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+}
+
+/// A small seeded random number generator using xorshift64*.
+///
+/// The same seed always gives the same numbers, which makes things like
+/// generated levels repeatable. Not fit for anything needing real
+/// randomness.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Creates a new instance of Random from a seed.
+    pub fn new(seed: u64) -> Random {
+        // xorshift gets stuck on zero, so mix the seed first.
+        let state = (seed ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        Random {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+    /// Returns the next random u64.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    /// Returns a random f32 from 0 up to but not including 1.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+    /// Returns a random number from min up to and including max.
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max as i64 - min as i64 + 1) as u64;
+        (min as i64 + (self.next_u64() % span) as i64) as i32
+    }
+    /// Returns true with the given chance from 0 to 1.
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+}
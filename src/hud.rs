@@ -0,0 +1,340 @@
+//! The heads up display drawn over the game.
+//!
+//! The HUD is a list of items, like health or the turn, each pinned to an
+//! edge or corner of the window. Items on the same anchor stack away from
+//! the edge in the order they are listed. Positions are worked out from the
+//! window size every frame, so the HUD follows the window as it resizes.
+//!
+//! The main menu and settings only show the messages, so errors from them,
+//! like loading without a save, are still seen.
+//!
+//! The layout is the hud option in config.txt or --hud, a list of
+//! item:anchor words, or `off` for no HUD:
+//!
+//! ```text
+//! hud health:top_left quest:top_left level:top_center turn:top_right messages:bottom_left
+//! ```
+//!
+//! # Planned changes:
+//!
+//! - Icons next to the numbers.
+//! - Hide items with a key.
+
+use std::collections::HashMap;
+
+use crate::color::Color;
+use crate::data::Data;
+use crate::math::{Dimension, Rectangle, TwoDimensional};
+use crate::screens::Screen;
+use crate::text::{Align, Text};
+
+/// Pixels between the HUD and the edge of the window, and between items.
+pub const HUD_MARGIN: u32 = 8;
+/// How many of the newest messages are shown.
+pub const HUD_MESSAGE_LINES: usize = 3;
+
+const TEXT_COLOR: Color = Color {
+    r: 230,
+    g: 230,
+    b: 230,
+    a: 255,
+};
+const LABEL_COLOR: Color = Color {
+    r: 150,
+    g: 150,
+    b: 150,
+    a: 255,
+};
+
+/// Something the HUD can show.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HudItem {
+    /// The player's health, coloured by how hurt they are.
+    Health,
+    /// The turn counter.
+    Turn,
+    /// The number and name of the current level.
+    Level,
+    /// The objective of the first quest being done.
+    Quest,
+    /// The newest messages.
+    Messages,
+}
+
+impl HudItem {
+    /// Every item.
+    pub const ALL: [HudItem; 5] = [
+        HudItem::Health,
+        HudItem::Turn,
+        HudItem::Level,
+        HudItem::Quest,
+        HudItem::Messages,
+    ];
+
+    /// Returns the name used in the hud option.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            HudItem::Health => "health",
+            HudItem::Turn => "turn",
+            HudItem::Level => "level",
+            HudItem::Quest => "quest",
+            HudItem::Messages => "messages",
+        }
+    }
+    /// Returns the item with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<HudItem> {
+        HudItem::ALL
+            .iter()
+            .find(|item| item.get_name() == name)
+            .copied()
+    }
+    /// Returns what the item shows, or None if there is nothing to show,
+    /// like the quest when there are no quests.
+    pub fn get_text(&self, data: &Data) -> Option<Text> {
+        match self {
+            HudItem::Health => {
+                let (health, most) = data.get_player().get_health()?;
+                Some(
+                    Text::new("Health ", LABEL_COLOR)
+                        .add_span(&format!("{}/{}", health, most), health_color(health, most)),
+                )
+            }
+            HudItem::Turn => Some(
+                Text::new("Turn ", LABEL_COLOR).add_span(&data.get_time().to_string(), TEXT_COLOR),
+            ),
+            HudItem::Level => {
+                let level = data.get_level();
+                Some(
+                    Text::new(&format!("{} ", level.get_level_number()), LABEL_COLOR)
+                        .add_span(level.get_name(), TEXT_COLOR),
+                )
+            }
+            HudItem::Quest => {
+                let quest = data.get_current_quests().first()?;
+                Some(
+                    Text::new(&format!("{}: ", quest.get_name()), LABEL_COLOR)
+                        .add_span(quest.get_objective(), TEXT_COLOR),
+                )
+            }
+            HudItem::Messages => {
+                let newest = data.get_messages().get_newest(HUD_MESSAGE_LINES);
+                let (last, older) = newest.split_last()?;
+                let mut text = Text::new("", LABEL_COLOR);
+                for message in older.iter() {
+                    text = text.add_span(&format!("{}\n", message.get_text()), message.get_color());
+                }
+                Some(text.add_span(last.get_text(), last.get_color()))
+            }
+        }
+    }
+}
+
+/// Where on the window an item is pinned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Anchor {
+    /// The top left corner.
+    TopLeft,
+    /// The middle of the top edge.
+    TopCenter,
+    /// The top right corner.
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The middle of the bottom edge.
+    BottomCenter,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+impl Anchor {
+    /// Every anchor.
+    pub const ALL: [Anchor; 6] = [
+        Anchor::TopLeft,
+        Anchor::TopCenter,
+        Anchor::TopRight,
+        Anchor::BottomLeft,
+        Anchor::BottomCenter,
+        Anchor::BottomRight,
+    ];
+
+    /// Returns the name used in the hud option.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top_left",
+            Anchor::TopCenter => "top_center",
+            Anchor::TopRight => "top_right",
+            Anchor::BottomLeft => "bottom_left",
+            Anchor::BottomCenter => "bottom_center",
+            Anchor::BottomRight => "bottom_right",
+        }
+    }
+    /// Returns the anchor with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Anchor> {
+        Anchor::ALL
+            .iter()
+            .find(|anchor| anchor.get_name() == name)
+            .copied()
+    }
+    /// Returns how text lines up at the anchor, so it hugs the edge.
+    pub fn get_align(&self) -> Align {
+        match self {
+            Anchor::TopLeft | Anchor::BottomLeft => Align::Left,
+            Anchor::TopCenter | Anchor::BottomCenter => Align::Center,
+            Anchor::TopRight | Anchor::BottomRight => Align::Right,
+        }
+    }
+    /// Returns true for anchors on the top edge.
+    fn is_top(&self) -> bool {
+        matches!(self, Anchor::TopLeft | Anchor::TopCenter | Anchor::TopRight)
+    }
+}
+
+/// Which items the HUD shows and where.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hud {
+    items: Vec<(HudItem, Anchor)>,
+}
+
+impl Hud {
+    /// Creates a new instance of Hud showing nothing.
+    pub fn new() -> Hud {
+        Hud { items: Vec::new() }
+    }
+    /// Adds an item at anchor, after any items already there.
+    pub fn add_item(mut self, item: HudItem, anchor: Anchor) -> Self {
+        self.items.push((item, anchor));
+        self
+    }
+    /// Returns the items and their anchors, in order.
+    pub fn get_items(&self) -> &Vec<(HudItem, Anchor)> {
+        &self.items
+    }
+    /// Returns the items shown over screen. The game shows every item, the
+    /// main menu and settings just the messages, where the layout puts
+    /// them or in the bottom left. Other screens show nothing.
+    pub fn get_screen_items(&self, screen: &Screen) -> Vec<(HudItem, Anchor)> {
+        match screen {
+            Screen::Game => self.items.clone(),
+            Screen::MainMenu(_) | Screen::Settings(_) => {
+                let anchor = self
+                    .items
+                    .iter()
+                    .find(|(item, _)| *item == HudItem::Messages)
+                    .map_or(Anchor::BottomLeft, |(_, anchor)| *anchor);
+                vec![(HudItem::Messages, anchor)]
+            }
+            _ => Vec::new(),
+        }
+    }
+    /// Reads a layout like `health:top_left turn:top_right`, or `off`.
+    pub fn from_record(record: &str) -> Result<Hud, &'static str> {
+        if record.trim() == "off" {
+            return Ok(Hud::new());
+        }
+        let mut hud = Hud::new();
+        for word in record.split_whitespace() {
+            let (item, anchor) = word
+                .split_once(':')
+                .ok_or("Expected HUD items like health:top_left.")?;
+            hud = hud.add_item(
+                HudItem::from_name(item).ok_or("Unknown HUD item.")?,
+                Anchor::from_name(anchor).ok_or("Unknown HUD anchor.")?,
+            );
+        }
+        Ok(hud)
+    }
+    /// Writes the layout the way from_record reads it.
+    pub fn to_record(&self) -> String {
+        if self.items.is_empty() {
+            return "off".to_string();
+        }
+        let words: Vec<String> = self
+            .items
+            .iter()
+            .map(|(item, anchor)| format!("{}:{}", item.get_name(), anchor.get_name()))
+            .collect();
+        words.join(" ")
+    }
+    /// Places blocks of the given sizes at their anchors on a window of
+    /// screen_size, in order. Blocks on the same anchor stack away from
+    /// its edge.
+    pub fn place(anchored: &[(Anchor, Dimension)], screen_size: Dimension) -> Vec<Rectangle> {
+        let mut used: HashMap<Anchor, u32> = HashMap::new();
+        let (screen_width, screen_height) = screen_size.get_coordinates();
+        anchored
+            .iter()
+            .map(|(anchor, size)| {
+                let (width, height) = size.get_coordinates();
+                let x = match anchor.get_align() {
+                    Align::Left => HUD_MARGIN as i32,
+                    Align::Center => (screen_width as i32 - width as i32) / 2,
+                    Align::Right => screen_width as i32 - (HUD_MARGIN + width) as i32,
+                };
+                let stacked = used.entry(*anchor).or_insert(HUD_MARGIN);
+                let y = if anchor.is_top() {
+                    *stacked as i32
+                } else {
+                    screen_height as i32 - (*stacked + height) as i32
+                };
+                *stacked += height + HUD_MARGIN;
+                Rectangle::new(x, y, width, height)
+            })
+            .collect()
+    }
+}
+
+impl Default for Hud {
+    /// Health and the quest in the top left, the level in the middle, the
+    /// turn in the top right and messages in the bottom left.
+    fn default() -> Hud {
+        Hud::new()
+            .add_item(HudItem::Health, Anchor::TopLeft)
+            .add_item(HudItem::Quest, Anchor::TopLeft)
+            .add_item(HudItem::Level, Anchor::TopCenter)
+            .add_item(HudItem::Turn, Anchor::TopRight)
+            .add_item(HudItem::Messages, Anchor::BottomLeft)
+    }
+}
+
+/// Green when healthy, yellow when hurt and red when nearly dead.
+fn health_color(health: u32, most: u32) -> Color {
+    match health * 3 {
+        left if left > most * 2 => Color::RGB(80, 220, 80),
+        left if left > most => Color::RGB(230, 200, 60),
+        _ => Color::RGB(230, 60, 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_stack_at_their_anchors() {
+        let anchored = [
+            (Anchor::TopLeft, Dimension::new(100, 20)),
+            (Anchor::BottomRight, Dimension::new(50, 10)),
+            (Anchor::TopLeft, Dimension::new(60, 30)),
+            (Anchor::BottomRight, Dimension::new(40, 10)),
+            (Anchor::TopCenter, Dimension::new(80, 20)),
+        ];
+        for (width, height) in [(800, 600), (1280, 720)].iter() {
+            let places = Hud::place(&anchored, Dimension::new(*width, *height));
+            let (width, height) = (*width as i32, *height as i32);
+            let margin = HUD_MARGIN as i32;
+            assert_eq!(places[0], Rectangle::new(margin, margin, 100, 20));
+            // under the first, a margin apart
+            assert_eq!(places[2], Rectangle::new(margin, 20 + 2 * margin, 60, 30));
+            assert_eq!(
+                places[1],
+                Rectangle::new(width - margin - 50, height - margin - 10, 50, 10)
+            );
+            // above the first at the bottom
+            assert_eq!(
+                places[3],
+                Rectangle::new(width - margin - 40, height - 2 * margin - 20, 40, 10)
+            );
+            assert_eq!(places[4], Rectangle::new((width - 80) / 2, margin, 80, 20));
+        }
+    }
+}
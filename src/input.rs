@@ -0,0 +1,1009 @@
+//! Turns events from the keyboard and game controllers into inputs that
+//! can be bound to player actions.
+//!
+//! Keys, controller buttons and stick directions are all an Input, so they
+//! go through the same bindings in Settings. InputState does the turning
+//! from DeviceEvents, the game's own copy of the SDL events it cares about.
+//! It doesn't need SDL at all, so it can be fed made up events, like in
+//! the tests. The main loop turns SDL events into DeviceEvents, and
+//! Controllers owns the opened controllers, both only with the sdl feature.
+//!
+//! Held inputs repeat on their own timer instead of the operating system's,
+//! and one input is buffered until the game is ready for it. The game then
+//! runs it through screens::handle_input, or a frontend without SDL inputs
+//! can give actions straight to screens::handle_action.
+//!
+//! The mouse and typed text aren't bound to actions. They are kept until
+//! the game takes them and gives them to the top screen, for widgets.
+//!
+//! # Planned changes:
+//!
+//! - Rumble when the player is hit, once there is combat.
+//! - Scale mouse positions on high DPI screens, where the canvas is
+//!   bigger than the window.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[cfg(feature = "sdl")]
+use sdl2::controller::GameController;
+#[cfg(feature = "sdl")]
+use sdl2::event::Event;
+#[cfg(feature = "sdl")]
+use sdl2::GameControllerSubsystem;
+
+#[cfg(feature = "sdl")]
+use crate::data::Data;
+use crate::math::Point;
+
+/// How far a stick or trigger must be pushed, out of 32767, to count as a
+/// press.
+pub const AXIS_PRESS_THRESHOLD: i16 = 16_000;
+/// How far back a pressed stick must come before it can press again.
+/// Lower than the press threshold so a stick on the edge doesn't flicker.
+pub const AXIS_RELEASE_THRESHOLD: i16 = 8_000;
+/// How long, in milliseconds, a movement is held before it repeats.
+pub const DEFAULT_REPEAT_DELAY: u32 = 250;
+/// How long, in milliseconds, between repeats of a held movement.
+pub const DEFAULT_REPEAT_INTERVAL: u32 = 100;
+
+/// The names used for keys in files. They match the names SDL gives keys.
+const KEY_NAMES: [(Key, &str); 77] = [
+    (Key::A, "A"),
+    (Key::B, "B"),
+    (Key::C, "C"),
+    (Key::D, "D"),
+    (Key::E, "E"),
+    (Key::F, "F"),
+    (Key::G, "G"),
+    (Key::H, "H"),
+    (Key::I, "I"),
+    (Key::J, "J"),
+    (Key::K, "K"),
+    (Key::L, "L"),
+    (Key::M, "M"),
+    (Key::N, "N"),
+    (Key::O, "O"),
+    (Key::P, "P"),
+    (Key::Q, "Q"),
+    (Key::R, "R"),
+    (Key::S, "S"),
+    (Key::T, "T"),
+    (Key::U, "U"),
+    (Key::V, "V"),
+    (Key::W, "W"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Z, "Z"),
+    (Key::Num0, "0"),
+    (Key::Num1, "1"),
+    (Key::Num2, "2"),
+    (Key::Num3, "3"),
+    (Key::Num4, "4"),
+    (Key::Num5, "5"),
+    (Key::Num6, "6"),
+    (Key::Num7, "7"),
+    (Key::Num8, "8"),
+    (Key::Num9, "9"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+    (Key::Up, "Up"),
+    (Key::Down, "Down"),
+    (Key::Left, "Left"),
+    (Key::Right, "Right"),
+    (Key::Return, "Return"),
+    (Key::KpEnter, "Keypad Enter"),
+    (Key::Escape, "Escape"),
+    (Key::Backspace, "Backspace"),
+    (Key::Tab, "Tab"),
+    (Key::Space, "Space"),
+    (Key::Delete, "Delete"),
+    (Key::Insert, "Insert"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::LShift, "Left Shift"),
+    (Key::RShift, "Right Shift"),
+    (Key::LCtrl, "Left Ctrl"),
+    (Key::RCtrl, "Right Ctrl"),
+    (Key::LAlt, "Left Alt"),
+    (Key::RAlt, "Right Alt"),
+    (Key::Minus, "-"),
+    (Key::Equals, "="),
+    (Key::LeftBracket, "["),
+    (Key::RightBracket, "]"),
+    (Key::Semicolon, ";"),
+    (Key::Comma, ","),
+    (Key::Period, "."),
+];
+
+/// The names used for controller buttons in files. They match the names in
+/// SDL controller mappings.
+const BUTTON_NAMES: [(Button, &str); 15] = [
+    (Button::A, "a"),
+    (Button::B, "b"),
+    (Button::X, "x"),
+    (Button::Y, "y"),
+    (Button::Back, "back"),
+    (Button::Guide, "guide"),
+    (Button::Start, "start"),
+    (Button::LeftStick, "leftstick"),
+    (Button::RightStick, "rightstick"),
+    (Button::LeftShoulder, "leftshoulder"),
+    (Button::RightShoulder, "rightshoulder"),
+    (Button::DPadUp, "dpup"),
+    (Button::DPadDown, "dpdown"),
+    (Button::DPadLeft, "dpleft"),
+    (Button::DPadRight, "dpright"),
+];
+
+/// The names used for controller axes in files. They match the names in
+/// SDL controller mappings.
+const AXIS_NAMES: [(Axis, &str); 6] = [
+    (Axis::LeftX, "leftx"),
+    (Axis::LeftY, "lefty"),
+    (Axis::RightX, "rightx"),
+    (Axis::RightY, "righty"),
+    (Axis::TriggerLeft, "lefttrigger"),
+    (Axis::TriggerRight, "righttrigger"),
+];
+
+/// A key on the keyboard. Only the keys in KEY_NAMES are here, others
+/// are ignored.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Num0,
+    Num1,
+    Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Up,
+    Down,
+    Left,
+    Right,
+    Return,
+    KpEnter,
+    Escape,
+    Backspace,
+    Tab,
+    Space,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    LShift,
+    RShift,
+    LCtrl,
+    RCtrl,
+    LAlt,
+    RAlt,
+    Minus,
+    Equals,
+    LeftBracket,
+    RightBracket,
+    Semicolon,
+    Comma,
+    Period,
+}
+
+impl Key {
+    /// Returns the file name of the key.
+    pub fn get_name(&self) -> &'static str {
+        KEY_NAMES
+            .iter()
+            .find(|(other, _)| other == self)
+            .map(|(_, name)| *name)
+            .unwrap()
+    }
+    /// Returns the key with a file name, ignoring case like SDL does.
+    pub fn from_name(name: &str) -> Option<Key> {
+        KEY_NAMES
+            .iter()
+            .find(|(_, other)| other.eq_ignore_ascii_case(name))
+            .map(|(key, _)| *key)
+    }
+    /// Returns the key for an SDL keycode, if it is one the game knows.
+    #[cfg(feature = "sdl")]
+    pub fn from_sdl(keycode: sdl2::keyboard::Keycode) -> Option<Key> {
+        Key::from_name(&keycode.name())
+    }
+}
+
+/// A button on a game controller, named like the SDL ones.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Button {
+    A,
+    B,
+    X,
+    Y,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl Button {
+    /// Returns the button for an SDL controller button.
+    #[cfg(feature = "sdl")]
+    pub fn from_sdl(button: sdl2::controller::Button) -> Button {
+        use sdl2::controller::Button as Sdl;
+        match button {
+            Sdl::A => Button::A,
+            Sdl::B => Button::B,
+            Sdl::X => Button::X,
+            Sdl::Y => Button::Y,
+            Sdl::Back => Button::Back,
+            Sdl::Guide => Button::Guide,
+            Sdl::Start => Button::Start,
+            Sdl::LeftStick => Button::LeftStick,
+            Sdl::RightStick => Button::RightStick,
+            Sdl::LeftShoulder => Button::LeftShoulder,
+            Sdl::RightShoulder => Button::RightShoulder,
+            Sdl::DPadUp => Button::DPadUp,
+            Sdl::DPadDown => Button::DPadDown,
+            Sdl::DPadLeft => Button::DPadLeft,
+            Sdl::DPadRight => Button::DPadRight,
+        }
+    }
+}
+
+/// A stick or trigger on a game controller, named like the SDL ones.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    TriggerLeft,
+    TriggerRight,
+}
+
+impl Axis {
+    /// Returns the axis for an SDL controller axis.
+    #[cfg(feature = "sdl")]
+    pub fn from_sdl(axis: sdl2::controller::Axis) -> Axis {
+        use sdl2::controller::Axis as Sdl;
+        match axis {
+            Sdl::LeftX => Axis::LeftX,
+            Sdl::LeftY => Axis::LeftY,
+            Sdl::RightX => Axis::RightX,
+            Sdl::RightY => Axis::RightY,
+            Sdl::TriggerLeft => Axis::TriggerLeft,
+            Sdl::TriggerRight => Axis::TriggerRight,
+        }
+    }
+}
+
+/// A button on the mouse.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    X1,
+    X2,
+}
+
+impl MouseButton {
+    /// Returns the button for an SDL mouse button, or None if SDL doesn't
+    /// know it either.
+    #[cfg(feature = "sdl")]
+    pub fn from_sdl(button: sdl2::mouse::MouseButton) -> Option<MouseButton> {
+        use sdl2::mouse::MouseButton as Sdl;
+        match button {
+            Sdl::Left => Some(MouseButton::Left),
+            Sdl::Middle => Some(MouseButton::Middle),
+            Sdl::Right => Some(MouseButton::Right),
+            Sdl::X1 => Some(MouseButton::X1),
+            Sdl::X2 => Some(MouseButton::X2),
+            Sdl::Unknown => None,
+        }
+    }
+}
+
+/// An event from the keyboard, a controller or the mouse, with only what
+/// InputState reads from it. Named like the SDL events they come from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeviceEvent {
+    /// A key was pressed. repeat is true for the operating system's own
+    /// repeats of a held key.
+    KeyDown {
+        /// The key pressed.
+        key: Key,
+        /// True if it is a repeat.
+        repeat: bool,
+    },
+    /// A key was released.
+    KeyUp {
+        /// The key released.
+        key: Key,
+    },
+    /// A controller button was pressed.
+    ControllerButtonDown {
+        /// The instance id of the controller.
+        which: i32,
+        /// The button pressed.
+        button: Button,
+    },
+    /// A controller button was released.
+    ControllerButtonUp {
+        /// The instance id of the controller.
+        which: i32,
+        /// The button released.
+        button: Button,
+    },
+    /// A stick or trigger moved.
+    ControllerAxisMotion {
+        /// The instance id of the controller.
+        which: i32,
+        /// The stick or trigger that moved.
+        axis: Axis,
+        /// Where it is now, from -32768 to 32767.
+        value: i16,
+    },
+    /// The mouse moved to a point in the window.
+    MouseMotion(Point),
+    /// A mouse button was pressed at a point in the window.
+    MouseButtonDown(MouseButton, Point),
+    /// The wheel was turned, by how many notches. Up is positive.
+    MouseWheel(i32),
+    /// The player typed text.
+    TextInput(String),
+}
+
+impl DeviceEvent {
+    /// Turns an SDL event into a DeviceEvent. Returns None for events
+    /// InputState doesn't care about and keys the game doesn't know.
+    #[cfg(feature = "sdl")]
+    pub fn from_sdl(event: &Event) -> Option<DeviceEvent> {
+        Some(match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat,
+                ..
+            } => DeviceEvent::KeyDown {
+                key: Key::from_sdl(*keycode)?,
+                repeat: *repeat,
+            },
+            Event::KeyUp {
+                keycode: Some(keycode),
+                ..
+            } => DeviceEvent::KeyUp {
+                key: Key::from_sdl(*keycode)?,
+            },
+            Event::ControllerButtonDown { which, button, .. } => {
+                DeviceEvent::ControllerButtonDown {
+                    which: *which,
+                    button: Button::from_sdl(*button),
+                }
+            }
+            Event::ControllerButtonUp { which, button, .. } => DeviceEvent::ControllerButtonUp {
+                which: *which,
+                button: Button::from_sdl(*button),
+            },
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => DeviceEvent::ControllerAxisMotion {
+                which: *which,
+                axis: Axis::from_sdl(*axis),
+                value: *value,
+            },
+            Event::MouseMotion { x, y, .. } => DeviceEvent::MouseMotion(Point::new(*x, *y)),
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                DeviceEvent::MouseButtonDown(MouseButton::from_sdl(*mouse_btn)?, Point::new(*x, *y))
+            }
+            Event::MouseWheel { y, .. } => DeviceEvent::MouseWheel(*y),
+            Event::TextInput { text, .. } => DeviceEvent::TextInput(text.clone()),
+            _ => return None,
+        })
+    }
+}
+
+/// Something the player can press that can be bound to an action.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Input {
+    /// A key on the keyboard.
+    Key(Key),
+    /// A button on a game controller, including the d-pad.
+    Button(Button),
+    /// A stick or trigger pushed past AXIS_PRESS_THRESHOLD. The bool is true
+    /// for the positive direction, which is right and down for sticks.
+    Axis(Axis, bool),
+}
+
+impl Input {
+    /// Returns true if the input comes from a game controller.
+    pub fn is_controller(&self) -> bool {
+        !matches!(self, Input::Key(_))
+    }
+    /// Writes the input as a record for settings files, such as `key A`,
+    /// `button dpup` or `axis leftx -`.
+    pub fn to_record(&self) -> String {
+        match self {
+            Input::Key(key) => format!("key {}", key.get_name()),
+            Input::Button(button) => format!("button {}", button_name(*button)),
+            Input::Axis(axis, positive) => format!(
+                "axis {} {}",
+                axis_name(*axis),
+                if *positive { "+" } else { "-" }
+            ),
+        }
+    }
+    /// Reads an input written by to_record.
+    pub fn from_record(record: &str) -> Result<Input, &'static str> {
+        let record = record.trim();
+        let (kind, rest) = match record.find(char::is_whitespace) {
+            Some(index) => (&record[..index], record[index..].trim()),
+            None => (record, ""),
+        };
+        match kind {
+            "key" => Key::from_name(rest).map(Input::Key).ok_or("Unknown key."),
+            "button" => BUTTON_NAMES
+                .iter()
+                .find(|(_, name)| *name == rest)
+                .map(|(button, _)| Input::Button(*button))
+                .ok_or("Unknown controller button."),
+            "axis" => {
+                let words: Vec<&str> = rest.split_whitespace().collect();
+                let (name, direction) = match words.as_slice() {
+                    [name, direction] => (*name, *direction),
+                    _ => return Err("An axis needs a name and a + or - direction."),
+                };
+                let axis = AXIS_NAMES
+                    .iter()
+                    .find(|(_, axis_name)| *axis_name == name)
+                    .map(|(axis, _)| *axis)
+                    .ok_or("Unknown controller axis.")?;
+                match direction {
+                    "+" => Ok(Input::Axis(axis, true)),
+                    "-" => Ok(Input::Axis(axis, false)),
+                    _ => Err("An axis direction is + or -."),
+                }
+            }
+            _ => Err("Unknown input, use key, button or axis."),
+        }
+    }
+}
+
+/// Where an input came from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Device {
+    /// The keyboard.
+    Keyboard,
+    /// The game controller with the given joystick instance id.
+    Controller(i32),
+}
+
+/// An input that was just pressed, and the device it was pressed on.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InputEvent {
+    input: Input,
+    device: Device,
+}
+
+impl InputEvent {
+    /// Creates a new instance of InputEvent.
+    pub fn new(input: Input, device: Device) -> InputEvent {
+        InputEvent { input, device }
+    }
+    /// Returns the input that was pressed.
+    pub fn get_input(&self) -> Input {
+        self.input
+    }
+    /// Returns the device the input was pressed on.
+    pub fn get_device(&self) -> Device {
+        self.device
+    }
+}
+
+/// Something the mouse did, in pixels from the top left of the window.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PointerEvent {
+    /// The mouse moved to a point.
+    Moved(Point),
+    /// A mouse button was pressed at a point.
+    Clicked(MouseButton, Point),
+    /// The wheel was turned, by how many notches. Up is positive.
+    Scrolled(i32),
+}
+
+/// How long an input must be held before it starts repeating, and how
+/// often it repeats after that, both in milliseconds.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct KeyRepeat {
+    delay: u32,
+    interval: u32,
+}
+
+impl KeyRepeat {
+    /// Creates a new instance of KeyRepeat. An interval of 0 is treated as
+    /// 1 so a held input can't repeat forever in one frame.
+    pub fn new(delay: u32, interval: u32) -> KeyRepeat {
+        KeyRepeat {
+            delay,
+            interval: interval.max(1),
+        }
+    }
+    /// Returns how long an input is held before it repeats.
+    pub fn get_delay(&self) -> u32 {
+        self.delay
+    }
+    /// Returns how long between repeats.
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+    /// Returns how many times an input held for the given time should have
+    /// repeated.
+    fn get_repeat_count(&self, held_for: Duration) -> u32 {
+        let held_for = held_for.as_millis() as u32;
+        if held_for < self.delay {
+            return 0;
+        }
+        1 + (held_for - self.delay) / self.interval
+    }
+}
+
+impl Default for KeyRepeat {
+    fn default() -> KeyRepeat {
+        KeyRepeat::new(DEFAULT_REPEAT_DELAY, DEFAULT_REPEAT_INTERVAL)
+    }
+}
+
+/// An input that is being held down.
+#[derive(Copy, Clone, Debug)]
+struct HeldInput {
+    input_event: InputEvent,
+    held_for: Duration,
+    repeats: u32,
+}
+
+/// Keeps what is needed to turn DeviceEvents into InputEvents: the names of
+/// the connected controllers, for their binding profiles, which sticks
+/// are pushed, so holding one only presses once, and what is held down.
+///
+/// Presses and repeats go into a buffer that holds one input, which the
+/// game takes when it is ready for the next command. A newer press
+/// replaces what is in the buffer, a repeat only fills an empty one.
+///
+/// Other frontends can skip the events and call press and release.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    controller_names: HashMap<i32, String>,
+    pressed_axes: HashMap<(i32, Axis), bool>,
+    held: Vec<HeldInput>,
+    buffered: Option<InputEvent>,
+    last_device: Option<Device>,
+    mouse_position: Option<Point>,
+    pointer_events: Vec<PointerEvent>,
+    typed: String,
+}
+
+impl InputState {
+    /// Creates a new instance of InputState with no controllers.
+    pub fn new() -> InputState {
+        InputState::default()
+    }
+    /// Remembers a connected controller by its instance id.
+    pub fn add_controller(&mut self, id: i32, name: &str) {
+        self.controller_names.insert(id, name.to_string());
+    }
+    /// Forgets a controller that was unplugged.
+    pub fn remove_controller(&mut self, id: i32) {
+        self.controller_names.remove(&id);
+        self.pressed_axes
+            .retain(|(controller, _), _| *controller != id);
+        self.held
+            .retain(|held| held.input_event.get_device() != Device::Controller(id));
+        if self.last_device == Some(Device::Controller(id)) {
+            self.last_device = None;
+        }
+    }
+    /// Returns the name of the device, used to find its binding profile.
+    /// The keyboard and unknown controllers have no name.
+    pub fn get_device_name(&self, device: Device) -> Option<&str> {
+        match device {
+            Device::Keyboard => None,
+            Device::Controller(id) => self.controller_names.get(&id).map(|name| name.as_str()),
+        }
+    }
+    /// Returns the ids and names of every connected controller.
+    pub fn get_controllers(&self) -> Vec<(i32, &str)> {
+        let mut controllers: Vec<(i32, &str)> = self
+            .controller_names
+            .iter()
+            .map(|(id, name)| (*id, name.as_str()))
+            .collect();
+        controllers.sort();
+        controllers
+    }
+    /// Returns the device the player last pressed something on.
+    pub fn get_last_device(&self) -> Option<Device> {
+        self.last_device
+    }
+    /// Reads presses and releases of keys, buttons and sticks, the mouse and
+    /// typed text from an event. Everything else is ignored, as are the
+    /// operating system's own key repeats since held inputs repeat in
+    /// update.
+    pub fn handle_event(&mut self, event: &DeviceEvent) {
+        match event {
+            DeviceEvent::KeyDown { key, repeat: false } => {
+                self.press(InputEvent::new(Input::Key(*key), Device::Keyboard))
+            }
+            DeviceEvent::KeyDown { .. } => {}
+            DeviceEvent::KeyUp { key } => self.release(Input::Key(*key), Device::Keyboard),
+            DeviceEvent::ControllerButtonDown { which, button } => self.press(InputEvent::new(
+                Input::Button(*button),
+                Device::Controller(*which),
+            )),
+            DeviceEvent::ControllerButtonUp { which, button } => {
+                self.release(Input::Button(*button), Device::Controller(*which))
+            }
+            DeviceEvent::ControllerAxisMotion { which, axis, value } => {
+                self.update_axis(*which, *axis, *value)
+            }
+            DeviceEvent::MouseMotion(point) => self.move_pointer(*point),
+            DeviceEvent::MouseButtonDown(button, point) => self.click(*button, *point),
+            DeviceEvent::MouseWheel(notches) => {
+                self.pointer_events.push(PointerEvent::Scrolled(*notches))
+            }
+            DeviceEvent::TextInput(text) => self.type_text(text),
+        }
+    }
+    /// Moves the mouse to point.
+    pub fn move_pointer(&mut self, point: Point) {
+        self.mouse_position = Some(point);
+        self.pointer_events.push(PointerEvent::Moved(point));
+    }
+    /// Presses a mouse button at point.
+    pub fn click(&mut self, button: MouseButton, point: Point) {
+        self.mouse_position = Some(point);
+        self.pointer_events
+            .push(PointerEvent::Clicked(button, point));
+    }
+    /// Returns where the mouse was last seen, if it has been.
+    pub fn get_mouse_position(&self) -> Option<Point> {
+        self.mouse_position
+    }
+    /// Takes what the mouse did since last time, oldest first.
+    pub fn take_pointer_events(&mut self) -> Vec<PointerEvent> {
+        std::mem::take(&mut self.pointer_events)
+    }
+    /// Adds text the player typed.
+    pub fn type_text(&mut self, text: &str) {
+        self.typed.push_str(text);
+    }
+    /// Takes the text typed since last time.
+    pub fn take_typed(&mut self) -> String {
+        std::mem::take(&mut self.typed)
+    }
+    /// Presses an input. It goes into the buffer and repeats until it is
+    /// released.
+    pub fn press(&mut self, input_event: InputEvent) {
+        self.last_device = Some(input_event.get_device());
+        self.buffered = Some(input_event);
+        if !self.held.iter().any(|held| held.input_event == input_event) {
+            self.held.push(HeldInput {
+                input_event,
+                held_for: Duration::from_millis(0),
+                repeats: 0,
+            });
+        }
+    }
+    /// Releases an input so it stops repeating.
+    pub fn release(&mut self, input: Input, device: Device) {
+        self.held
+            .retain(|held| held.input_event != InputEvent::new(input, device));
+    }
+    /// Forgets everything held down, so nothing repeats until it is
+    /// pressed again. Used when the screen changes.
+    pub fn clear_held(&mut self) {
+        self.held.clear();
+    }
+    /// Moves time forward for held inputs and returns the ones that should
+    /// repeat. Inputs repeat once they have been held past the delay of
+    /// repeat, then again every interval. Several repeats in one update
+    /// count as one.
+    pub fn update(&mut self, elapsed: Duration, repeat: KeyRepeat) -> Vec<InputEvent> {
+        let mut repeated = Vec::new();
+        for held in self.held.iter_mut() {
+            held.held_for += elapsed;
+            let repeats = repeat.get_repeat_count(held.held_for);
+            if repeats > held.repeats {
+                held.repeats = repeats;
+                repeated.push(held.input_event);
+            }
+        }
+        repeated
+    }
+    /// Puts a repeat in the buffer if it is empty. Presses are never
+    /// replaced by repeats.
+    pub fn buffer_repeat(&mut self, input_event: InputEvent) {
+        if self.buffered.is_none() {
+            self.buffered = Some(input_event);
+        }
+    }
+    /// Returns the buffered input without taking it.
+    pub fn get_buffered(&self) -> Option<&InputEvent> {
+        self.buffered.as_ref()
+    }
+    /// Takes the buffered input, leaving the buffer empty.
+    pub fn take_buffered(&mut self) -> Option<InputEvent> {
+        self.buffered.take()
+    }
+    /// Presses or releases a stick direction when it crosses the
+    /// thresholds.
+    fn update_axis(&mut self, id: i32, axis: Axis, value: i16) {
+        let key = (id, axis);
+        let magnitude = (value as i32).abs();
+        if let Some(positive) = self.pressed_axes.get(&key).copied() {
+            if magnitude < AXIS_RELEASE_THRESHOLD as i32 {
+                self.pressed_axes.remove(&key);
+                self.release(Input::Axis(axis, positive), Device::Controller(id));
+            }
+            return;
+        }
+        if magnitude > AXIS_PRESS_THRESHOLD as i32 {
+            let positive = value > 0;
+            self.pressed_axes.insert(key, positive);
+            self.press(InputEvent::new(
+                Input::Axis(axis, positive),
+                Device::Controller(id),
+            ));
+        }
+    }
+}
+
+/// Opens game controllers as they are plugged in and closes them when they
+/// are unplugged. Controllers only send events while they are open.
+#[cfg(feature = "sdl")]
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    open: Vec<GameController>,
+}
+
+#[cfg(feature = "sdl")]
+impl Controllers {
+    /// Creates a new instance of Controllers and opens every controller
+    /// that is already plugged in. Ones that can't be opened are reported
+    /// in the message log.
+    pub fn new(subsystem: GameControllerSubsystem, data: &mut Data) -> Controllers {
+        let mut controllers = Controllers {
+            subsystem,
+            open: Vec::new(),
+        };
+        let count = controllers.subsystem.num_joysticks().unwrap_or(0);
+        for index in 0..count {
+            controllers.open(index, data);
+        }
+        controllers
+    }
+    /// Handles controllers being plugged in and unplugged. Other events
+    /// are ignored.
+    pub fn handle_event(&mut self, event: &Event, data: &mut Data) {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => self.open(*which, data),
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.open
+                    .retain(|controller| controller.instance_id() != *which);
+                data.get_mut_input_state().remove_controller(*which);
+            }
+            _ => {}
+        }
+    }
+    /// Opens the joystick at index if it is a game controller.
+    fn open(&mut self, index: u32, data: &mut Data) {
+        if !self.subsystem.is_game_controller(index) {
+            return;
+        }
+        match self.subsystem.open(index) {
+            Ok(controller) => {
+                let id = controller.instance_id();
+                // added events can come for controllers opened at start up
+                if self.open.iter().any(|open| open.instance_id() == id) {
+                    return;
+                }
+                data.get_mut_input_state()
+                    .add_controller(id, &controller.name());
+                self.open.push(controller);
+            }
+            Err(error) => data.add_error(&format!("Could not open controller: {}", error)),
+        }
+    }
+}
+
+/// Returns the file name of a button.
+pub fn button_name(button: Button) -> &'static str {
+    BUTTON_NAMES
+        .iter()
+        .find(|(other, _)| *other == button)
+        .map(|(_, name)| *name)
+        .unwrap()
+}
+
+/// Returns the file name of an axis.
+pub fn axis_name(axis: Axis) -> &'static str {
+    AXIS_NAMES
+        .iter()
+        .find(|(other, _)| *other == axis)
+        .map(|(_, name)| *name)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Data, Settings};
+    use crate::events::PlayerAction;
+    use crate::helper::Builder;
+
+    /// Returns the action of the buffered input, taking it.
+    fn take_action(data: &mut Data) -> Option<PlayerAction> {
+        let input_event = data.get_mut_input_state().take_buffered()?;
+        data.get_action(&input_event)
+    }
+
+    #[test]
+    fn controller_button_uses_shared_bindings() {
+        let mut data = Data::new();
+        data.get_mut_input_state()
+            .handle_event(&DeviceEvent::ControllerButtonDown {
+                which: 3,
+                button: Button::A,
+            });
+        let input_event = *data.get_input_state().get_buffered().unwrap();
+        assert_eq!(input_event.get_input(), Input::Button(Button::A));
+        assert_eq!(input_event.get_device(), Device::Controller(3));
+        assert_eq!(take_action(&mut data), Some(PlayerAction::Confirm));
+    }
+
+    #[test]
+    fn controller_button_uses_its_profile() {
+        let settings = Settings::new()
+            .add_profile("Pad", vec![(Input::Button(Button::A), PlayerAction::Back)])
+            .build()
+            .unwrap();
+        let mut data = Data::new();
+        data.set_settings(settings);
+        data.get_mut_input_state().add_controller(7, "Pad");
+        data.get_mut_input_state().add_controller(8, "Other");
+        for (which, action) in [(7, PlayerAction::Back), (8, PlayerAction::Confirm)] {
+            data.get_mut_input_state()
+                .handle_event(&DeviceEvent::ControllerButtonDown {
+                    which,
+                    button: Button::A,
+                });
+            assert_eq!(take_action(&mut data), Some(action));
+        }
+        // keys never use a controller's profile
+        data.get_mut_input_state()
+            .handle_event(&DeviceEvent::KeyDown {
+                key: Key::Return,
+                repeat: false,
+            });
+        assert_eq!(take_action(&mut data), Some(PlayerAction::Confirm));
+    }
+
+    #[test]
+    fn axis_presses_once_past_the_thresholds() {
+        let mut data = Data::new();
+        let motion = |value| DeviceEvent::ControllerAxisMotion {
+            which: 1,
+            axis: Axis::LeftX,
+            value,
+        };
+        data.get_mut_input_state()
+            .handle_event(&motion(AXIS_PRESS_THRESHOLD));
+        assert_eq!(take_action(&mut data), None);
+        data.get_mut_input_state().handle_event(&motion(20_000));
+        assert_eq!(take_action(&mut data), Some(PlayerAction::MoveRight));
+        // still pushed, or only part way back, isn't another press
+        data.get_mut_input_state().handle_event(&motion(30_000));
+        data.get_mut_input_state().handle_event(&motion(10_000));
+        assert_eq!(take_action(&mut data), None);
+        data.get_mut_input_state().handle_event(&motion(0));
+        data.get_mut_input_state().handle_event(&motion(-20_000));
+        assert_eq!(take_action(&mut data), Some(PlayerAction::MoveLeft));
+    }
+
+    #[test]
+    fn unplugging_releases_held_inputs() {
+        let mut input_state = InputState::new();
+        input_state.add_controller(2, "Pad");
+        input_state.handle_event(&DeviceEvent::ControllerButtonDown {
+            which: 2,
+            button: Button::DPadUp,
+        });
+        input_state.remove_controller(2);
+        let repeat = KeyRepeat::new(0, 1);
+        assert!(input_state
+            .update(Duration::from_millis(10), repeat)
+            .is_empty());
+        assert_eq!(input_state.get_device_name(Device::Controller(2)), None);
+    }
+
+    #[test]
+    fn records_round_trip() {
+        for input in [
+            Input::Key(Key::KpEnter),
+            Input::Key(Key::F5),
+            Input::Button(Button::DPadLeft),
+            Input::Axis(Axis::TriggerRight, true),
+        ] {
+            assert_eq!(Input::from_record(&input.to_record()), Ok(input));
+        }
+        assert_eq!(
+            Input::from_record("key return"),
+            Ok(Input::Key(Key::Return))
+        );
+        assert!(Input::from_record("key Nope").is_err());
+    }
+}
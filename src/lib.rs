@@ -6,68 +6,208 @@
 
 #![deny(missing_docs)]
 
+#[cfg(feature = "sdl")]
 extern crate sdl2;
 
+pub mod animation;
+#[cfg(feature = "sdl")]
+pub mod assets;
+pub mod camera;
+pub mod campaign;
+pub mod color;
 pub mod data;
+pub mod dungeon;
 pub mod entity;
+pub mod environment;
 pub mod events;
+pub mod fov;
 pub mod helper;
+pub mod hud;
+pub mod input;
 pub mod maps;
 pub mod math;
+pub mod messages;
+pub mod minimap;
+pub mod path;
 pub mod quests;
+#[cfg(feature = "sdl")]
 pub mod render;
+pub mod save;
+pub mod screens;
+pub mod spatial;
+#[cfg(feature = "sdl")]
 pub mod start;
+pub mod terrain;
+pub mod text;
+pub mod tween;
+pub mod ui;
 
+#[cfg(feature = "sdl")]
+use sdl2::render::Canvas;
+#[cfg(feature = "sdl")]
+use sdl2::video::{FullscreenType, Window};
+
+#[cfg(feature = "sdl")]
+use data::Data;
+#[cfg(feature = "sdl")]
 use render::*;
 
 /// The main loop. Events and such happen here. Takes the init structure
 /// to get start up information.
 // fix result here, an error enum would be cool to get past
 // dyn errors maybe dont bother with normal errors
+#[cfg(feature = "sdl")]
 pub fn main_loop(init: start::Init) -> Result<(), ()> {
     let (sdl_context, mut canvas, environment) = init.release();
     let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut data = data::Data::new();
-    data.change_entities(data.get_level().get_level_entities().to_vec());
+    let mut data = match environment.get_campaign() {
+        Some(directory) => match campaign::load_campaign(directory, environment.get_seed()) {
+            Ok(map) => data::Data::from_map(map),
+            Err(error) => {
+                eprintln!("{}", error);
+                return Err(());
+            }
+        },
+        None => data::Data::new(),
+    };
+    // saves can't be written into a directory that isn't there
+    let save_directory = environment.get_save_directory();
+    if let Err(error) = std::fs::create_dir_all(save_directory) {
+        eprintln!("{}", error);
+    }
+    data.set_save_directory(save_directory);
+    data.set_hud(environment.get_hud().clone());
+    // a missing settings file just means the defaults are used
+    let settings_path = data.get_settings_path();
+    if settings_path.exists() {
+        match data::Settings::read(&settings_path) {
+            Ok(settings) => data.set_settings(settings),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    data.set_fullscreen(environment.is_fullscreen());
 
     canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
     canvas.clear();
     canvas.present();
+    data.set_screen_size(environment.get_window_size());
+    let mut viewport = compute_viewport(&canvas, &environment);
+    data.set_screen_size(viewport.get_size());
+    data.set_tile_size(viewport.get_tile_size());
 
+    // atlases from the campaign replace ones with the same name
+    let texture_creator = canvas.texture_creator();
+    let mut assets = assets::Assets::new(&texture_creator);
+    let asset_directories =
+        std::iter::once(environment.get_assets_directory()).chain(environment.get_campaign());
+    for directory in asset_directories {
+        if directory.join(assets::ATLASES_FILE).exists() {
+            if let Err(error) = assets.load_directory(directory) {
+                eprintln!("{}", error);
+            }
+        }
+    }
+
+    // the game still works with just a keyboard if controllers can't start
+    let mut controllers = match sdl_context.game_controller() {
+        Ok(subsystem) => Some(input::Controllers::new(subsystem, &mut data)),
+        Err(error) => {
+            eprintln!("Controllers are disabled: {}", error);
+            None
+        }
+    };
+
+    let frame_length = std::time::Duration::new(0, 1_000_000_000u32 / 60);
+    let mut last_frame = std::time::Instant::now();
     'running: loop {
+        let frame_start = std::time::Instant::now();
+        let elapsed = frame_start - last_frame;
+        last_frame = frame_start;
+        data.advance_clock(elapsed);
+
         for event in event_pump.poll_iter() {
+            if let Some(controllers) = controllers.as_mut() {
+                controllers.handle_event(&event, &mut data);
+            }
             match event {
-                sdl2::event::Event::Quit { .. }
-                | sdl2::event::Event::KeyDown {
-                    keycode: Some(sdl2::keyboard::Keycode::Escape),
+                sdl2::event::Event::Quit { .. } => break 'running,
+                sdl2::event::Event::Window {
+                    win_event: sdl2::event::WindowEvent::SizeChanged(..),
                     ..
-                } => break 'running,
-                _ => events::handle_events(&event, &mut data),
+                } => {
+                    viewport = compute_viewport(&canvas, &environment);
+                    data.set_screen_size(viewport.get_size());
+                    data.set_tile_size(viewport.get_tile_size());
+                }
+                _ => {
+                    if let Some(event) = input::DeviceEvent::from_sdl(&event) {
+                        events::handle_events(&event, &mut data);
+                    }
+                }
             };
         }
+        events::update_input(&mut data, elapsed);
+        if data.is_quitting() {
+            break 'running;
+        }
+        update_fullscreen(&mut canvas, &environment, &mut data);
+        data.update_spatial_grid();
+        data.update_minimap();
+        data.update_cameras(viewport.get_tile_size(), viewport.get_size(), elapsed);
 
-        // change to one that handles menus
-        render_objects(
-            environment.get_tile_size(),
-            environment.get_window_size(),
+        render_screens(
+            viewport.get_tile_size(),
+            viewport.get_size(),
             &mut canvas,
-            &data.get_player(),
-            &data.get_entities(),
+            &mut assets,
+            &data,
         );
 
-        std::thread::sleep(std::time::Duration::new(0, 1_000_000_000u32 / 60));
+        // only sleep for what is left of the frame
+        if let Some(rest) = frame_length.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(rest);
+        }
     }
     Ok(())
 }
 
-/// WIP may be removed in the future.
-pub enum Screen {
-    // MainMenu,
-//     Settings,
-//     Inventory,
-//     Combat,
-//     Game,
-//     Dialog,
+/// Works out the viewport from the size of the canvas, which can be bigger
+/// than the window size on high DPI screens.
+#[cfg(feature = "sdl")]
+fn compute_viewport(canvas: &Canvas<Window>, environment: &environment::Environment) -> Viewport {
+    let window_size = match canvas.output_size() {
+        Ok((width, height)) => math::Dimension::new(width, height),
+        Err(_) => environment.get_window_size(),
+    };
+    Viewport::compute(
+        window_size,
+        environment.get_window_size(),
+        environment.get_tile_size(),
+        environment.get_scaling(),
+    )
+}
+
+/// Changes the window to match data when fullscreen was toggled. The
+/// window sends a size change afterwards, which updates the viewport.
+#[cfg(feature = "sdl")]
+fn update_fullscreen(
+    canvas: &mut Canvas<Window>,
+    environment: &environment::Environment,
+    data: &mut Data,
+) {
+    let fullscreen_type = if data.is_fullscreen() {
+        environment.get_fullscreen_mode().into()
+    } else {
+        FullscreenType::Off
+    };
+    if canvas.window().fullscreen_state() != fullscreen_type {
+        if let Err(error) = canvas.window_mut().set_fullscreen(fullscreen_type) {
+            // put data back so it isn't tried again every frame
+            data.add_error(&error);
+            data.toggle_fullscreen();
+        }
+    }
 }
 
 /// WIP What occurs after main loop.
@@ -2,23 +2,282 @@
 //!
 //! May move fully designed worlds and levels to level.rs
 //!
+//! A Map is a graph of levels. Every level has a LevelId that never
+//! changes, levels are joined by named Connections and a level is only
+//! instantiated from its template the first time it is visited. The type
+//! and number of a level are kept next to its template, so levels can be
+//! found without making them.
+//!
+//! Warps and connections arrive at a point, or at a named entrance of the
+//! level they go to, which is only looked up once the level is made.
+//! Generated levels use entrances since their points aren't known before.
+//!
+//! Maps are made with a MapBuilder, or loaded from a campaign directory
+//! with the campaign module.
+//!
 //! # Planned changes:
 //!
 //! - Make a premade map with multiples levels.
-//! - Make a map builder.
-//! - Find some way to better refer to levels.
 
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::dungeon::DungeonBuilder;
 use crate::entity::{self, Entity};
 use crate::helper::Builder;
 use crate::helper::BuilderError;
-use crate::math;
+use crate::math::{self, Point};
+use crate::quests::Quest;
+use crate::terrain::TileLayer;
+
+/// Creates a fresh copy of a level. Used to instantiate levels lazily.
+pub type LevelTemplate = Box<dyn Fn() -> Level>;
+
+/// A level that hasn't been made yet, with what is needed to find it.
+struct Definition {
+    level_type: LevelType,
+    number: i32,
+    template: LevelTemplate,
+}
+
+/// A stable identifier for a level. Ids are given by whoever defines the
+/// level and stay the same between runs, so they can be saved.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct LevelId(u32);
+
+impl LevelId {
+    /// Creates a new instance of LevelId.
+    pub fn new(id: u32) -> LevelId {
+        LevelId(id)
+    }
+    /// Returns the number behind the id.
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for LevelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Where a warp or connection puts whatever goes through it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Arrival {
+    /// A point on the level.
+    At(Point),
+    /// A named entrance of the level, looked up once the level is made.
+    Entrance(String),
+}
+
+/// A named, one way link from one level to another, arriving at a point or
+/// entrance.
+///
+/// Warps are how entities actually travel, connections describe the shape
+/// of the world so it can be looked up and navigated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection {
+    name: String,
+    from: LevelId,
+    to: LevelId,
+    arrival: Arrival,
+}
+
+impl Connection {
+    /// Creates a new instance of Connection.
+    pub fn new(name: &str, from: LevelId, to: LevelId, arrival: Arrival) -> Connection {
+        Connection {
+            name: name.to_string(),
+            from,
+            to,
+            arrival,
+        }
+    }
+    /// Returns the name of the connection.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the level the connection leaves from.
+    pub fn get_from(&self) -> LevelId {
+        self.from
+    }
+    /// Returns the level the connection goes to.
+    pub fn get_to(&self) -> LevelId {
+        self.to
+    }
+    /// Returns where on the destination level the connection arrives.
+    pub fn get_arrival(&self) -> &Arrival {
+        &self.arrival
+    }
+}
+
+/// Builds an instance of Map. Requires a start level and spawn point, and
+/// the start level must be one of the added levels.
+pub struct MapBuilder {
+    templates: Vec<(LevelId, Definition)>,
+    dungeons: Vec<DungeonBuilder>,
+    connections: Vec<Connection>,
+    start: Option<(LevelId, Point)>,
+    seed: u64,
+    quests: Vec<Quest>,
+}
+
+impl MapBuilder {
+    /// Adds a level. It is kept as a template and copied the first time it is visited.
+    pub fn add_level(self, level: Level) -> Self {
+        let (id, level_type, number) = (level.get_id(), level.level_type, level.level_number);
+        self.define_level(id, level_type, number, Box::new(move || level.clone()))
+    }
+    /// Short hand for add_level. Adds a level.
+    pub fn level(self, level: Level) -> Self {
+        self.add_level(level)
+    }
+    /// Defines a level by a template that creates it the first time it is visited. The
+    /// template must make a level with the given id, type and number.
+    pub fn define_level(
+        mut self,
+        id: LevelId,
+        level_type: LevelType,
+        number: i32,
+        template: LevelTemplate,
+    ) -> Self {
+        self.templates.push((
+            id,
+            Definition {
+                level_type,
+                number,
+                template,
+            },
+        ));
+        self
+    }
+    /// Adds every floor of a generated dungeon along with their connections.
+    pub fn add_dungeon(mut self, dungeon: DungeonBuilder) -> Self {
+        self.dungeons.push(dungeon);
+        self
+    }
+    /// Adds a named connection between two levels.
+    pub fn add_connection(mut self, connection: Connection) -> Self {
+        self.connections.push(connection);
+        self
+    }
+    /// Short hand for add_connection. Adds a connection.
+    pub fn connection(self, connection: Connection) -> Self {
+        self.add_connection(connection)
+    }
+    /// Adds a quest the player has from the start of every game.
+    pub fn add_quest(mut self, quest: Quest) -> Self {
+        self.quests.push(quest);
+        self
+    }
+    /// Sets the level the player starts on and where on it. Required to build.
+    pub fn set_start(mut self, level: LevelId, spawn: Point) -> Self {
+        self.start = Some((level, spawn));
+        self
+    }
+    /// Short hand for set_start. Sets the start level and spawn point.
+    pub fn start(self, level: LevelId, spawn: Point) -> Self {
+        self.set_start(level, spawn)
+    }
+    /// Sets a seed that is mixed into the seed of every added dungeon, so
+    /// the same campaign can be played with different floors. 0 leaves the
+    /// dungeon seeds alone.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Builder for MapBuilder {
+    type product = Map;
+
+    /// Creates an instance of MapBuilder with no levels.
+    fn new() -> MapBuilder {
+        MapBuilder {
+            templates: Vec::new(),
+            dungeons: Vec::new(),
+            connections: Vec::new(),
+            start: None,
+            seed: 0,
+            quests: Vec::new(),
+        }
+    }
+    /// Builds an instance of Map from MapBuilder and instantiates the start level.
+    ///
+    /// Returns an error if no start was set, two levels share an id, or a connection or
+    /// the start refer to a level that wasn't added.
+    fn build(self) -> Result<Map, BuilderError> {
+        let (start_level, spawn) = match self.start {
+            Some(start) => start,
+            None => {
+                return Err(BuilderError::new(
+                    "Need to set where the player starts, use set_start method.",
+                ))
+            }
+        };
+        let mut map = Map {
+            current_level: start_level,
+            start_level,
+            spawn,
+            templates: BTreeMap::new(),
+            levels: BTreeMap::new(),
+            connections: Vec::new(),
+            quests: self.quests,
+        };
+        for (id, definition) in self.templates.into_iter() {
+            map.define_level(
+                id,
+                definition.level_type,
+                definition.number,
+                definition.template,
+            )
+            .map_err(BuilderError::new)?;
+        }
+        for mut dungeon in self.dungeons.into_iter() {
+            // a dungeon without an exit leads back to where the first
+            // connection into it comes from, arriving at the entrance named
+            // after that connection
+            let entry = self
+                .connections
+                .iter()
+                .find(|connection| connection.get_to() == dungeon.floor_id(1));
+            if let (None, Some(entry)) = (dungeon.get_exit(), entry) {
+                dungeon = dungeon.set_exit(
+                    entry.get_from(),
+                    Arrival::Entrance(entry.get_name().to_string()),
+                );
+            }
+            let seed = dungeon.get_seed() ^ self.seed;
+            dungeon
+                .set_seed(seed)
+                .add_floors(&mut map)
+                .map_err(BuilderError::new)?;
+        }
+        for connection in self.connections.into_iter() {
+            map.connect(connection).map_err(BuilderError::new)?;
+        }
+        if !map.has_level(start_level) {
+            return Err(BuilderError::new(
+                "The start level was never added, use add_level method.",
+            ));
+        }
+        map.instantiate(start_level).map_err(BuilderError::new)?;
+        Ok(map)
+    }
+}
 
 /// A map holds the levels of the game.
 ///
-/// Different maps will be able to be loaded up in the future.
+/// Different maps can be loaded from campaign directories.
 pub struct Map {
-    current_level: Level,
-    modified_levels: Vec<Level>,
+    current_level: LevelId,
+    start_level: LevelId,
+    spawn: Point,
+    templates: BTreeMap<LevelId, Definition>,
+    levels: BTreeMap<LevelId, Level>,
+    connections: Vec<Connection>,
+    quests: Vec<Quest>,
 }
 
 /// Implementations of Map that auto create a campain. May get moved to level.rs or
@@ -41,123 +300,307 @@ impl Map {
         first_entities.append(&mut Map::get_boundry_entities(math::Rectangle::new(
             0, 0, 50, 50,
         )));
-        Map {
-            current_level: Level::new()
-                .level_type(LevelType::MainMenu)
-                .number(1)
-                .entities(first_entities)
-                .build()
-                .unwrap(),
-            modified_levels: Vec::new(),
-        }
+        Map::new()
+            .level(
+                Level::new()
+                    .id(LevelId::new(1))
+                    .name("Test Level")
+                    .level_type(LevelType::Game)
+                    .number(1)
+                    .entities(first_entities)
+                    .build()
+                    .unwrap(),
+            )
+            .start(LevelId::new(1), Point::new(5, 5))
+            .build()
+            .unwrap()
     }
 }
 
 /// Implementations that revolve around getting, setting and creating levels.
 impl Map {
+    /// Creates an instance of MapBuilder to help build an instance of Map.
+    pub fn new() -> MapBuilder {
+        MapBuilder::new()
+    }
+    /// Returns where the player starts on the start level.
+    pub fn get_spawn(&self) -> Point {
+        self.spawn
+    }
+    /// Returns the id of the level the player starts on.
+    pub fn get_start_level_id(&self) -> LevelId {
+        self.start_level
+    }
+    /// Returns the quests the player has from the start of a game.
+    pub fn get_quests(&self) -> &Vec<Quest> {
+        &self.quests
+    }
     /// Adds a game level to the map and fills it with the given entities. Tries to find a
-    /// level number that isn't taken.
-    pub fn add_game_level(&mut self, entities: Vec<Entity>) {
-        let mut counter = 1;
-        let mut is_continued = true;
-        while is_continued {
-            is_continued = false;
-            for level in self.modified_levels.iter() {
-                if level.get_level_type() == LevelType::Game {
-                    if level.get_level_number() == counter {
-                        is_continued = true;
-                        counter += 1;
-                    }
-                }
-            }
-        }
+    /// level number that isn't taken and gives it the next free id, which is returned.
+    pub fn add_game_level(&mut self, entities: Vec<Entity>) -> LevelId {
+        let counter = self.get_free_numbers(LevelType::Game, 1, 1);
+        let id = self.get_free_id();
         self.add_level(
             Level::new()
-                .number(0)
+                .id(id)
+                .number(counter)
                 .level_type(LevelType::Game)
                 .entities(entities)
                 .build()
                 .unwrap(),
         )
         .unwrap();
-    }
-    /// Gets a reference to the current level.
-    pub fn get_current_level(&self) -> &Level {
-        &self.current_level
+        id
     }
     /// Adds a given level. Use over add_game_level if you want something specific or a
     /// Menu.
+    ///
+    /// The level is kept as a template, so it is copied the first time it is visited.
     pub fn add_level(&mut self, level: Level) -> Result<(), &'static str> {
-        if self.is_modified(&level) {
+        let (id, level_type, number) = (level.get_id(), level.level_type, level.level_number);
+        self.define_level(id, level_type, number, Box::new(move || level.clone()))
+    }
+    /// Defines a level by a template that creates it. The template is not called until the
+    /// level is first needed, which keeps large campaigns and generated levels cheap.
+    ///
+    /// The template must create a level with the given id, type and number, which are
+    /// kept so the level can be found before it is made.
+    pub fn define_level(
+        &mut self,
+        id: LevelId,
+        level_type: LevelType,
+        number: i32,
+        template: LevelTemplate,
+    ) -> Result<(), &'static str> {
+        if self.has_level(id) {
             return Err("The Level you are trying to add already exists.");
         }
-        self.modified_levels.push(level);
+        self.templates.insert(
+            id,
+            Definition {
+                level_type,
+                number,
+                template,
+            },
+        );
+        Ok(())
+    }
+    /// Adds a named connection between two levels.
+    pub fn connect(&mut self, connection: Connection) -> Result<(), &'static str> {
+        if !self.has_level(connection.get_from()) || !self.has_level(connection.get_to()) {
+            return Err("Both levels of a connection must be added first.");
+        }
+        self.connections.push(connection);
         Ok(())
     }
-    /// Changes the level with the given one. If the current level is not in the modified or prebuilt list
-    /// of levels, it adds it there.
-    pub fn change_level(&mut self, level: Level) -> bool {
-        if level == self.current_level {
-            return false;
+    /// Returns true if a level with the given id has been added.
+    pub fn has_level(&self, id: LevelId) -> bool {
+        self.templates.contains_key(&id)
+    }
+    /// Returns the ids of every level added, in order.
+    pub fn get_level_ids(&self) -> Vec<LevelId> {
+        self.templates.keys().cloned().collect()
+    }
+    /// Returns the lowest id not used by any level.
+    pub fn get_free_id(&self) -> LevelId {
+        let mut id = 1;
+        while self.has_level(LevelId::new(id)) {
+            id += 1;
         }
-        if self.is_not_modified(&self.current_level) {
-            self.modified_levels.push(self.current_level.clone());
-        } else {
-            for modified_level in self.modified_levels.iter_mut() {
-                if &mut self.current_level == modified_level {
-                    modified_level.level_entities = Vec::new();
-                    modified_level
-                        .level_entities
-                        .append(&mut self.current_level.level_entities);
-                    break;
-                }
+        LevelId::new(id)
+    }
+    /// Returns the lowest number from `from` up where it and the count - 1
+    /// numbers after it aren't used by a level of the given type.
+    pub fn get_free_numbers(&self, level_type: LevelType, from: i32, count: i32) -> i32 {
+        let taken: HashSet<i32> = self
+            .templates
+            .values()
+            .filter(|definition| definition.level_type == level_type)
+            .map(|definition| definition.number)
+            .collect();
+        let mut first = from;
+        while (first..first + count).any(|number| taken.contains(&number)) {
+            first += 1;
+        }
+        first
+    }
+    /// Returns a reference to a level if it has been instantiated.
+    pub fn get_level(&self, id: LevelId) -> Option<&Level> {
+        self.levels.get(&id)
+    }
+    /// Returns a mutable reference to a level, instantiating it first if needed.
+    pub fn get_mut_level(&mut self, id: LevelId) -> Option<&mut Level> {
+        self.instantiate(id).ok()?;
+        self.levels.get_mut(&id)
+    }
+    /// Creates a fresh copy of the level with the given id from its template, without
+    /// instantiating it in the map. Useful for menu levels that are never played on.
+    pub fn create_level(&self, id: LevelId) -> Option<Level> {
+        self.templates
+            .get(&id)
+            .map(|definition| (definition.template)())
+    }
+    /// Returns every level that has been instantiated, in order of id.
+    pub fn get_levels(&self) -> Vec<&Level> {
+        self.levels.values().collect()
+    }
+    /// Finds the id of a level by its type and number, without making any levels.
+    pub fn find_level(&self, level_type: LevelType, number: i32) -> Option<LevelId> {
+        self.templates
+            .iter()
+            .find(|(_, definition)| {
+                definition.level_type == level_type && definition.number == number
+            })
+            .map(|(id, _)| *id)
+    }
+    /// Returns the point arrival puts something at on the level with the given id. The
+    /// level is instantiated first if it needs to be, to look up entrances.
+    ///
+    /// Returns an error if there is no level with the id or it has no such entrance.
+    pub fn find_arrival(&mut self, id: LevelId, arrival: &Arrival) -> Result<Point, &'static str> {
+        match arrival {
+            Arrival::At(point) => Ok(*point),
+            Arrival::Entrance(name) => {
+                self.instantiate(id)?;
+                self.levels[&id]
+                    .get_entrance(name)
+                    .ok_or("The level has no entrance with that name.")
             }
         }
-        self.current_level = level;
-        true
+    }
+    /// Returns every connection leaving the given level.
+    pub fn get_connections_from(&self, id: LevelId) -> Vec<&Connection> {
+        self.connections
+            .iter()
+            .filter(|connection| connection.get_from() == id)
+            .collect()
+    }
+    /// Finds a connection leaving the given level by name.
+    pub fn find_connection(&self, from: LevelId, name: &str) -> Option<&Connection> {
+        self.connections
+            .iter()
+            .find(|connection| connection.get_from() == from && connection.get_name() == name)
+    }
+    /// Returns every connection in the map.
+    pub fn get_connections(&self) -> &Vec<Connection> {
+        &self.connections
+    }
+    /// Gets the id of the current level.
+    pub fn get_current_level_id(&self) -> LevelId {
+        self.current_level
+    }
+    /// Gets a reference to the current level.
+    pub fn get_current_level(&self) -> &Level {
+        &self.levels[&self.current_level]
+    }
+    /// Gets a mutable reference to the current level.
+    pub fn get_mut_current_level(&mut self) -> &mut Level {
+        self.levels.get_mut(&self.current_level).unwrap()
+    }
+    /// Changes the current level to the level with the given id, instantiating it if it
+    /// has not been visited yet. Levels that have been visited keep their entities and
+    /// explored tiles.
+    ///
+    /// Returns false if it is already the current level and an error if there is no level
+    /// with the id.
+    pub fn change_level(&mut self, id: LevelId) -> Result<bool, &'static str> {
+        if id == self.current_level {
+            return Ok(false);
+        }
+        self.instantiate(id)?;
+        self.current_level = id;
+        Ok(true)
+    }
+    /// Throws away every instantiated level, so levels start fresh from their templates the
+    /// next time they are needed. The current level is instantiated again straight away.
+    pub fn reset(&mut self) {
+        self.levels.clear();
+        // it was made before, so it can be made again
+        self.instantiate(self.current_level).unwrap();
+    }
+    /// Resets the map and goes back to the start level, for starting a new game.
+    pub fn restart(&mut self) {
+        self.current_level = self.start_level;
+        self.reset();
+    }
+    /// Creates the level from its template if it doesn't exist yet.
+    ///
+    /// Returns an error if the level was never added, or the template made a level with a
+    /// different id, type or number than it was defined with.
+    fn instantiate(&mut self, id: LevelId) -> Result<(), &'static str> {
+        if self.levels.contains_key(&id) {
+            return Ok(());
+        }
+        let definition = self
+            .templates
+            .get(&id)
+            .ok_or("There is no level with that id in the map.")?;
+        let level = (definition.template)();
+        if level.get_id() != id
+            || level.get_level_type() != definition.level_type
+            || level.get_level_number() != definition.number
+        {
+            return Err("A level template made a level different to how it was defined.");
+        }
+        self.levels.insert(id, level);
+        Ok(())
     }
 }
 
 impl Map {
     /// An associated function that returns 4 wall entities to use as level borders that
-    /// can be appended to levels entities.
+    /// can be appended to levels entities. They are indestructible, so nothing can break
+    /// out of the level.
     pub fn get_boundry_entities(boundry: math::Rectangle) -> Vec<Entity> {
         vec![
-            Entity::new_wall(boundry.x(), boundry.y(), boundry.width(), 1),
-            Entity::new_wall(boundry.x(), boundry.y() + 1, 1, boundry.height() - 2),
-            Entity::new_wall(boundry.x_max(), boundry.y() + 1, 1, boundry.height() - 2),
-            Entity::new_wall(boundry.x(), boundry.y_max(), boundry.width(), 1),
+            Entity::new_indestructible_wall(boundry.x(), boundry.y(), boundry.width(), 1),
+            Entity::new_indestructible_wall(boundry.x(), boundry.y() + 1, 1, boundry.height() - 2),
+            Entity::new_indestructible_wall(
+                boundry.x_max(),
+                boundry.y() + 1,
+                1,
+                boundry.height() - 2,
+            ),
+            Entity::new_indestructible_wall(boundry.x(), boundry.y_max(), boundry.width(), 1),
         ]
     }
-    fn is_modified(&self, level: &Level) -> bool {
-        for modified_level in self.modified_levels.iter() {
-            if level == modified_level {
-                return true;
-            }
-        }
-        return false;
-    }
-    fn is_not_modified(&self, level: &Level) -> bool {
-        !self.is_modified(level)
-    }
-    fn move_current_to_modified(&mut self) {
-        if self.is_modified(&self.current_level) {
-            return ();
-        }
-        self.modified_levels.push(self.current_level.clone());
-    }
 }
 
-/// Builds a instance of Level. Requires a set number and entities list.
+/// Builds a instance of Level. Requires a set id, number and entities list.
 ///
 /// Defaults a game LevelType.
 pub struct LevelBuilder {
+    id: Option<LevelId>,
+    name: Option<String>,
     level_type: LevelType,
     number: Option<i32>,
     entities: Option<Vec<Entity>>,
+    spawn: Option<Point>,
+    entrances: Vec<(String, Point)>,
+    tiles: TileLayer,
 }
 
 impl LevelBuilder {
+    /// Sets the id of the Level. Required to build.
+    ///
+    /// The id is how the level is found in a Map, so it should be unique and never change.
+    pub fn set_id(mut self, id: LevelId) -> Self {
+        self.id = Some(id);
+        self
+    }
+    /// Short hand for set_id. Sets the id.
+    pub fn id(self, id: LevelId) -> Self {
+        self.set_id(id)
+    }
+    /// Sets the name shown to the player. Defaults to the level type and number.
+    pub fn set_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+    /// Short hand for set_name. Sets the name.
+    pub fn name(self, name: &str) -> Self {
+        self.set_name(name)
+    }
     /// Sets the LevelType of the Level. Default is LevelType::Game.
     pub fn set_level_type(mut self, level_type: LevelType) -> Self {
         self.level_type = level_type;
@@ -169,8 +612,7 @@ impl LevelBuilder {
     }
     /// Sets the number of the Level. Required to build.
     ///
-    /// Level, along with LevelType, gives the level a unique
-    /// identifier and gives an order to the games levels.
+    /// Level, along with LevelType, gives an order to the games levels.
     pub fn set_number(mut self, number: i32) -> Self {
         self.number = Some(number);
         self
@@ -192,6 +634,37 @@ impl LevelBuilder {
     pub fn entities(self, entities: Vec<Entity>) -> Self {
         self.set_entities(entities)
     }
+    /// Sets where the player appears when they enter the level without a warp. Menu
+    /// levels need one, game levels use the map's spawn or a warp's point instead.
+    pub fn set_spawn(mut self, spawn: Point) -> Self {
+        self.spawn = Some(spawn);
+        self
+    }
+    /// Short hand for set_spawn. Sets the spawn point.
+    pub fn spawn(self, spawn: Point) -> Self {
+        self.set_spawn(spawn)
+    }
+    /// Names a point that warps and connections can arrive at. One with the same name is
+    /// replaced.
+    pub fn set_entrance(mut self, name: &str, point: Point) -> Self {
+        self.entrances.retain(|(other, _)| other != name);
+        self.entrances.push((name.to_string(), point));
+        self
+    }
+    /// Short hand for set_entrance. Names an entrance.
+    pub fn entrance(self, name: &str, point: Point) -> Self {
+        self.set_entrance(name, point)
+    }
+    /// Sets the terrain under the entities. Default has no terrain, so
+    /// every tile is open.
+    pub fn set_tiles(mut self, tiles: TileLayer) -> Self {
+        self.tiles = tiles;
+        self
+    }
+    /// Short hand for set_tiles. Sets the tile layer.
+    pub fn tiles(self, tiles: TileLayer) -> Self {
+        self.set_tiles(tiles)
+    }
 }
 
 impl Builder for LevelBuilder {
@@ -200,15 +673,25 @@ impl Builder for LevelBuilder {
     /// Creates an instance of LevelBuilder.
     fn new() -> LevelBuilder {
         LevelBuilder {
+            id: None,
+            name: None,
             level_type: LevelType::Game,
             number: None,
             entities: None,
+            spawn: None,
+            entrances: Vec::new(),
+            tiles: TileLayer::new(),
         }
     }
     /// Builds an instance of Level from LevelBuilder.
     ///
-    /// Returns an error if the level number or entities were not set.
+    /// Returns an error if the level id, number or entities were not set.
     fn build(self) -> Result<Level, BuilderError> {
+        if self.id.is_none() {
+            return Err(BuilderError::new(
+                "Need to include a level id, use set_id method.",
+            ));
+        }
         if self.number.is_none() {
             return Err(BuilderError::new(
                 "Need to include a level number, use set_level_number method.",
@@ -219,24 +702,46 @@ impl Builder for LevelBuilder {
                 "Need to add entities, use set_entities method.",
             ));
         }
+        let level_number = self.number.unwrap();
+        let name = match self.name {
+            Some(name) => name,
+            None => format!("{} {}", self.level_type.get_name(), level_number),
+        };
         Ok(Level {
+            id: self.id.unwrap(),
+            name,
             level_type: self.level_type,
-            level_number: self.number.unwrap(),
+            level_number,
+            bounds: get_bounds(self.entities.as_ref().unwrap()),
             level_entities: self.entities.unwrap(),
+            spawn: self.spawn,
+            entrances: self.entrances,
+            tiles: self.tiles,
+            explored: HashSet::new(),
         })
     }
 }
 
 /// Represents a level.
 ///
-/// A level is simply a LevelType such as, game or menu; a level number that
-/// is unique for the level type; and a list of the entities that fill the levels
-/// map.
+/// A level is simply a LevelId that identifies it; a LevelType such as, game or
+/// menu; a level number that orders it within the level type; and a list of the
+/// entities that fill the levels map.
+///
+/// Levels also have a layer of terrain under the entities, and remember which
+/// tiles the player has seen.
 #[derive(Clone, Debug)]
 pub struct Level {
+    id: LevelId,
+    name: String,
     level_type: LevelType,
     level_number: i32,
     level_entities: Vec<Entity>,
+    bounds: Option<math::Rectangle>,
+    spawn: Option<Point>,
+    entrances: Vec<(String, Point)>,
+    tiles: TileLayer,
+    explored: HashSet<Point>,
 }
 
 impl Level {
@@ -244,6 +749,14 @@ impl Level {
     pub fn new() -> LevelBuilder {
         LevelBuilder::new()
     }
+    /// Returns the id of the level.
+    pub fn get_id(&self) -> LevelId {
+        self.id
+    }
+    /// Returns the name of the level.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
     /// Returns the level number.
     pub fn get_level_number(&self) -> i32 {
         self.level_number
@@ -256,13 +769,59 @@ impl Level {
     pub fn get_level_entities(&self) -> &Vec<Entity> {
         &self.level_entities
     }
+    /// Returns the smallest rectangle holding every entity of the level, or
+    /// None if it has none. With walls around the level this is everything
+    /// worth showing.
+    pub fn get_bounds(&self) -> Option<math::Rectangle> {
+        self.bounds
+    }
+    /// Returns where the player appears when entering the level without a warp, if the
+    /// level has its own spawn.
+    pub fn get_spawn(&self) -> Option<Point> {
+        self.spawn
+    }
+    /// Returns the point of the entrance with the given name, if the level has one.
+    pub fn get_entrance(&self, name: &str) -> Option<Point> {
+        self.entrances
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, point)| *point)
+    }
+    /// Returns every named entrance of the level.
+    pub fn get_entrances(&self) -> &Vec<(String, Point)> {
+        &self.entrances
+    }
+    /// Returns the terrain under the entities.
+    pub fn get_tiles(&self) -> &TileLayer {
+        &self.tiles
+    }
+    /// Returns a reference to the tiles the player has seen on this level.
+    pub fn get_explored(&self) -> &HashSet<Point> {
+        &self.explored
+    }
+    /// Returns true if the player has seen the tile at point.
+    pub fn is_explored(&self, point: Point) -> bool {
+        self.explored.contains(&point)
+    }
+    /// Marks all the given tiles as seen.
+    pub fn explore(&mut self, points: Vec<Point>) {
+        self.explored.extend(points);
+    }
+    /// Replaces the tiles the player has seen.
+    pub fn set_explored(&mut self, explored: HashSet<Point>) {
+        self.explored = explored;
+    }
+    /// Replaces the level's vector of entities.
+    pub fn set_level_entities(&mut self, entities: Vec<Entity>) {
+        self.bounds = get_bounds(&entities);
+        self.level_entities = entities;
+    }
 }
 
 impl PartialEq for Level {
-    /// Checks the level type and number for equality. The entities are ignored.
+    /// Checks the level ids for equality. Everything else is ignored.
     fn eq(&self, other: &Self) -> bool {
-        self.get_level_type() == other.get_level_type()
-            && self.get_level_number() == other.get_level_number()
+        self.get_id() == other.get_id()
     }
 }
 
@@ -278,3 +837,210 @@ pub enum LevelType {
     /// enemies and more.
     Game,
 }
+
+impl LevelType {
+    /// Returns the name of the level type, used when writing files.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            LevelType::MainMenu => "MainMenu",
+            LevelType::Settings => "Settings",
+            LevelType::Game => "Game",
+        }
+    }
+    /// Returns the level type with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<LevelType> {
+        match name {
+            "MainMenu" => Some(LevelType::MainMenu),
+            "Settings" => Some(LevelType::Settings),
+            "Game" => Some(LevelType::Game),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the smallest rectangle holding every entity.
+fn get_bounds(entities: &[Entity]) -> Option<math::Rectangle> {
+    entities.iter().map(|entity| entity.get_rectangle()).fold(
+        None,
+        |bounds, rectangle| match bounds {
+            Some(bounds) => Some(rectangle.union(bounds)),
+            None => Some(rectangle),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{self, Dungeon};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn game_level(id: u32, number: i32) -> Level {
+        Level::new()
+            .id(LevelId::new(id))
+            .number(number)
+            .entities(Vec::new())
+            .build()
+            .unwrap()
+    }
+
+    /// A map with levels 1 to 3, where every time a template is called it
+    /// is counted.
+    fn counted_map(calls: &Rc<Cell<u32>>) -> Map {
+        (1..=3)
+            .fold(Map::new(), |builder, id| {
+                let calls = Rc::clone(calls);
+                builder.define_level(
+                    LevelId::new(id),
+                    LevelType::Game,
+                    id as i32,
+                    Box::new(move || {
+                        calls.set(calls.get() + 1);
+                        game_level(id, id as i32)
+                    }),
+                )
+            })
+            .start(LevelId::new(1), Point::new(1, 1))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn finding_levels_makes_none() {
+        let calls = Rc::new(Cell::new(0));
+        let mut map = counted_map(&calls);
+        // only the start level
+        assert_eq!(calls.get(), 1);
+        assert_eq!(map.find_level(LevelType::Game, 3), Some(LevelId::new(3)));
+        assert_eq!(map.find_level(LevelType::Settings, 1), None);
+        assert_eq!(map.add_game_level(Vec::new()), LevelId::new(4));
+        assert_eq!(map.get_level(LevelId::new(4)), None);
+        assert_eq!(map.find_level(LevelType::Game, 4), Some(LevelId::new(4)));
+        assert_eq!(calls.get(), 1);
+        map.change_level(LevelId::new(3)).unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn templates_must_make_what_they_define() {
+        let mut map = Map::new()
+            .level(game_level(1, 1))
+            .define_level(
+                LevelId::new(2),
+                LevelType::Game,
+                2,
+                Box::new(|| game_level(5, 2)),
+            )
+            .start(LevelId::new(1), Point::new(1, 1))
+            .build()
+            .unwrap();
+        assert!(map.change_level(LevelId::new(2)).is_err());
+        assert_eq!(map.get_current_level_id(), LevelId::new(1));
+        assert!(map.get_mut_level(LevelId::new(2)).is_none());
+    }
+
+    #[test]
+    fn dungeon_arrivals_are_found_on_the_floor() {
+        let mut map = Map::new()
+            .level(game_level(1, 1))
+            .add_dungeon(
+                Dungeon::new()
+                    .set_first_id(LevelId::new(10))
+                    .set_floor_count(3),
+            )
+            .start(LevelId::new(1), Point::new(1, 1))
+            .build()
+            .unwrap();
+        assert!(map.get_level(LevelId::new(11)).is_none());
+        let down = map
+            .find_connection(LevelId::new(10), "down")
+            .unwrap()
+            .clone();
+        let arrival = map.find_arrival(down.get_to(), down.get_arrival()).unwrap();
+        let floor = map.get_level(LevelId::new(11)).unwrap();
+        assert_eq!(floor.get_entrance(dungeon::UP_ENTRANCE), Some(arrival));
+        // the floor it leads from is still not made
+        assert!(map.get_level(LevelId::new(10)).is_none());
+        let missing = Arrival::Entrance("nowhere".to_string());
+        assert!(map.find_arrival(LevelId::new(11), &missing).is_err());
+    }
+
+    #[test]
+    fn dungeon_floors_get_their_own_numbers() {
+        let mut map = Map::new()
+            .level(game_level(1, 1))
+            .add_dungeon(
+                Dungeon::new()
+                    .set_first_id(LevelId::new(10))
+                    .set_floor_count(2),
+            )
+            .add_dungeon(
+                Dungeon::new()
+                    .set_first_id(LevelId::new(20))
+                    .set_floor_count(2),
+            )
+            .start(LevelId::new(1), Point::new(1, 1))
+            .build()
+            .unwrap();
+        let found: Vec<Option<LevelId>> = (1..=5)
+            .map(|number| map.find_level(LevelType::Game, number))
+            .collect();
+        let expected = [1, 10, 11, 20, 21].iter();
+        assert_eq!(
+            found,
+            expected
+                .map(|id| Some(LevelId::new(*id)))
+                .collect::<Vec<_>>()
+        );
+        // the floors are made with the numbers they were defined with
+        let floor = map.get_mut_level(LevelId::new(21)).unwrap();
+        assert_eq!(floor.get_level_number(), 5);
+        assert_eq!(floor.get_name(), "Floor 2");
+        assert_eq!(map.get_free_numbers(LevelType::Game, 1, 1), 6);
+        assert_eq!(map.get_free_numbers(LevelType::Settings, 1, 3), 1);
+    }
+
+    #[test]
+    fn dungeons_lead_back_up_to_their_entry() {
+        let cellar = Level::new()
+            .id(LevelId::new(2))
+            .number(2)
+            .entities(Vec::new())
+            .entrance("down", Point::new(24, 3))
+            .build()
+            .unwrap();
+        let mut map = Map::new()
+            .level(cellar)
+            .add_dungeon(Dungeon::new().set_first_id(LevelId::new(100)))
+            .connection(Connection::new(
+                "down",
+                LevelId::new(2),
+                LevelId::new(100),
+                Arrival::Entrance(dungeon::UP_ENTRANCE.to_string()),
+            ))
+            .start(LevelId::new(2), Point::new(1, 1))
+            .build()
+            .unwrap();
+        let up = map
+            .find_connection(LevelId::new(100), "up")
+            .unwrap()
+            .clone();
+        assert_eq!(up.get_to(), LevelId::new(2));
+        assert_eq!(
+            map.find_arrival(up.get_to(), up.get_arrival()),
+            Ok(Point::new(24, 3))
+        );
+        map.change_level(LevelId::new(100)).unwrap();
+        let warps: Vec<_> = map
+            .get_current_level()
+            .get_level_entities()
+            .iter()
+            .filter_map(|entity| entity.get_abilities().is_warp())
+            .collect();
+        assert_eq!(
+            warps,
+            vec![(LevelId::new(2), Arrival::Entrance("down".to_string()))]
+        );
+    }
+}
@@ -23,7 +23,7 @@ pub trait TwoDimensional {
 ///
 /// May be fused with Dimension to become Point<T> though risks
 /// clarity.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Point {
     x: i32,
     y: i32,
@@ -177,6 +177,86 @@ impl Rectangle {
         }
         false
     }
+    /// Returns true if point is inside the Rectangle.
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.x_min()..=self.x_max()).contains(&point.get_x())
+            && (self.y_min()..=self.y_max()).contains(&point.get_y())
+    }
+    /// Returns every point inside the Rectangle, a row at a time from the
+    /// top.
+    pub fn get_points(&self) -> impl Iterator<Item = Point> {
+        let (x_min, x_max) = (self.x_min(), self.x_max());
+        (self.y_min()..=self.y_max())
+            .flat_map(move |y| (x_min..=x_max).map(move |x| Point::new(x, y)))
+    }
+    /// Returns the smallest Rectangle that holds both rectangles.
+    pub fn union(&self, other_rectangle: Rectangle) -> Rectangle {
+        let x = self.x_min().min(other_rectangle.x_min());
+        let y = self.y_min().min(other_rectangle.y_min());
+        let x_max = self.x_max().max(other_rectangle.x_max());
+        let y_max = self.y_max().max(other_rectangle.y_max());
+        Rectangle::new(x, y, (x_max - x + 1) as u32, (y_max - y + 1) as u32)
+    }
+    /// Returns the part of the Rectangle inside the other Rectangle, if they
+    /// overlap.
+    pub fn intersection(&self, other_rectangle: Rectangle) -> Option<Rectangle> {
+        let x = self.x_min().max(other_rectangle.x_min());
+        let y = self.y_min().max(other_rectangle.y_min());
+        let x_max = self.x_max().min(other_rectangle.x_max());
+        let y_max = self.y_max().min(other_rectangle.y_max());
+        if x > x_max || y > y_max {
+            return None;
+        }
+        Some(Rectangle::new(
+            x,
+            y,
+            (x_max - x + 1) as u32,
+            (y_max - y + 1) as u32,
+        ))
+    }
+    /// Returns what is left of the Rectangle with the other Rectangle cut
+    /// out, as up to 4 rectangles: the rows above and below it, then what is
+    /// left and right of it.
+    pub fn subtract(&self, other_rectangle: Rectangle) -> Vec<Rectangle> {
+        let hole = match self.intersection(other_rectangle) {
+            Some(hole) => hole,
+            None => return vec![*self],
+        };
+        let mut pieces = Vec::new();
+        if hole.y_min() > self.y_min() {
+            pieces.push(Rectangle::new(
+                self.x(),
+                self.y(),
+                self.width(),
+                (hole.y_min() - self.y_min()) as u32,
+            ));
+        }
+        if hole.y_max() < self.y_max() {
+            pieces.push(Rectangle::new(
+                self.x(),
+                hole.y_max() + 1,
+                self.width(),
+                (self.y_max() - hole.y_max()) as u32,
+            ));
+        }
+        if hole.x_min() > self.x_min() {
+            pieces.push(Rectangle::new(
+                self.x(),
+                hole.y(),
+                (hole.x_min() - self.x_min()) as u32,
+                hole.height(),
+            ));
+        }
+        if hole.x_max() < self.x_max() {
+            pieces.push(Rectangle::new(
+                hole.x_max() + 1,
+                hole.y(),
+                (self.x_max() - hole.x_max()) as u32,
+                hole.height(),
+            ));
+        }
+        pieces
+    }
     /// Returns false if the Rectangle is colliding with the given other Rectangle.
     /// Else returns true.
     pub fn is_not_colliding(&self, other_rectangle: Rectangle) -> bool {
@@ -203,3 +283,74 @@ impl Rectangle {
         self.x()
     }
 }
+
+#[cfg(feature = "sdl")]
+impl From<Rectangle> for sdl2::rect::Rect {
+    fn from(rectangle: Rectangle) -> sdl2::rect::Rect {
+        sdl2::rect::Rect::new(
+            rectangle.x(),
+            rectangle.y(),
+            rectangle.width(),
+            rectangle.height(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Checks the pieces don't overlap and cover exactly what is left of
+    /// whole once hole is cut out.
+    fn assert_cut(whole: Rectangle, hole: Rectangle, pieces: &[Rectangle]) {
+        let mut covered = HashSet::new();
+        for piece in pieces.iter() {
+            for point in piece.get_points() {
+                assert!(covered.insert(point), "{:?} is in two pieces", point);
+            }
+        }
+        let left: HashSet<Point> = whole
+            .get_points()
+            .filter(|point| !hole.contains_point(*point))
+            .collect();
+        assert_eq!(covered, left);
+    }
+
+    #[test]
+    fn subtract_a_corner() {
+        let whole = Rectangle::new(0, 0, 4, 3);
+        let hole = Rectangle::new(3, 2, 3, 3);
+        let pieces = whole.subtract(hole);
+        assert_eq!(pieces.len(), 2);
+        assert_cut(whole, hole, &pieces);
+    }
+
+    #[test]
+    fn subtract_an_edge() {
+        let whole = Rectangle::new(0, 0, 5, 1);
+        let hole = Rectangle::new(0, 0, 1, 1);
+        assert_eq!(whole.subtract(hole), vec![Rectangle::new(1, 0, 4, 1)]);
+        let hole = Rectangle::new(-2, 1, 4, 1);
+        let whole = Rectangle::new(0, 0, 3, 3);
+        let pieces = whole.subtract(hole);
+        assert_eq!(pieces.len(), 3);
+        assert_cut(whole, hole, &pieces);
+    }
+
+    #[test]
+    fn subtract_the_middle() {
+        let whole = Rectangle::new(-2, -2, 5, 5);
+        let hole = Rectangle::new(0, 0, 1, 1);
+        let pieces = whole.subtract(hole);
+        assert_eq!(pieces.len(), 4);
+        assert_cut(whole, hole, &pieces);
+    }
+
+    #[test]
+    fn subtract_nothing_or_everything() {
+        let whole = Rectangle::new(0, 0, 3, 3);
+        assert_eq!(whole.subtract(Rectangle::new(5, 5, 1, 1)), vec![whole]);
+        assert!(whole.subtract(Rectangle::new(-1, -1, 5, 5)).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+//! The message log, telling the player what happened.
+//!
+//! Every message is stamped with the turn it happened on and has a
+//! category, like combat or travel, which gives it its colour unless it
+//! asks for its own. The log keeps the newest MESSAGE_LIMIT messages and is
+//! saved with the game, one record per message:
+//!
+//! ```text
+//! message 12 combat - The slime lashes out at you.
+//! message 14 item 255,215,0 You pick up a gold key.
+//! ```
+//!
+//! The HUD shows the last few lines and the history screen scrolls through
+//! the rest.
+//!
+//! # Planned changes:
+//!
+//! - Folding repeated messages into one with a count.
+//! - Filtering the history by category.
+
+use crate::color::Color;
+
+/// How many messages are kept before the oldest are dropped.
+pub const MESSAGE_LIMIT: usize = 100;
+/// The colour errors are drawn in.
+pub const ERROR_COLOR: Color = Color {
+    r: 240,
+    g: 70,
+    b: 70,
+    a: 255,
+};
+
+/// What a message is about.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MessageCategory {
+    /// Hits, misses and deaths.
+    Combat,
+    /// Picking up and using things.
+    Item,
+    /// Lines said by other entities.
+    Dialog,
+    /// Going between levels.
+    Travel,
+    /// Saving, loading, errors and looking at things.
+    System,
+}
+
+impl MessageCategory {
+    /// Every category.
+    pub const ALL: [MessageCategory; 5] = [
+        MessageCategory::Combat,
+        MessageCategory::Item,
+        MessageCategory::Dialog,
+        MessageCategory::Travel,
+        MessageCategory::System,
+    ];
+
+    /// Returns the name used in saves.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            MessageCategory::Combat => "combat",
+            MessageCategory::Item => "item",
+            MessageCategory::Dialog => "dialog",
+            MessageCategory::Travel => "travel",
+            MessageCategory::System => "system",
+        }
+    }
+    /// Returns the category with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<MessageCategory> {
+        MessageCategory::ALL
+            .iter()
+            .find(|category| category.get_name() == name)
+            .copied()
+    }
+    /// Returns the colour messages in the category are drawn in.
+    pub fn get_color(&self) -> Color {
+        match self {
+            MessageCategory::Combat => Color::RGB(230, 90, 80),
+            MessageCategory::Item => Color::RGB(230, 200, 60),
+            MessageCategory::Dialog => Color::RGB(150, 200, 240),
+            MessageCategory::Travel => Color::RGB(130, 210, 130),
+            MessageCategory::System => Color::RGB(200, 200, 200),
+        }
+    }
+}
+
+/// Something that happened, for the player to read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    turn: u32,
+    category: MessageCategory,
+    text: String,
+    color: Option<Color>,
+}
+
+impl Message {
+    /// Creates a new instance of Message, drawn in its category's colour.
+    /// Messages are one line, so line breaks in text become spaces and
+    /// trailing spaces are dropped, so the message saves as a single record.
+    pub fn new(turn: u32, category: MessageCategory, text: &str) -> Message {
+        Message {
+            turn,
+            category,
+            text: text
+                .trim_end()
+                .replace("\r\n", " ")
+                .replace(&['\n', '\r'][..], " "),
+            color: None,
+        }
+    }
+    /// Draws the message in color instead of its category's colour.
+    pub fn set_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+    /// Returns the turn the message happened on.
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+    /// Returns what the message is about.
+    pub fn get_category(&self) -> MessageCategory {
+        self.category
+    }
+    /// Returns what the message says.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+    /// Returns the colour the message is drawn in.
+    pub fn get_color(&self) -> Color {
+        self.color.unwrap_or_else(|| self.category.get_color())
+    }
+    /// Writes the message as `turn category color text`, with `-` for the
+    /// category's colour.
+    pub fn to_record(&self) -> String {
+        let color = match self.color {
+            Some(color) => format!("{},{},{}", color.r, color.g, color.b),
+            None => "-".to_string(),
+        };
+        format!(
+            "{} {} {} {}",
+            self.turn,
+            self.category.get_name(),
+            color,
+            self.text
+        )
+    }
+    /// Reads a message written by to_record.
+    pub fn from_record(record: &str) -> Result<Message, &'static str> {
+        let mut words = record.trim().splitn(4, ' ');
+        let turn = words
+            .next()
+            .and_then(|turn| turn.parse().ok())
+            .ok_or("Expected the turn of the message.")?;
+        let category = words
+            .next()
+            .and_then(MessageCategory::from_name)
+            .ok_or("Unknown message category.")?;
+        let color = match words.next() {
+            Some("-") => None,
+            Some(color) => Some(parse_color(color)?),
+            None => return Err("Expected the colour of the message."),
+        };
+        let text = words.next().unwrap_or("");
+        Ok(Message {
+            turn,
+            category,
+            text: text.to_string(),
+            color,
+        })
+    }
+}
+
+/// Reads a colour written as r,g,b.
+fn parse_color(word: &str) -> Result<Color, &'static str> {
+    let values: Vec<u8> = word
+        .split(',')
+        .map(|value| value.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "Expected a colour like 255,215,0.")?;
+    match values[..] {
+        [red, green, blue] => Ok(Color::RGB(red, green, blue)),
+        _ => Err("Expected a colour like 255,215,0."),
+    }
+}
+
+/// The newest messages, oldest first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageLog {
+    messages: Vec<Message>,
+    limit: usize,
+}
+
+impl MessageLog {
+    /// Creates a new instance of MessageLog that keeps limit messages.
+    pub fn new(limit: usize) -> MessageLog {
+        MessageLog {
+            messages: Vec::new(),
+            limit,
+        }
+    }
+    /// Adds a message, dropping the oldest once there are too many.
+    pub fn add(&mut self, message: Message) {
+        self.messages.push(message);
+        if self.messages.len() > self.limit {
+            let extra = self.messages.len() - self.limit;
+            self.messages.drain(..extra);
+        }
+    }
+    /// Returns every message, oldest first.
+    pub fn get_messages(&self) -> &Vec<Message> {
+        &self.messages
+    }
+    /// Returns up to count of the newest messages, oldest first.
+    pub fn get_newest(&self, count: usize) -> &[Message] {
+        &self.messages[self.messages.len().saturating_sub(count)..]
+    }
+    /// Returns how many messages there are.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+    /// Returns true if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+    /// Throws away every message.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
+
+impl Default for MessageLog {
+    fn default() -> MessageLog {
+        MessageLog::new(MESSAGE_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(turn: u32) -> Message {
+        Message::new(turn, MessageCategory::System, &turn.to_string())
+    }
+
+    #[test]
+    fn the_log_keeps_the_newest() {
+        let mut log = MessageLog::new(3);
+        assert!(log.get_newest(2).is_empty());
+        for turn in 1..=5 {
+            log.add(numbered(turn));
+        }
+        assert_eq!(log.len(), 3);
+        let turns: Vec<u32> = log.get_messages().iter().map(Message::get_turn).collect();
+        assert_eq!(turns, vec![3, 4, 5]);
+        assert_eq!(log.get_newest(2), &[numbered(4), numbered(5)][..]);
+        assert_eq!(log.get_newest(10).len(), 3);
+        assert!(log.get_newest(0).is_empty());
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let messages = [
+            Message::new(7, MessageCategory::System, "Game saved."),
+            Message::new(0, MessageCategory::Combat, ""),
+            Message::new(12, MessageCategory::System, "Gold!  # not a comment")
+                .set_color(Color::RGB(255, 215, 0)),
+        ];
+        for message in messages.iter() {
+            assert_eq!(
+                Message::from_record(&message.to_record()),
+                Ok(message.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn messages_are_one_line() {
+        let message = Message::new(1, MessageCategory::System, "No such file\nor directory\r\n");
+        assert_eq!(message.get_text(), "No such file or directory");
+        assert_eq!(message.to_record().lines().count(), 1);
+    }
+}
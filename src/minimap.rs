@@ -0,0 +1,141 @@
+//! The minimap, the whole level drawn small in a corner of the screen.
+//!
+//! Walls and everything else that can't move rarely change, so they are
+//! worked out once into runs of tiles of one colour and kept until Data
+//! marks the minimap stale, like when a new tile is explored. Only the
+//! player and the entities in sight are added each frame, as dots.
+//!
+//! Tiles the player hasn't explored are left out.
+//!
+//! # Planned changes:
+//!
+//! - Warps and items in their own colours.
+//! - Clicking the minimap to walk there.
+
+use std::collections::HashSet;
+
+use crate::color::Color;
+use crate::entity::Entity;
+use crate::maps::Level;
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+
+/// The colour of explored floor without terrain.
+pub const FLOOR_COLOR: Color = Color {
+    r: 45,
+    g: 45,
+    b: 55,
+    a: 255,
+};
+/// The colour of walls and other entities that don't move.
+pub const STATIC_COLOR: Color = Color {
+    r: 150,
+    g: 150,
+    b: 150,
+    a: 255,
+};
+
+/// The part of the minimap that only changes when the level does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minimap {
+    area: Rectangle,
+    cells: Vec<(Color, Rectangle)>,
+}
+
+impl Minimap {
+    /// Creates a new instance of Minimap with nothing on it.
+    pub fn new() -> Minimap {
+        Minimap {
+            area: Rectangle::new(0, 0, 0, 0),
+            cells: Vec::new(),
+        }
+    }
+    /// Works out the static layer of level, with entities being the
+    /// entities on it. The minimap covers the bounds of the level, or the
+    /// explored tiles if it has none.
+    pub fn build(level: &Level, entities: &[Entity]) -> Minimap {
+        let area = match level.get_bounds() {
+            Some(bounds) => bounds,
+            None => match get_explored_area(level) {
+                Some(area) => area,
+                None => return Minimap::new(),
+            },
+        };
+        let blocked: HashSet<Point> = entities
+            .iter()
+            .filter(|entity| entity.get_abilities().can_not_move())
+            .filter_map(|entity| entity.get_rectangle().intersection(area))
+            .flat_map(|rectangle| rectangle.get_points())
+            .collect();
+        // joined into runs along each row, so there are fewer to draw
+        let mut cells = Vec::new();
+        for y in area.y_min()..=area.y_max() {
+            let mut run: Option<(Color, i32, u32)> = None;
+            for x in area.x_min()..=area.x_max() + 1 {
+                let point = Point::new(x, y);
+                let color = if x > area.x_max() || !level.is_explored(point) {
+                    None
+                } else if blocked.contains(&point) {
+                    Some(STATIC_COLOR)
+                } else {
+                    Some(get_floor_color(level, point))
+                };
+                match (run, color) {
+                    (Some((run_color, _, length)), Some(color)) if run_color == color => {
+                        run = run.map(|(color, start, _)| (color, start, length + 1));
+                    }
+                    _ => {
+                        if let Some((run_color, start, length)) = run {
+                            cells.push((run_color, Rectangle::new(start, y, length, 1)));
+                        }
+                        run = color.map(|color| (color, x, 1));
+                    }
+                }
+            }
+        }
+        Minimap { area, cells }
+    }
+    /// Returns the tiles the minimap covers.
+    pub fn get_area(&self) -> Rectangle {
+        self.area
+    }
+    /// Returns the runs of tiles to draw and their colours.
+    pub fn get_cells(&self) -> &Vec<(Color, Rectangle)> {
+        &self.cells
+    }
+    /// Returns how many pixels a tile can be so the minimap fits in
+    /// max_size, at least 1.
+    pub fn get_cell_size(&self, max_size: Dimension) -> u32 {
+        let (width, height) = (self.area.width().max(1), self.area.height().max(1));
+        (max_size.get_width() / width)
+            .min(max_size.get_height() / height)
+            .max(1)
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap::new()
+    }
+}
+
+/// Returns the colour of the terrain at point, or FLOOR_COLOR if it has
+/// none or is drawn with a sprite.
+fn get_floor_color(level: &Level, point: Point) -> Color {
+    match level.get_tiles().get_terrain(point) {
+        Some(terrain) => match terrain.get_skin().get_rgb_colors() {
+            Ok((r, g, b)) => Color::RGB(r, g, b),
+            Err(_) => FLOOR_COLOR,
+        },
+        None => FLOOR_COLOR,
+    }
+}
+
+/// Returns the smallest rectangle around the explored tiles of level, or
+/// None if nothing has been explored.
+fn get_explored_area(level: &Level) -> Option<Rectangle> {
+    level
+        .get_explored()
+        .iter()
+        .map(|point| Rectangle::new(point.get_x(), point.get_y(), 1, 1))
+        .reduce(|area, tile| area.union(tile))
+}
@@ -0,0 +1,192 @@
+//! Finds paths through levels, for the player walking to where the mouse
+//! clicked.
+//!
+//! Paths go one tile at a time left, right, up or down, the same as
+//! entities move, and take the fewest turns there are, so slow terrain is
+//! walked around when that is quicker. Searches give up after
+//! MAX_PATH_SEARCH tiles, so a click far outside a level doesn't search
+//! forever.
+//!
+//! # Planned changes:
+//!
+//! - Paths for entities chasing the player, instead of heading straight
+//!   for them.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::math::{Point, TwoDimensional};
+
+/// The most tiles a search looks at before giving up.
+pub const MAX_PATH_SEARCH: usize = 10_000;
+
+/// Returns the steps from `from` to `to`, not counting from, or None if
+/// there is no way there. get_cost says how many turns a step onto a tile
+/// takes, or None if it can't be walked through. `to` doesn't need to be
+/// open, so a path can end by walking into something like a warp.
+pub fn find_path<F: Fn(Point) -> Option<u32>>(
+    from: Point,
+    to: Point,
+    get_cost: F,
+) -> Option<Vec<Point>> {
+    if from == to {
+        return Some(Vec::new());
+    }
+    let mut came_from: HashMap<Point, Point> = HashMap::new();
+    let mut costs: HashMap<Point, u32> = HashMap::new();
+    costs.insert(from, 0);
+    // cheapest first, the coordinates only break ties
+    let mut frontier = BinaryHeap::new();
+    frontier.push(Reverse((0, from.get_x(), from.get_y())));
+    while let Some(Reverse((cost, x, y))) = frontier.pop() {
+        let point = Point::new(x, y);
+        if point == to {
+            return Some(walk_back(&came_from, from, to));
+        }
+        if cost > costs[&point] {
+            continue;
+        }
+        if costs.len() > MAX_PATH_SEARCH {
+            return None;
+        }
+        for next in get_neighbours(point).iter() {
+            let step = match get_cost(*next) {
+                Some(step) => step,
+                None if *next == to => 1,
+                None => continue,
+            };
+            let next_cost = cost + step;
+            if matches!(costs.get(next), Some(known) if *known <= next_cost) {
+                continue;
+            }
+            costs.insert(*next, next_cost);
+            came_from.insert(*next, point);
+            frontier.push(Reverse((next_cost, next.get_x(), next.get_y())));
+        }
+    }
+    None
+}
+
+/// Returns the tiles left, right, above and below point.
+fn get_neighbours(point: Point) -> [Point; 4] {
+    let (x, y) = point.get_coordinates();
+    [
+        Point::new(x - 1, y),
+        Point::new(x + 1, y),
+        Point::new(x, y - 1),
+        Point::new(x, y + 1),
+    ]
+}
+
+/// Follows came_from back from `to`, giving the steps in order.
+fn walk_back(came_from: &HashMap<Point, Point>, from: Point, to: Point) -> Vec<Point> {
+    let mut steps = vec![to];
+    let mut point = to;
+    while let Some(previous) = came_from.get(&point) {
+        if *previous == from {
+            break;
+        }
+        steps.push(*previous);
+        point = *previous;
+    }
+    steps.reverse();
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open ground everywhere, with the tiles in slow costing slow_cost.
+    fn open_with_slow(slow: &[Point], slow_cost: u32) -> impl Fn(Point) -> Option<u32> + '_ {
+        move |point| {
+            if slow.contains(&point) {
+                Some(slow_cost)
+            } else {
+                Some(1)
+            }
+        }
+    }
+
+    /// Adds up the turns a path takes.
+    fn get_turns<F: Fn(Point) -> Option<u32>>(path: &[Point], get_cost: F) -> u32 {
+        path.iter().map(|point| get_cost(*point).unwrap()).sum()
+    }
+
+    #[test]
+    fn standing_still_is_no_steps() {
+        let point = Point::new(3, 4);
+        assert_eq!(find_path(point, point, |_| None), Some(Vec::new()));
+    }
+
+    #[test]
+    fn slow_terrain_is_walked_around_when_quicker() {
+        let (from, to) = (Point::new(0, 0), Point::new(4, 0));
+        let slow = [Point::new(2, 0)];
+        // straight through takes 3 + 5 turns, around takes 6
+        let path = find_path(from, to, open_with_slow(&slow, 5)).unwrap();
+        assert!(!path.contains(&slow[0]));
+        assert_eq!(get_turns(&path, open_with_slow(&slow, 5)), 6);
+        assert_eq!(path.last(), Some(&to));
+        // straight through takes 3 + 2 turns, quicker than around
+        let path = find_path(from, to, open_with_slow(&slow, 2)).unwrap();
+        assert_eq!(path, (1..=4).map(|x| Point::new(x, 0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn steps_are_one_tile_at_a_time() {
+        let path = find_path(Point::new(0, 0), Point::new(3, -2), |_| Some(1)).unwrap();
+        assert_eq!(path.len(), 5);
+        let mut previous = Point::new(0, 0);
+        for point in path {
+            let distance =
+                (point.get_x() - previous.get_x()).abs() + (point.get_y() - previous.get_y()).abs();
+            assert_eq!(distance, 1);
+            previous = point;
+        }
+    }
+
+    #[test]
+    fn blocked_to_can_be_walked_into() {
+        let to = Point::new(2, 0);
+        let path = find_path(Point::new(0, 0), to, |point| {
+            if point == to {
+                None
+            } else {
+                Some(1)
+            }
+        });
+        assert_eq!(path, Some(vec![Point::new(1, 0), to]));
+    }
+
+    #[test]
+    fn walled_in_is_no_path() {
+        // a 3x3 room around the start
+        let path = find_path(Point::new(0, 0), Point::new(5, 0), |point| {
+            if point.get_x().abs() <= 1 && point.get_y().abs() <= 1 {
+                Some(1)
+            } else {
+                None
+            }
+        });
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn searches_give_up() {
+        // the target is walled in on open ground that goes on forever
+        let to = Point::new(5, 0);
+        let walls = get_neighbours(to);
+        let path = find_path(Point::new(0, 0), to, |point| {
+            if walls.contains(&point) {
+                None
+            } else {
+                Some(1)
+            }
+        });
+        assert_eq!(path, None);
+        // and a target too far away to reach in MAX_PATH_SEARCH tiles
+        let far = Point::new(MAX_PATH_SEARCH as i32, 0);
+        assert_eq!(find_path(Point::new(0, 0), far, |_| Some(1)), None);
+    }
+}
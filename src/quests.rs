@@ -1,4 +1,65 @@
 //! WIP Holds a list of player quests. May get fused with inventory.
+//!
+//! Campaigns start quests with a `quest` record in campaign.txt, and saves
+//! keep the quests being done and the ones that are done.
+//!
+//! # Planned changes:
+//!
+//! - Steps that complete themselves, like reaching a level.
 
-/// Quest struct will hold quest information.
-pub struct Quest {}
+/// Quest struct will hold quest information. For now that is a name and
+/// what the player has to do next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quest {
+    name: String,
+    objective: String,
+}
+
+impl Quest {
+    /// Creates a new instance of Quest.
+    pub fn new(name: &str, objective: &str) -> Quest {
+        Quest {
+            name: name.to_string(),
+            objective: objective.to_string(),
+        }
+    }
+    /// Returns the name of the quest.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Returns what the player has to do next.
+    pub fn get_objective(&self) -> &str {
+        &self.objective
+    }
+    /// Changes what the player has to do next.
+    pub fn set_objective(&mut self, objective: &str) {
+        self.objective = objective.to_string();
+    }
+    /// Writes the quest as `name: objective`.
+    pub fn to_record(&self) -> String {
+        format!("{}: {}", self.name, self.objective)
+    }
+    /// Reads a quest written as `name: objective`.
+    pub fn from_record(record: &str) -> Result<Quest, &'static str> {
+        match record.split_once(':') {
+            Some((name, objective)) if !name.trim().is_empty() => {
+                Ok(Quest::new(name.trim(), objective.trim()))
+            }
+            _ => Err("Expected a quest like Name: what to do."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip() {
+        let quest = Quest::new("The Cellar", "Find the stairs: they go down.");
+        assert_eq!(Quest::from_record(&quest.to_record()), Ok(quest));
+        assert_eq!(Quest::from_record("Rest:"), Ok(Quest::new("Rest", "")));
+        assert!(Quest::from_record("No colon").is_err());
+        assert!(Quest::from_record(": No name").is_err());
+    }
+}
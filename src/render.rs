@@ -1,76 +1,1338 @@
 //! Primary entry to the use of sdl2 graphics.
 //! Objects are rendered to the canvas here based on their positions, color, etc.
 
-use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
+use sdl2::render::{BlendMode, Canvas};
 use sdl2::video::Window;
+use std::time::Duration;
 
-use crate::entity;
+use crate::animation::Clip;
+use crate::assets::Assets;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::data::Data;
+use crate::entity::{self, Skin};
+use crate::environment::Scaling;
+use crate::fov::FieldOfView;
+use crate::hud::{Anchor, Hud};
+use crate::maps;
 use crate::math::{self, TwoDimensional};
+use crate::screens::{Menu, MenuOption, MessageHistory, Rebinding, Screen};
+use crate::text::{Align, Font, Text, DEFAULT_FONT};
+use crate::ui::{self, Ui, Widget};
 
-/// Renders all of the objects given to it onto the canvas.
-pub fn render_objects(
+/// How much of an entities colour is kept when it is muted.
+const MUTE_FACTOR: f32 = 0.4;
+/// Drawn over muted sprites to darken them as much as muted colours.
+const MUTE_OVERLAY_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: ((1.0 - MUTE_FACTOR) * 255.0) as u8,
+};
+/// Drawn in place of a sprite whose atlas or frame is missing.
+const MISSING_SPRITE_COLOR: Color = Color {
+    r: 255,
+    g: 0,
+    b: 255,
+    a: 255,
+};
+/// The size of a menu option bar in tiles.
+const MENU_OPTION_SIZE: (u32, u32) = (12, 2);
+/// Pixels between HUD text and the edge of its panel, before scaling.
+const HUD_PADDING: u32 = 2;
+/// The see through panel behind each HUD item.
+const HUD_PANEL_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 160,
+};
+/// The most of the screen the minimap can cover, as a fraction of each
+/// side.
+const MINIMAP_MAX_FRACTION: u32 = 4;
+/// The dot the player is drawn as on the minimap.
+const MINIMAP_PLAYER_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+/// The dot for entities in sight that have no colour of their own.
+const MINIMAP_ENTITY_COLOR: Color = Color {
+    r: 230,
+    g: 200,
+    b: 60,
+    a: 255,
+};
+/// The panel the message history is drawn on.
+const HISTORY_PANEL_COLOR: Color = Color {
+    r: 20,
+    g: 20,
+    b: 30,
+    a: 255,
+};
+/// The colour of the turn before each message in the history.
+const HISTORY_TURN_COLOR: Color = Color {
+    r: 150,
+    g: 150,
+    b: 150,
+    a: 255,
+};
+/// The background of lists, inputs, scrollbars and progress bars.
+const UI_FIELD_COLOR: Color = Color {
+    r: 55,
+    g: 55,
+    b: 70,
+    a: 255,
+};
+/// The colour of buttons and scrollbar handles.
+const UI_BUTTON_COLOR: Color = Color {
+    r: 80,
+    g: 80,
+    b: 110,
+    a: 255,
+};
+/// The frame around the focused widget and the selected row of a
+/// focused list.
+const UI_FOCUS_COLOR: Color = Color {
+    r: 230,
+    g: 190,
+    b: 70,
+    a: 255,
+};
+/// The selected row of a list that isn't focused.
+const UI_SELECTED_COLOR: Color = Color {
+    r: 95,
+    g: 95,
+    b: 125,
+    a: 255,
+};
+/// The colour of the placeholder in an empty text input.
+const UI_PLACEHOLDER_COLOR: Color = Color {
+    r: 140,
+    g: 140,
+    b: 150,
+    a: 255,
+};
+/// How far in pixels the tooltip is from the mouse.
+const TOOLTIP_OFFSET: i32 = 16;
+/// The colour drawn over the screens below an overlay.
+const OVERLAY_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 160,
+};
+
+/// The area the game is drawn in and how big a tile is inside it. Worked
+/// out again whenever the window changes size.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Viewport {
+    size: math::Dimension,
+    tile_size: u32,
+}
+
+impl Viewport {
+    /// Works out the viewport for a window of window_size. resolution and
+    /// tile_size are what the game was started with, and are what integer
+    /// scaling multiplies.
+    pub fn compute(
+        window_size: math::Dimension,
+        resolution: math::Dimension,
+        tile_size: u32,
+        scaling: Scaling,
+    ) -> Viewport {
+        let tile_size = match scaling {
+            Scaling::Fixed => tile_size,
+            Scaling::Integer => {
+                let scale = (window_size.get_width() / resolution.get_width().max(1))
+                    .min(window_size.get_height() / resolution.get_height().max(1))
+                    .max(1);
+                tile_size * scale
+            }
+        };
+        Viewport {
+            size: window_size,
+            tile_size,
+        }
+    }
+    /// Returns the size of the area drawn in, in pixels.
+    pub fn get_size(&self) -> math::Dimension {
+        self.size
+    }
+    /// Returns the size of a tile in pixels.
+    pub fn get_tile_size(&self) -> u32 {
+        self.tile_size
+    }
+}
+
+/// Clears the canvas and draws the screen stack in data.
+///
+/// Drawing starts at the highest screen that isn't an overlay, so a pause
+/// menu still shows the game under it.
+pub fn render_screens(
     tile_size: u32,
     screen_size: math::Dimension,
     canvas: &mut Canvas<Window>,
-    player: &entity::Entity,
-    objects: &Vec<entity::Entity>,
+    assets: &mut Assets,
+    data: &Data,
 ) {
     canvas.set_draw_color(Color::RGB(0, 0, 0));
     canvas.clear();
 
-    render_player(tile_size, screen_size, canvas, player);
-    for object in objects.iter() {
-        render_entity(
-            tile_size,
-            screen_size,
-            canvas,
-            object,
-            player.get_rectangle(),
-        );
+    let screens = data.get_screens();
+    let first = screens
+        .iter()
+        .rposition(|screen| !screen.is_overlay())
+        .unwrap_or(0);
+    for screen in screens[first..].iter() {
+        if screen.is_overlay() {
+            render_overlay(screen_size, canvas);
+        }
+        render_screen(tile_size, screen_size, canvas, assets, screen, data);
     }
 
     canvas.present();
 }
 
-/// Create the player and tries to put them in the center of the canvas.
-pub fn render_player(
+/// Draws a single screen.
+pub fn render_screen(
     tile_size: u32,
     screen_size: math::Dimension,
     canvas: &mut Canvas<Window>,
-    entity: &entity::Entity,
+    assets: &mut Assets,
+    screen: &Screen,
+    data: &Data,
+) {
+    match screen {
+        Screen::Game => {
+            render_objects(
+                tile_size,
+                screen_size,
+                canvas,
+                assets,
+                data.get_camera(),
+                data.get_player(),
+                data.get_entities_in(
+                    data.get_camera()
+                        .get_visible_rectangle(tile_size, screen_size),
+                ),
+                data.get_field_of_view(),
+                data.get_level(),
+                data.get_clock(),
+            );
+            render_hud(tile_size, screen_size, canvas, assets, screen, data);
+            if data.is_minimap_shown() {
+                render_minimap(tile_size, screen_size, canvas, assets, data);
+            }
+            render_tooltip(tile_size, screen_size, canvas, assets, data);
+        }
+        Screen::MainMenu(menu_level) | Screen::Settings(menu_level) => {
+            render_objects(
+                tile_size,
+                screen_size,
+                canvas,
+                assets,
+                menu_level.get_camera(),
+                menu_level.get_player(),
+                menu_level.get_level().get_level_entities(),
+                menu_level.get_field_of_view(),
+                menu_level.get_level(),
+                data.get_clock(),
+            );
+            render_hud(tile_size, screen_size, canvas, assets, screen, data);
+        }
+        Screen::Pause(menu) => render_menu(tile_size, screen_size, canvas, assets, menu),
+        Screen::Rebind(rebinding) => {
+            render_rebinding(tile_size, screen_size, canvas, rebinding, data)
+        }
+        // WIP screens are a plain panel until they have something to show
+        Screen::Inventory(ui) => render_ui(tile_size, screen_size, canvas, assets, ui),
+        Screen::Combat => render_panel(screen_size, canvas, Color::RGB(100, 30, 30)),
+        Screen::Dialog => render_panel(screen_size, canvas, Color::RGB(50, 80, 50)),
+        Screen::History(history) => {
+            render_history(tile_size, screen_size, canvas, assets, history, data)
+        }
+        Screen::Context(menu) => render_ui(tile_size, screen_size, canvas, assets, menu.get_ui()),
+    }
+}
+
+/// Draws the minimap in the bottom right corner, out of the way of the
+/// default HUD. The static layer is cached in data, so only the player and
+/// the moving entities in sight are worked out here.
+fn render_minimap(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    data: &Data,
+) {
+    let minimap = data.get_minimap();
+    let area = minimap.get_area();
+    if minimap.get_cells().is_empty() {
+        return;
+    }
+    let padding = HUD_PADDING * (tile_size / 16).max(1);
+    let cell_size = minimap.get_cell_size(math::Dimension::new(
+        screen_size.get_width() / MINIMAP_MAX_FRACTION,
+        screen_size.get_height() / MINIMAP_MAX_FRACTION,
+    ));
+    let (width, height) = (area.width() * cell_size, area.height() * cell_size);
+    let x = screen_size.get_width() as i32 - (width + 3 * padding) as i32;
+    let y = screen_size.get_height() as i32 - (height + 3 * padding) as i32;
+    // tiles to pixels, with the corner of area at the corner of the map
+    let place = |rectangle: math::Rectangle| {
+        Rect::new(
+            x + padding as i32 + (rectangle.x() - area.x()) * cell_size as i32,
+            y + padding as i32 + (rectangle.y() - area.y()) * cell_size as i32,
+            rectangle.width() * cell_size,
+            rectangle.height() * cell_size,
+        )
+    };
+
+    let mut draw_list = DrawList::new();
+    draw_list.add(
+        HUD_PANEL_COLOR,
+        Rect::new(x, y, width + 2 * padding, height + 2 * padding),
+    );
+    draw_list.next_layer();
+    for (color, rectangle) in minimap.get_cells() {
+        draw_list.add(*color, place(*rectangle));
+    }
+    draw_list.next_layer();
+    let field_of_view = data.get_field_of_view();
+    for entity in data.get_entities().iter().filter(|entity| {
+        entity.get_abilities().can_move() && field_of_view.is_visible(entity.get_position())
+    }) {
+        let color = match entity.get_skin().get_rgb_colors() {
+            Ok((r, g, b)) => Color::RGB(r, g, b),
+            Err(_) => MINIMAP_ENTITY_COLOR,
+        };
+        if let Some(rectangle) = entity.get_rectangle().intersection(area) {
+            draw_list.add(color, place(rectangle));
+        }
+    }
+    if let Some(rectangle) = data.get_player().get_rectangle().intersection(area) {
+        draw_list.add(MINIMAP_PLAYER_COLOR, place(rectangle));
+    }
+    draw_list.draw(canvas, assets);
+}
+
+/// Draws what is under the mouse next to it, while the game is the top
+/// screen. The tile is found with the camera the game was drawn with.
+fn render_tooltip(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    data: &Data,
+) {
+    if data.get_screen() != Some(&Screen::Game) {
+        return;
+    }
+    let pointer = match data.get_input_state().get_mouse_position() {
+        Some(pointer) => pointer,
+        None => return,
+    };
+    let tile = data
+        .get_camera()
+        .screen_to_world(pointer, tile_size, screen_size);
+    let (description, font) = match (data.describe_tile(tile), assets.get_font(DEFAULT_FONT)) {
+        (Some(description), Some(font)) => (description, *font),
+        _ => return,
+    };
+    let scale = (tile_size / 16).max(1);
+    let padding = HUD_PADDING * scale;
+    let text = Text::new(&description, Color::RGB(235, 235, 235)).set_scale(scale);
+    let size = font.measure(&text, Some(screen_size.get_width() / 3));
+    let (width, height) = (
+        size.get_width() + 2 * padding,
+        size.get_height() + 2 * padding,
+    );
+    // below and right of the mouse, flipped to stay on the screen
+    let mut x = pointer.get_x() + TOOLTIP_OFFSET;
+    let mut y = pointer.get_y() + TOOLTIP_OFFSET;
+    if x + width as i32 > screen_size.get_width() as i32 {
+        x = pointer.get_x() - TOOLTIP_OFFSET - width as i32;
+    }
+    if y + height as i32 > screen_size.get_height() as i32 {
+        y = pointer.get_y() - TOOLTIP_OFFSET - height as i32;
+    }
+
+    let mut draw_list = DrawList::new();
+    draw_list.add(HUD_PANEL_COLOR, Rect::new(x, y, width, height));
+    draw_list.next_layer();
+    draw_list.add_text(
+        DEFAULT_FONT,
+        &font,
+        &text,
+        math::Point::new(x + padding as i32, y + padding as i32),
+        Some(size.get_width()),
+    );
+    draw_list.draw(canvas, assets);
+}
+
+/// Draws the widgets of a Ui placed on a window of screen_size, in order,
+/// with a frame around the focused one. Text is in the default font, and
+/// is left out if it isn't loaded.
+pub fn render_ui(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    ui: &Ui,
+) {
+    let font = assets.get_font(DEFAULT_FONT).copied();
+    let scale = (tile_size / 16).max(1);
+    let border = 2 * scale;
+    let mut draw_list = DrawList::new();
+    for (index, (element, rect)) in ui
+        .get_elements()
+        .iter()
+        .zip(ui.place(screen_size))
+        .enumerate()
+    {
+        let focused = ui.get_focus() == Some(index);
+        if focused {
+            draw_list.add(
+                UI_FOCUS_COLOR,
+                Rect::new(
+                    rect.x() - border as i32,
+                    rect.y() - border as i32,
+                    rect.width() + 2 * border,
+                    rect.height() + 2 * border,
+                ),
+            );
+            draw_list.next_layer();
+        }
+        // each widget gets its own layers so later ones cover earlier ones
+        let mut texts: Vec<(Text, Rect)> = Vec::new();
+        match element.get_widget() {
+            Widget::Panel(color) => draw_list.add(*color, rect),
+            Widget::Label(text) => texts.push((text.clone(), rect.into())),
+            Widget::Button(label) => {
+                draw_list.add(UI_BUTTON_COLOR, rect);
+                let text = Text::new(label, ui::TEXT_COLOR).set_align(Align::Center);
+                texts.push((text, rect.into()));
+            }
+            Widget::List(list) => {
+                draw_list.add(UI_FIELD_COLOR, rect);
+                let row_height = rect.height() / list.get_rows() as u32;
+                let scrollbar = list.get_scrollbar();
+                let text_width = if scrollbar.is_full() {
+                    rect.width()
+                } else {
+                    rect.width().saturating_sub(4 * border)
+                };
+                let shown = list
+                    .get_items()
+                    .iter()
+                    .enumerate()
+                    .skip(list.get_scroll())
+                    .take(list.get_rows());
+                for (row, (item_index, item)) in shown.enumerate() {
+                    let row_rect = Rect::new(
+                        rect.x(),
+                        rect.y() + (row as u32 * row_height) as i32,
+                        text_width,
+                        row_height,
+                    );
+                    if item_index == list.get_selected() {
+                        let color = if focused {
+                            UI_FOCUS_COLOR
+                        } else {
+                            UI_SELECTED_COLOR
+                        };
+                        draw_list.add(color, row_rect);
+                    }
+                    texts.push((Text::new(item, ui::TEXT_COLOR), row_rect));
+                }
+                if !scrollbar.is_full() {
+                    let track = math::Rectangle::new(
+                        rect.x_max() + 1 - (3 * border) as i32,
+                        rect.y(),
+                        3 * border,
+                        rect.height(),
+                    );
+                    draw_list.add(UI_BUTTON_COLOR, scrollbar.get_handle(track));
+                }
+            }
+            Widget::Scrollbar(scrollbar) => {
+                draw_list.add(UI_FIELD_COLOR, rect);
+                draw_list.add(UI_BUTTON_COLOR, scrollbar.get_handle(rect));
+            }
+            Widget::ProgressBar(bar) => {
+                draw_list.add(UI_FIELD_COLOR, rect);
+                let filled = (rect.width() as f32 * bar.get_fraction()) as u32;
+                if filled > 0 {
+                    draw_list.add(
+                        bar.get_color(),
+                        Rect::new(rect.x(), rect.y(), filled, rect.height()),
+                    );
+                }
+                let (value, most) = bar.get_value();
+                let text = Text::new(&format!("{}/{}", value, most), ui::TEXT_COLOR)
+                    .set_align(Align::Center);
+                texts.push((text, rect.into()));
+            }
+            Widget::TextInput(input) => {
+                draw_list.add(UI_FIELD_COLOR, rect);
+                let text = match (input.get_text(), focused) {
+                    ("", false) => Text::new(input.get_placeholder(), UI_PLACEHOLDER_COLOR),
+                    (typed, true) => Text::new(&format!("{}_", typed), ui::TEXT_COLOR),
+                    (typed, false) => Text::new(typed, ui::TEXT_COLOR),
+                };
+                texts.push((text, rect.into()));
+            }
+        }
+        draw_list.next_layer();
+        if let Some(font) = font.as_ref() {
+            for (text, place) in texts.into_iter() {
+                // centred up and down, with a little room at the sides
+                let text = text.set_scale(scale);
+                let width = place.width().saturating_sub(2 * border);
+                let height = font.measure(&text, Some(width)).get_height();
+                let y = place.y() + (place.height() as i32 - height as i32).max(0) / 2;
+                draw_list.add_text(
+                    DEFAULT_FONT,
+                    font,
+                    &text,
+                    math::Point::new(place.x() + border as i32, y),
+                    Some(width),
+                );
+            }
+            draw_list.next_layer();
+        }
+    }
+    draw_list.draw(canvas, assets);
+}
+
+/// Draws the message log on a panel filling most of the screen, newest at
+/// the bottom, scrolled back by history. Each message is wrapped to the
+/// panel and starts with its turn. Only the panel is drawn without the
+/// default font.
+fn render_history(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    history: &MessageHistory,
+    data: &Data,
+) {
+    let (screen_width, screen_height) = screen_size.get_coordinates();
+    let margin = tile_size.min(screen_width / 8).min(screen_height / 8);
+    let panel = Rect::new(
+        margin as i32,
+        margin as i32,
+        screen_width - 2 * margin,
+        screen_height - 2 * margin,
+    );
+    canvas.set_draw_color(HISTORY_PANEL_COLOR);
+    canvas.fill_rect(panel).unwrap();
+    let font = match assets.get_font(DEFAULT_FONT) {
+        Some(font) => *font,
+        None => return,
+    };
+
+    let scale = (tile_size / 16).max(1);
+    let padding = HUD_PADDING * 2 * scale;
+    let width = panel.width().saturating_sub(2 * padding);
+    let (left, top) = (panel.x() + padding as i32, panel.y() + padding as i32);
+    let mut bottom = panel.bottom() - padding as i32;
+    let mut draw_list = DrawList::new();
+
+    let log = data.get_messages();
+    let shown = log.len().saturating_sub(history.get_scroll());
+    let title = Text::new("Messages", Color::RGB(255, 255, 255)).set_scale(scale);
+    let title_height = font.measure(&title, None).get_height();
+    draw_list.add_text(
+        DEFAULT_FONT,
+        &font,
+        &title,
+        math::Point::new(left, top),
+        None,
+    );
+    let count = Text::new(&format!("{} of {}", shown, log.len()), HISTORY_TURN_COLOR)
+        .set_align(Align::Right)
+        .set_scale(scale);
+    draw_list.add_text(
+        DEFAULT_FONT,
+        &font,
+        &count,
+        math::Point::new(left, top),
+        Some(width),
+    );
+
+    // from the newest shown upwards, until the panel is full
+    let first_line = top + (title_height * 2) as i32;
+    for message in log.get_messages()[..shown].iter().rev() {
+        let text = Text::new(&format!("{}: ", message.get_turn()), HISTORY_TURN_COLOR)
+            .add_span(message.get_text(), message.get_color())
+            .set_scale(scale);
+        let height = font.measure(&text, Some(width)).get_height() as i32;
+        if bottom - height < first_line {
+            break;
+        }
+        bottom -= height;
+        draw_list.add_text(
+            DEFAULT_FONT,
+            &font,
+            &text,
+            math::Point::new(left, bottom),
+            Some(width),
+        );
+    }
+    draw_list.draw(canvas, assets);
+}
+
+/// Draws the HUD items of data for screen, in screen space. Each item gets
+/// a dark panel behind it so it can be read over anything. Nothing is drawn
+/// without the default font.
+pub fn render_hud(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    screen: &Screen,
+    data: &Data,
 ) {
-    let entity_rectangle = Rect::new(
-        (screen_size.get_width() / 2 - 1) as i32,
-        (screen_size.get_height() / 2 - 1) as i32,
-        entity.get_rectangle().width() * tile_size,
-        entity.get_rectangle().height() * tile_size,
+    let font = match assets.get_font(DEFAULT_FONT) {
+        Some(font) => *font,
+        None => return,
+    };
+    let scale = (tile_size / 16).max(1);
+    let max_width = screen_size.get_width() / 3;
+    let texts: Vec<(Anchor, Text)> = data
+        .get_hud()
+        .get_screen_items(screen)
+        .iter()
+        .filter_map(|(item, anchor)| {
+            let text = item
+                .get_text(data)?
+                .set_align(anchor.get_align())
+                .set_scale(scale);
+            Some((*anchor, text))
+        })
+        .collect();
+    let padding = HUD_PADDING * scale;
+    let sizes: Vec<(Anchor, math::Dimension)> = texts
+        .iter()
+        .map(|(anchor, text)| {
+            let size = font.measure(text, Some(max_width));
+            (
+                *anchor,
+                math::Dimension::new(
+                    size.get_width() + 2 * padding,
+                    size.get_height() + 2 * padding,
+                ),
+            )
+        })
+        .collect();
+    let places = Hud::place(&sizes, screen_size);
+
+    let mut draw_list = DrawList::new();
+    for place in places.iter() {
+        draw_list.add(HUD_PANEL_COLOR, *place);
+    }
+    draw_list.next_layer();
+    for ((_, text), place) in texts.iter().zip(places.iter()) {
+        draw_list.add_text(
+            DEFAULT_FONT,
+            &font,
+            text,
+            math::Point::new(place.x() + padding as i32, place.y() + padding as i32),
+            Some(place.width() - 2 * padding),
+        );
+    }
+    draw_list.draw(canvas, assets);
+}
+
+/// Darkens everything drawn so far.
+fn render_overlay(screen_size: math::Dimension, canvas: &mut Canvas<Window>) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(OVERLAY_COLOR);
+    canvas
+        .fill_rect(Rect::new(
+            0,
+            0,
+            screen_size.get_width(),
+            screen_size.get_height(),
+        ))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Draws a panel in the middle of the screen.
+fn render_panel(screen_size: math::Dimension, canvas: &mut Canvas<Window>, color: Color) {
+    let (width, height) = (screen_size.get_width() / 2, screen_size.get_height() / 2);
+    canvas.set_draw_color(color);
+    canvas
+        .fill_rect(Rect::new(
+            (width / 2) as i32,
+            (height / 2) as i32,
+            width,
+            height,
+        ))
+        .unwrap();
+}
+
+/// Draws a panel in the colour of the action being bound, with a square
+/// for each input it already has on the device last used. A conflict is
+/// shown as a red frame around the colour of the action that owns the key.
+fn render_rebinding(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    rebinding: &Rebinding,
+    data: &Data,
+) {
+    let action = rebinding.get_action();
+    render_panel(
+        screen_size,
+        canvas,
+        option_color(MenuOption::Rebind(action)),
     );
-    let (red, green, blue) = entity.get_skin().get_rgb_colors().unwrap();
 
-    canvas.set_draw_color(Color::RGB(red, green, blue));
-    canvas.fill_rect(entity_rectangle).unwrap();
+    let left = (screen_size.get_width() / 4) as i32 + tile_size as i32;
+    let bottom = (screen_size.get_height() * 3 / 4) as i32 - 2 * tile_size as i32;
+    canvas.set_draw_color(Color::RGB(255, 255, 255));
+    let inputs = data
+        .get_settings()
+        .get_inputs(action, data.get_last_profile_name());
+    for index in 0..inputs.len() as i32 {
+        canvas
+            .fill_rect(Rect::new(
+                left + index * 2 * tile_size as i32,
+                bottom,
+                tile_size,
+                tile_size,
+            ))
+            .unwrap();
+    }
+
+    if let Some(other) = rebinding.get_conflict() {
+        let size = 4 * tile_size;
+        let x = (screen_size.get_width() - size) as i32 / 2;
+        let y = (screen_size.get_height() - size) as i32 / 2;
+        canvas.set_draw_color(Color::RGB(220, 30, 30));
+        canvas
+            .fill_rect(Rect::new(x - 4, y - 4, size + 8, size + 8))
+            .unwrap();
+        canvas.set_draw_color(option_color(MenuOption::Rebind(other)));
+        canvas.fill_rect(Rect::new(x, y, size, size)).unwrap();
+    }
 }
 
-/// Creates entities and places them relative to the player.
-pub fn render_entity(
+/// Draws the options of a menu as bars down the middle of the screen. Each
+/// option has its own colour for now, and the selected one is outlined.
+/// The name of each option is written on its bar in the default font.
+pub fn render_menu(
     tile_size: u32,
     screen_size: math::Dimension,
     canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    menu: &Menu,
+) {
+    let width = MENU_OPTION_SIZE.0 * tile_size;
+    let height = MENU_OPTION_SIZE.1 * tile_size;
+    let gap = tile_size;
+    let count = menu.get_options().len() as u32;
+    let x = (screen_size.get_width() as i32 - width as i32) / 2;
+    let top = (screen_size.get_height() as i32 - (count * (height + gap)) as i32) / 2;
+
+    for (index, option) in menu.get_options().iter().enumerate() {
+        let y = top + index as i32 * (height + gap) as i32;
+        let bar = Rect::new(x, y, width, height);
+        let color = option_color(*option);
+        if index == menu.get_selected() {
+            canvas.set_draw_color(Color::RGB(255, 255, 255));
+            canvas
+                .fill_rect(Rect::new(x - 3, y - 3, width + 6, height + 6))
+                .unwrap();
+            canvas.set_draw_color(color);
+        } else {
+            canvas.set_draw_color(mute_color(color));
+        }
+        canvas.fill_rect(bar).unwrap();
+
+        let label = Text::new(&option.get_label(), Color::RGB(255, 255, 255))
+            .set_align(Align::Center)
+            .set_scale((tile_size / 8).max(1));
+        if let Some(size) = measure_text(assets, DEFAULT_FONT, &label, Some(width)) {
+            let label_y = y + (height as i32 - size.get_height() as i32) / 2;
+            render_text(
+                canvas,
+                assets,
+                DEFAULT_FONT,
+                &label,
+                math::Point::new(x, label_y),
+                Some(width),
+            );
+        }
+    }
+}
+
+/// The colour of a menu option's bar.
+fn option_color(option: MenuOption) -> Color {
+    let (red, green, blue) = option.get_skin().get_rgb_colors().unwrap();
+    Color::RGB(red, green, blue)
+}
+
+/// A group of things drawn together, in a single call where SDL allows it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Batch {
+    /// Rectangles filled with a colour. Colours with alpha are blended.
+    Fill(Color, Vec<Rect>),
+    /// Frames of the named atlas drawn into rectangles. Muted sprites are
+    /// darkened the same amount as muted colours.
+    Sprites(String, bool, Vec<(SpriteFrame, Rect)>),
+    /// Glyphs of the named font tinted with a colour.
+    Glyphs(String, Color, Vec<(u32, Rect)>),
+}
+
+/// Which frame of an atlas a sprite shows. Clips are turned into a frame
+/// when drawn, since the clips live with the atlas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpriteFrame {
+    /// Always the same frame.
+    Still(u32),
+    /// Whatever frame the clip is on after playing for the duration.
+    Clip(Clip, Duration),
+}
+
+impl Batch {
+    /// Returns how many rectangles the batch draws.
+    pub fn len(&self) -> usize {
+        match self {
+            Batch::Fill(_, rects) => rects.len(),
+            Batch::Sprites(_, _, sprites) => sprites.len(),
+            Batch::Glyphs(_, _, glyphs) => glyphs.len(),
+        }
+    }
+    /// Returns true if the batch draws nothing.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Things to draw, grouped into batches by colour or atlas. Layers are
+/// drawn in the order they were started, so things in a later layer are
+/// always on top.
+#[derive(Clone, Debug, Default)]
+pub struct DrawList {
+    batches: Vec<Batch>,
+    layer_start: usize,
+}
+
+impl DrawList {
+    /// Creates a new instance of DrawList with nothing in it.
+    pub fn new() -> DrawList {
+        DrawList::default()
+    }
+    /// Adds a rectangle to the batch of its colour in the current layer.
+    pub fn add<R: Into<Rect>>(&mut self, color: Color, rect: R) {
+        let rect = rect.into();
+        let batch = self.batches[self.layer_start..]
+            .iter_mut()
+            .find_map(|batch| match batch {
+                Batch::Fill(batch_color, rects) if *batch_color == color => Some(rects),
+                _ => None,
+            });
+        match batch {
+            Some(rects) => rects.push(rect),
+            None => self.batches.push(Batch::Fill(color, vec![rect])),
+        }
+    }
+    /// Adds a frame of an atlas to the batch of its atlas in the current
+    /// layer.
+    pub fn add_sprite(&mut self, atlas: &str, frame: u32, rect: Rect, muted: bool) {
+        self.add_sprite_frame(atlas, SpriteFrame::Still(frame), rect, muted);
+    }
+    /// Adds a clip of an atlas that has played for elapsed to the batch of
+    /// its atlas in the current layer.
+    pub fn add_clip(
+        &mut self,
+        atlas: &str,
+        clip: Clip,
+        elapsed: Duration,
+        rect: Rect,
+        muted: bool,
+    ) {
+        self.add_sprite_frame(atlas, SpriteFrame::Clip(clip, elapsed), rect, muted);
+    }
+    /// Adds a sprite to the batch of its atlas in the current layer.
+    fn add_sprite_frame(&mut self, atlas: &str, frame: SpriteFrame, rect: Rect, muted: bool) {
+        let batch = self.batches[self.layer_start..]
+            .iter_mut()
+            .find_map(|batch| match batch {
+                Batch::Sprites(name, is_muted, sprites) if name == atlas && *is_muted == muted => {
+                    Some(sprites)
+                }
+                _ => None,
+            });
+        match batch {
+            Some(sprites) => sprites.push((frame, rect)),
+            None => self.batches.push(Batch::Sprites(
+                atlas.to_string(),
+                muted,
+                vec![(frame, rect)],
+            )),
+        }
+    }
+    /// Adds a skin covering rect, which is a whole number of tiles.
+    /// Animated skins show their idle clip from the start, add_entity_skin
+    /// plays the clip of the entity instead.
+    pub fn add_skin(&mut self, skin: &Skin, rect: Rect, tile_size: u32, muted: bool) {
+        match skin {
+            Skin::Sprite(atlas, frame) => self.add_sprite(atlas, *frame, rect, muted),
+            Skin::Animated(atlas) => {
+                self.add_clip(atlas, Clip::Idle, Duration::from_millis(0), rect, muted)
+            }
+            Skin::Tiled(atlas, frame) => {
+                let tile_size = tile_size.max(1);
+                for y in 0..rect.height() / tile_size {
+                    for x in 0..rect.width() / tile_size {
+                        let tile = Rect::new(
+                            rect.x() + (x * tile_size) as i32,
+                            rect.y() + (y * tile_size) as i32,
+                            tile_size,
+                            tile_size,
+                        );
+                        self.add_sprite(atlas, *frame, tile, muted);
+                    }
+                }
+            }
+            _ => {
+                let (red, green, blue, alpha) = skin.get_rgba_colors().unwrap();
+                let color = Color::RGBA(red, green, blue, alpha);
+                self.add(if muted { mute_color(color) } else { color }, rect);
+            }
+        }
+    }
+    /// Adds the skin of entity covering rect, with animated skins on the
+    /// clip the entity is playing at clock.
+    pub fn add_entity_skin(
+        &mut self,
+        entity: &entity::Entity,
+        rect: Rect,
+        tile_size: u32,
+        muted: bool,
+        clock: Duration,
+    ) {
+        match entity.get_skin() {
+            Skin::Animated(atlas) => {
+                let animation = entity.get_animation();
+                self.add_clip(
+                    atlas,
+                    animation.get_clip(),
+                    animation.get_elapsed(clock),
+                    rect,
+                    muted,
+                );
+            }
+            skin => self.add_skin(skin, rect, tile_size, muted),
+        }
+    }
+    /// Adds text in the font called font_name, with font its metrics, laid
+    /// out from position and wrapped to width if there is one. Glyphs go in
+    /// a batch for each colour in the current layer.
+    pub fn add_text(
+        &mut self,
+        font_name: &str,
+        font: &Font,
+        text: &Text,
+        position: math::Point,
+        width: Option<u32>,
+    ) {
+        for (frame, rect, color) in font.layout(text, position, width).into_iter() {
+            let batch = self.batches[self.layer_start..]
+                .iter_mut()
+                .find_map(|batch| match batch {
+                    Batch::Glyphs(name, batch_color, glyphs)
+                        if name == font_name && *batch_color == color =>
+                    {
+                        Some(glyphs)
+                    }
+                    _ => None,
+                });
+            match batch {
+                Some(glyphs) => glyphs.push((frame, rect.into())),
+                None => self.batches.push(Batch::Glyphs(
+                    font_name.to_string(),
+                    color,
+                    vec![(frame, rect.into())],
+                )),
+            }
+        }
+    }
+    /// Starts a new layer, drawn over everything added before it.
+    pub fn next_layer(&mut self) {
+        self.layer_start = self.batches.len();
+    }
+    /// Returns the batches in the order they are drawn.
+    pub fn get_batches(&self) -> &Vec<Batch> {
+        &self.batches
+    }
+    /// Returns how many rectangles are in the list.
+    pub fn get_rect_count(&self) -> usize {
+        self.batches.iter().map(Batch::len).sum()
+    }
+    /// Draws every batch onto the canvas. Sprites with a missing atlas or
+    /// frame are drawn as magenta rectangles.
+    pub fn draw(&self, canvas: &mut Canvas<Window>, assets: &mut Assets) {
+        for batch in self.batches.iter() {
+            match batch {
+                Batch::Fill(color, rects) => {
+                    if color.a < 255 {
+                        canvas.set_blend_mode(BlendMode::Blend);
+                    }
+                    canvas.set_draw_color(*color);
+                    canvas.fill_rects(rects).unwrap();
+                    canvas.set_blend_mode(BlendMode::None);
+                }
+                Batch::Sprites(name, muted, sprites) => {
+                    draw_sprites(canvas, assets, name, sprites);
+                    if *muted {
+                        let rects: Vec<Rect> = sprites.iter().map(|sprite| sprite.1).collect();
+                        canvas.set_blend_mode(BlendMode::Blend);
+                        canvas.set_draw_color(MUTE_OVERLAY_COLOR);
+                        canvas.fill_rects(&rects).unwrap();
+                        canvas.set_blend_mode(BlendMode::None);
+                    }
+                }
+                Batch::Glyphs(name, color, glyphs) => {
+                    draw_glyphs(canvas, assets, name, *color, glyphs)
+                }
+            }
+        }
+    }
+}
+
+/// Copies frames of the atlas called name onto the canvas.
+fn draw_sprites(
+    canvas: &mut Canvas<Window>,
+    assets: &Assets,
+    name: &str,
+    sprites: &[(SpriteFrame, Rect)],
+) {
+    let atlas = assets.get_atlas(name);
+    let mut missing = Vec::new();
+    for (frame, rect) in sprites.iter() {
+        let source = atlas.and_then(|atlas| {
+            let frame = match frame {
+                SpriteFrame::Still(frame) => *frame,
+                SpriteFrame::Clip(clip, elapsed) => {
+                    atlas.get_animation().get_frame(*clip, *elapsed)
+                }
+            };
+            atlas.get_frame(frame).map(|source| (atlas, source))
+        });
+        match source {
+            Some((atlas, source)) => canvas.copy(atlas.get_texture(), source, *rect).unwrap(),
+            None => missing.push(*rect),
+        }
+    }
+    if !missing.is_empty() {
+        canvas.set_draw_color(MISSING_SPRITE_COLOR);
+        canvas.fill_rects(&missing).unwrap();
+    }
+}
+
+/// Copies glyphs of the font called name onto the canvas, tinted by color.
+/// Text in a font that isn't loaded isn't drawn.
+fn draw_glyphs(
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    name: &str,
+    color: Color,
+    glyphs: &[(u32, Rect)],
+) {
+    let atlas = match assets.get_mut_atlas(name) {
+        Some(atlas) => atlas,
+        None => return,
+    };
+    let sources: Vec<(Rect, Rect)> = glyphs
+        .iter()
+        .filter_map(|(frame, rect)| atlas.get_frame(*frame).map(|source| (source, *rect)))
+        .collect();
+    let texture = atlas.get_mut_texture();
+    texture.set_color_mod(color.r, color.g, color.b);
+    texture.set_alpha_mod(color.a);
+    for (source, rect) in sources.into_iter() {
+        canvas.copy(texture, source, rect).unwrap();
+    }
+    texture.set_color_mod(255, 255, 255);
+    texture.set_alpha_mod(255);
+}
+
+/// Draws text in screen space with the font called font_name, laid out from
+/// position and wrapped to width if there is one. Nothing is drawn if the
+/// font isn't loaded.
+pub fn render_text(
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    font_name: &str,
+    text: &Text,
+    position: math::Point,
+    width: Option<u32>,
+) {
+    let font = match assets.get_font(font_name) {
+        Some(font) => *font,
+        None => return,
+    };
+    let mut draw_list = DrawList::new();
+    draw_list.add_text(font_name, &font, text, position, width);
+    draw_list.draw(canvas, assets);
+}
+
+/// Returns how much room text takes up in the font called font_name,
+/// wrapped to width if there is one, or None if the font isn't loaded.
+pub fn measure_text(
+    assets: &Assets,
+    font_name: &str,
+    text: &Text,
+    width: Option<u32>,
+) -> Option<math::Dimension> {
+    assets
+        .get_font(font_name)
+        .map(|font| font.measure(text, width))
+}
+
+/// Renders the objects on screen, placed by the camera.
+#[allow(clippy::too_many_arguments)]
+pub fn render_objects<'a, I: IntoIterator<Item = &'a entity::Entity>>(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    canvas: &mut Canvas<Window>,
+    assets: &mut Assets,
+    camera: &Camera,
+    player: &entity::Entity,
+    objects: I,
+    field_of_view: &FieldOfView,
+    level: &maps::Level,
+    clock: Duration,
+) {
+    get_objects_draw_list(
+        tile_size,
+        screen_size,
+        camera,
+        player,
+        objects,
+        field_of_view,
+        level,
+        clock,
+    )
+    .draw(canvas, assets);
+}
+
+/// Returns what render_objects draws, without drawing it.
+///
+/// Objects off screen or that the player can not see are skipped. Opaque
+/// objects such as walls are the terrain of the level, so any of their
+/// tiles the player has seen before stay on screen in a muted colour, the
+/// same as the tile layer of the level. The tile layer is drawn first,
+/// then terrain, then everything else, then the player.
+///
+/// clock is the render clock, for animated skins.
+#[allow(clippy::too_many_arguments)]
+pub fn get_objects_draw_list<'a, I: IntoIterator<Item = &'a entity::Entity>>(
+    tile_size: u32,
+    screen_size: math::Dimension,
+    camera: &Camera,
+    player: &entity::Entity,
+    objects: I,
+    field_of_view: &FieldOfView,
+    level: &maps::Level,
+    clock: Duration,
+) -> DrawList {
+    let visible_area = camera.get_visible_rectangle(tile_size, screen_size);
+    let mut terrain = DrawList::new();
+    add_tiles(
+        &mut terrain,
+        tile_size,
+        screen_size,
+        camera,
+        visible_area,
+        field_of_view,
+        level,
+    );
+    terrain.next_layer();
+    let mut things = Vec::new();
+    for object in objects.into_iter() {
+        if object.get_rectangle().is_not_colliding(visible_area) {
+            continue;
+        }
+        if object.get_abilities().is_opaque() {
+            add_remembered_entity(
+                &mut terrain,
+                tile_size,
+                screen_size,
+                camera,
+                object,
+                visible_area,
+                field_of_view,
+                level,
+                clock,
+            );
+        } else if field_of_view.is_rectangle_visible(object.get_rectangle()) {
+            things.push(object);
+        }
+    }
+
+    let mut draw_list = terrain;
+    draw_list.next_layer();
+    for thing in things.into_iter() {
+        add_entity(&mut draw_list, tile_size, screen_size, camera, thing, clock);
+    }
+    draw_list.next_layer();
+    add_entity(
+        &mut draw_list,
+        tile_size,
+        screen_size,
+        camera,
+        player,
+        clock,
+    );
+    draw_list
+}
+
+/// Adds the tile layer of level for the tiles in visible_area, muted when
+/// explored but out of view. Unexplored tiles are skipped.
+fn add_tiles(
+    draw_list: &mut DrawList,
+    tile_size: u32,
+    screen_size: math::Dimension,
+    camera: &Camera,
+    visible_area: math::Rectangle,
+    field_of_view: &FieldOfView,
+    level: &maps::Level,
+) {
+    let tiles = level.get_tiles();
+    if tiles.is_empty() {
+        return;
+    }
+    for point in visible_area.get_points() {
+        let terrain = match tiles.get_terrain(point) {
+            Some(terrain) => terrain,
+            None => continue,
+        };
+        let muted = if field_of_view.is_visible(point) {
+            false
+        } else if level.is_explored(point) {
+            true
+        } else {
+            continue;
+        };
+        let (x, y) = camera
+            .world_to_screen(point, tile_size, screen_size)
+            .get_coordinates();
+        draw_list.add_skin(
+            terrain.get_skin(),
+            Rect::new(x, y, tile_size, tile_size),
+            tile_size,
+            muted,
+        );
+    }
+}
+
+/// Adds an entity where the camera puts it on the screen, part of the way
+/// along if it is sliding.
+fn add_entity(
+    draw_list: &mut DrawList,
+    tile_size: u32,
+    screen_size: math::Dimension,
+    camera: &Camera,
     entity: &entity::Entity,
-    player_rect: math::Rectangle,
+    clock: Duration,
 ) {
-    let (x, y) = entity.get_position().get_coordinates();
     let (width, height) = entity.get_size().get_coordinates();
-    let entity_rectangle = Rect::new(
-        (x - player_rect.x()) * tile_size as i32 + (screen_size.get_width() / 2 - 1) as i32,
-        (y - player_rect.y()) * tile_size as i32 + (screen_size.get_height() / 2 - 1) as i32,
-        width * tile_size,
-        height * tile_size,
+    let (x, y) = camera
+        .position_to_screen(entity.get_drawn_position(clock), tile_size, screen_size)
+        .get_coordinates();
+    draw_list.add_entity_skin(
+        entity,
+        Rect::new(x, y, width * tile_size, height * tile_size),
+        tile_size,
+        false,
+        clock,
     );
-    let (red, green, blue) = entity.get_skin().get_rgb_colors().unwrap();
+}
+
+/// Adds an entity tile by tile, only for the tiles on screen. Tiles in view
+/// are drawn as normal, explored tiles out of view are muted and unexplored
+/// tiles are skipped.
+///
+/// A sprite or animation stretched over the entity can't be split into
+/// tiles, so it is drawn whole if any tile is in view, muted if any was
+/// explored.
+#[allow(clippy::too_many_arguments)]
+fn add_remembered_entity(
+    draw_list: &mut DrawList,
+    tile_size: u32,
+    screen_size: math::Dimension,
+    camera: &Camera,
+    entity: &entity::Entity,
+    visible_area: math::Rectangle,
+    field_of_view: &FieldOfView,
+    level: &maps::Level,
+    clock: Duration,
+) {
+    let rectangle = match entity.get_rectangle().intersection(visible_area) {
+        Some(rectangle) => rectangle,
+        None => return,
+    };
+    let skin = entity.get_skin();
+
+    if let entity::Skin::Sprite(..) | entity::Skin::Animated(..) = skin {
+        let muted = if field_of_view.is_rectangle_visible(entity.get_rectangle()) {
+            false
+        } else if rectangle.get_points().any(|point| level.is_explored(point)) {
+            true
+        } else {
+            return;
+        };
+        let (x, y) = camera
+            .world_to_screen(entity.get_position(), tile_size, screen_size)
+            .get_coordinates();
+        let (width, height) = entity.get_size().get_coordinates();
+        draw_list.add_entity_skin(
+            entity,
+            Rect::new(x, y, width * tile_size, height * tile_size),
+            tile_size,
+            muted,
+            clock,
+        );
+        return;
+    }
+
+    for point in rectangle.get_points() {
+        let muted = if field_of_view.is_visible(point) {
+            false
+        } else if level.is_explored(point) {
+            true
+        } else {
+            continue;
+        };
+        let (screen_x, screen_y) = camera
+            .world_to_screen(point, tile_size, screen_size)
+            .get_coordinates();
+        draw_list.add_skin(
+            skin,
+            Rect::new(screen_x, screen_y, tile_size, tile_size),
+            tile_size,
+            muted,
+        );
+    }
+}
 
-    canvas.set_draw_color(Color::RGB(red, green, blue));
-    canvas.fill_rect(entity_rectangle).unwrap();
+/// Returns a muted version of the colour for remembered things out of sight.
+/// Alpha is kept.
+pub fn mute_color(color: Color) -> Color {
+    Color::RGBA(
+        (color.r as f32 * MUTE_FACTOR) as u8,
+        (color.g as f32 * MUTE_FACTOR) as u8,
+        (color.b as f32 * MUTE_FACTOR) as u8,
+        color.a,
+    )
 }
@@ -0,0 +1,360 @@
+//! Saving and loading games.
+//!
+//! Saves are plain text files with one record per line, so they are easy
+//! to read and fix by hand. The first word of a line says what the record
+//! is and the rest are its values.
+//!
+//! # Planned changes:
+//!
+//! - Save the inventory once it exists.
+
+use std::fs;
+use std::path::Path;
+
+use crate::data::Data;
+use crate::entity::Entity;
+use crate::helper::FileError;
+use crate::maps::LevelId;
+use crate::math::{Point, TwoDimensional};
+use crate::messages::{Message, MessageLog};
+use crate::quests::Quest;
+
+/// The first line of every save. Bump the number when the format changes.
+const SAVE_HEADER: &str = "tiny_rpg_save 4";
+
+/// Where the quick save and quick load actions keep their save.
+pub const DEFAULT_SAVE_PATH: &str = "save.txt";
+
+/// A level the player has visited, with everything that may have changed.
+struct VisitedLevel {
+    id: LevelId,
+    entities: Vec<Entity>,
+    explored: Vec<Point>,
+}
+
+/// A snapshot of the parts of Data that need to survive closing the game.
+pub struct Save {
+    time: u32,
+    player: Entity,
+    current_level: LevelId,
+    visited_levels: Vec<VisitedLevel>,
+    messages: MessageLog,
+    current_quests: Vec<Quest>,
+    complete_quests: Vec<Quest>,
+}
+
+impl Save {
+    /// Takes a snapshot of the given data.
+    pub fn from_data(data: &Data) -> Save {
+        let current_level = data.get_level().get_id();
+        let mut visited_levels = Vec::new();
+        for level in data.get_map().get_levels().iter() {
+            let mut explored: Vec<Point> = level.get_explored().iter().cloned().collect();
+            explored.sort_by_key(|point| (point.get_y(), point.get_x()));
+            // the current level's entities live in data until the level is left
+            let entities = if level.get_id() == current_level {
+                data.get_entities().clone()
+            } else {
+                level.get_level_entities().clone()
+            };
+            visited_levels.push(VisitedLevel {
+                id: level.get_id(),
+                entities,
+                explored,
+            });
+        }
+        Save {
+            time: data.get_time(),
+            player: data.get_player().clone(),
+            current_level,
+            visited_levels,
+            messages: data.get_messages().clone(),
+            current_quests: data.get_current_quests().clone(),
+            complete_quests: data.get_complete_quests().clone(),
+        }
+    }
+    /// Puts the snapshot back into data.
+    ///
+    /// The levels in the save must exist in the map data was made with. The
+    /// save is checked before anything changes, so data is left alone if it
+    /// returns an error.
+    pub fn apply(self, data: &mut Data) -> Result<(), FileError> {
+        self.validate(data)?;
+        data.get_mut_map().reset();
+        for visited_level in self.visited_levels.into_iter() {
+            // validate made sure every level is in the map
+            let level = data.get_mut_map().get_mut_level(visited_level.id).unwrap();
+            level.set_level_entities(visited_level.entities);
+            level.set_explored(visited_level.explored.into_iter().collect());
+        }
+        data.get_mut_map().change_level(self.current_level).unwrap();
+        // the player goes first so what they see is worked out from where
+        // they were saved
+        data.set_player(self.player);
+        let entities = data.get_level().get_level_entities().clone();
+        data.change_entities(entities);
+        data.set_time(self.time);
+        data.set_messages(self.messages);
+        data.set_quests(self.current_quests, self.complete_quests);
+        Ok(())
+    }
+    /// Checks the save fits the map of data: every level it mentions, even
+    /// in warps, is in the map, the current level was visited, and the
+    /// player is inside it.
+    fn validate(&self, data: &Data) -> Result<(), FileError> {
+        let map = data.get_map();
+        let warps = self
+            .visited_levels
+            .iter()
+            .flat_map(|visited_level| visited_level.entities.iter())
+            .chain(std::iter::once(&self.player))
+            .filter_map(|entity| entity.get_abilities().is_warp());
+        for (id, _) in warps {
+            if !map.has_level(id) {
+                return Err(FileError::new(
+                    "A saved warp goes to a level not in the map.",
+                ));
+            }
+        }
+        if self
+            .visited_levels
+            .iter()
+            .any(|visited_level| !map.has_level(visited_level.id))
+        {
+            return Err(FileError::new("A saved level is not in the map."));
+        }
+        let current = self
+            .visited_levels
+            .iter()
+            .find(|visited_level| visited_level.id == self.current_level)
+            .ok_or_else(|| FileError::new("The saved level was never visited."))?;
+        let bounds = current
+            .entities
+            .iter()
+            .map(|entity| entity.get_rectangle())
+            .reduce(|bounds, rectangle| bounds.union(rectangle));
+        if let Some(bounds) = bounds {
+            if !bounds.contains_point(self.player.get_position()) {
+                return Err(FileError::new("The saved player is outside their level."));
+            }
+        }
+        Ok(())
+    }
+    /// Writes the save to a file at path.
+    pub fn write(&self, path: &Path) -> Result<(), FileError> {
+        let mut lines = vec![SAVE_HEADER.to_string()];
+        lines.push(format!("time {}", self.time));
+        lines.push(format!("level {}", self.current_level));
+        lines.push(format!("player {}", self.player.to_record()));
+        for visited_level in self.visited_levels.iter() {
+            let mut line = format!("visited {}", visited_level.id);
+            for tile in visited_level.explored.iter() {
+                line.push_str(&format!(" {},{}", tile.get_x(), tile.get_y()));
+            }
+            lines.push(line);
+            for entity in visited_level.entities.iter() {
+                lines.push(format!("entity {}", entity.to_record()));
+            }
+        }
+        for quest in self.current_quests.iter() {
+            lines.push(format!("quest {}", quest.to_record()));
+        }
+        for quest in self.complete_quests.iter() {
+            lines.push(format!("complete_quest {}", quest.to_record()));
+        }
+        for message in self.messages.get_messages().iter() {
+            lines.push(format!("message {}", message.to_record()));
+        }
+        lines.push(String::new());
+
+        fs::write(path, lines.join("\n"))?;
+        Ok(())
+    }
+    /// Reads a save from a file at path.
+    pub fn read(path: &Path) -> Result<Save, FileError> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == SAVE_HEADER => {}
+            _ => return Err(FileError::on_line(1, "Not a tiny rpg save.")),
+        }
+
+        let mut time = None;
+        let mut player = None;
+        let mut current_level = None;
+        let mut visited_levels: Vec<VisitedLevel> = Vec::new();
+        let mut messages = MessageLog::default();
+        let mut current_quests = Vec::new();
+        let mut complete_quests = Vec::new();
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let (record, rest) = split_record(line);
+            match record {
+                "" => {}
+                "time" => time = Some(parse_number(line_number, rest)?),
+                "level" => current_level = Some(LevelId::new(parse_number(line_number, rest)?)),
+                "player" => player = Some(parse_entity(line_number, rest)?),
+                "visited" => {
+                    let mut words = rest.split_whitespace();
+                    let id = match words.next() {
+                        Some(id) => LevelId::new(parse_number(line_number, id)?),
+                        None => {
+                            return Err(FileError::on_line(line_number, "Expected a level id."))
+                        }
+                    };
+                    let mut explored = Vec::new();
+                    for tile in words {
+                        explored.push(parse_point(line_number, tile)?);
+                    }
+                    visited_levels.push(VisitedLevel {
+                        id,
+                        entities: Vec::new(),
+                        explored,
+                    });
+                }
+                "entity" => match visited_levels.last_mut() {
+                    Some(visited_level) => visited_level
+                        .entities
+                        .push(parse_entity(line_number, rest)?),
+                    None => {
+                        return Err(FileError::on_line(
+                            line_number,
+                            "Entities must come after a visited record.",
+                        ))
+                    }
+                },
+                "quest" => current_quests.push(
+                    Quest::from_record(rest)
+                        .map_err(|error| FileError::on_line(line_number, error))?,
+                ),
+                "complete_quest" => complete_quests.push(
+                    Quest::from_record(rest)
+                        .map_err(|error| FileError::on_line(line_number, error))?,
+                ),
+                "message" => messages.add(
+                    Message::from_record(rest)
+                        .map_err(|error| FileError::on_line(line_number, error))?,
+                ),
+                _ => return Err(FileError::on_line(line_number, "Unknown record.")),
+            }
+        }
+
+        match (time, player, current_level) {
+            (Some(time), Some(player), Some(current_level)) => Ok(Save {
+                time,
+                player,
+                current_level,
+                visited_levels,
+                messages,
+                current_quests,
+                complete_quests,
+            }),
+            _ => Err(FileError::new(
+                "Save is missing the time, player or level record.",
+            )),
+        }
+    }
+}
+
+/// Splits a line into its first word, which says what the record is, and
+/// the rest of the line.
+pub fn split_record(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find(char::is_whitespace) {
+        Some(index) => (&line[..index], line[index..].trim_start()),
+        None => (line, ""),
+    }
+}
+
+/// Removes anything after a `#`, for the files people write by hand. Saves
+/// don't have comments, messages can have a `#` in them.
+pub fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+/// Parses an entity record from the rest of a line.
+pub fn parse_entity(line_number: usize, rest: &str) -> Result<Entity, FileError> {
+    Entity::from_record(rest).map_err(|error| FileError::on_line(line_number, error))
+}
+
+/// Parses a number from a word of a line.
+pub fn parse_number<T: std::str::FromStr>(line_number: usize, word: &str) -> Result<T, FileError> {
+    word.parse()
+        .map_err(|_| FileError::on_line(line_number, "Expected a number."))
+}
+
+/// Parses a point written as x,y from a word of a line.
+pub fn parse_point(line_number: usize, word: &str) -> Result<Point, FileError> {
+    let mut numbers = word.split(',');
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(x), Some(y), None) => Ok(Point::new(
+            parse_number(line_number, x)?,
+            parse_number(line_number, y)?,
+        )),
+        _ => Err(FileError::on_line(
+            line_number,
+            "Expected a point like 4,2.",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::Builder;
+    use crate::maps::{Level, Map};
+    use crate::math::Rectangle;
+
+    fn one_level_data() -> Data {
+        let level = Level::new()
+            .id(LevelId::new(1))
+            .number(1)
+            .entities(Map::get_boundry_entities(Rectangle::new(0, 0, 20, 20)))
+            .build()
+            .unwrap();
+        let map = Map::new()
+            .level(level)
+            .start(LevelId::new(1), Point::new(2, 2))
+            .build()
+            .unwrap();
+        Data::from_map(map)
+    }
+
+    #[test]
+    fn bad_saves_change_nothing() {
+        let mut data = one_level_data();
+        let mut save = Save::from_data(&data);
+        save.time = 50;
+        save.visited_levels[0].id = LevelId::new(9);
+        save.current_level = LevelId::new(9);
+        assert!(save.apply(&mut data).is_err());
+        assert_eq!(data.get_time(), 0);
+
+        let mut save = Save::from_data(&data);
+        save.time = 50;
+        save.player.set_position(Point::new(40, 40));
+        assert!(save.apply(&mut data).is_err());
+        assert_eq!(data.get_time(), 0);
+        assert_eq!(data.get_player().get_position(), Point::new(2, 2));
+    }
+
+    #[test]
+    fn quests_are_saved() {
+        let mut data = one_level_data();
+        data.start_quest(Quest::new("Well", "Fetch water."));
+        data.start_quest(Quest::new("Rats", "Clear the cellar."));
+        data.complete_quest("Well");
+        let path = std::env::temp_dir().join("tiny_rpg_test_quests_save.txt");
+        Save::from_data(&data).write(&path).unwrap();
+
+        let mut loaded = one_level_data();
+        let save = Save::read(&path);
+        fs::remove_file(&path).unwrap();
+        save.unwrap().apply(&mut loaded).unwrap();
+        assert_eq!(loaded.get_current_quests(), data.get_current_quests());
+        assert_eq!(loaded.get_complete_quests(), data.get_complete_quests());
+    }
+}
@@ -0,0 +1,1003 @@
+//! The screens of the game, such as the main menu and the game itself.
+//!
+//! Screens are kept on a stack in Data. Only the top screen gets input,
+//! while rendering starts at the highest screen that isn't an overlay, so a
+//! pause menu can be drawn on top of the game behind it.
+//!
+//! The main menu and settings are menu levels. They are built like any
+//! other level, and the player picks an option by walking into it. A
+//! campaign can replace them with its own MainMenu and Settings levels.
+//!
+//! Other screens are built from ui widgets, and turn what the player does
+//! with them into transitions.
+//!
+//! # Planned changes:
+//!
+//! - Fill in the inventory, combat and dialog screens.
+//! - Draw menu options with text once there is text rendering.
+
+use crate::camera::Camera;
+use crate::data::Data;
+use crate::entity::{Entity, Skin};
+use crate::events::{self, PlayerAction};
+use crate::fov::FieldOfView;
+use crate::helper::Builder;
+use crate::input::{InputEvent, MouseButton, PointerEvent};
+use crate::maps::{Level, LevelId, LevelType, Map};
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+use crate::messages::MessageCategory;
+use crate::save::Save;
+use crate::text::Text;
+use crate::ui::{self, Area, ProgressBar, Ui, UiEvent, Widget};
+
+use std::time::Duration;
+
+/// How far the player can see in a menu level. Menus should always be
+/// fully shown.
+const MENU_SIGHT_RADIUS: u32 = 100;
+/// How many messages the history scrolls by a page.
+const HISTORY_PAGE: usize = 10;
+/// How wide the context menu is, as a fraction of the window.
+const CONTEXT_MENU_WIDTH: f32 = 0.18;
+/// How tall each row of the context menu is, as a fraction of the window.
+const CONTEXT_MENU_ROW_HEIGHT: f32 = 0.05;
+
+/// What should happen to the screen stack after a screen handles an event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transition {
+    /// Nothing changes.
+    Stay,
+    /// Puts a new screen on top of the current one.
+    Push(Screen),
+    /// Removes the current screen, going back to the one below.
+    Pop,
+    /// Swaps the current screen for a new one.
+    Replace(Screen),
+    /// Throws away every screen and starts the stack over with a new one.
+    Reset(Screen),
+    /// Closes the game.
+    Quit,
+}
+
+/// The things a menu option can do when it is chosen.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MenuOption {
+    /// Starts the game from the beginning.
+    NewGame,
+    /// Closes the menu and goes back to the game.
+    Resume,
+    /// Saves the game to the default save.
+    Save,
+    /// Loads the game from the default save.
+    Load,
+    /// Opens the settings.
+    Settings,
+    /// Goes back to the main menu.
+    MainMenu,
+    /// Leaves the menu, going back to the screen before it.
+    Back,
+    /// Closes the game.
+    Quit,
+    /// Waits for an input and adds it to the inputs of the action.
+    Rebind(PlayerAction),
+    /// Removes the newest input of the action, from the profile of the
+    /// controller last used if it has one.
+    Unbind(PlayerAction),
+    /// Puts every key binding back to its default.
+    ResetBindings,
+}
+
+impl MenuOption {
+    /// Returns the name of the option, used when writing files.
+    pub fn get_name(&self) -> String {
+        match self {
+            MenuOption::NewGame => "new_game".to_string(),
+            MenuOption::Resume => "resume".to_string(),
+            MenuOption::Save => "save".to_string(),
+            MenuOption::Load => "load".to_string(),
+            MenuOption::Settings => "settings".to_string(),
+            MenuOption::MainMenu => "main_menu".to_string(),
+            MenuOption::Back => "back".to_string(),
+            MenuOption::Quit => "quit".to_string(),
+            MenuOption::Rebind(action) => format!("rebind:{}", action.get_name()),
+            MenuOption::Unbind(action) => format!("unbind:{}", action.get_name()),
+            MenuOption::ResetBindings => "reset_bindings".to_string(),
+        }
+    }
+    /// Returns the name of the option as it is shown to the player, like
+    /// "Rebind move left".
+    pub fn get_label(&self) -> String {
+        let words = self.get_name().replace([':', '_'], " ");
+        let mut characters = words.chars();
+        match characters.next() {
+            Some(first) => first.to_uppercase().chain(characters).collect(),
+            None => words,
+        }
+    }
+    /// Returns the option with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<MenuOption> {
+        match name {
+            "new_game" => Some(MenuOption::NewGame),
+            "resume" => Some(MenuOption::Resume),
+            "save" => Some(MenuOption::Save),
+            "load" => Some(MenuOption::Load),
+            "settings" => Some(MenuOption::Settings),
+            "main_menu" => Some(MenuOption::MainMenu),
+            "back" => Some(MenuOption::Back),
+            "quit" => Some(MenuOption::Quit),
+            "reset_bindings" => Some(MenuOption::ResetBindings),
+            _ => {
+                if let Some(action) = name.strip_prefix("rebind:") {
+                    PlayerAction::from_name(action).map(MenuOption::Rebind)
+                } else if let Some(action) = name.strip_prefix("unbind:") {
+                    PlayerAction::from_name(action).map(MenuOption::Unbind)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    /// The skin menu option entities and bars use until menus have text.
+    pub fn get_skin(&self) -> Skin {
+        match self {
+            MenuOption::NewGame | MenuOption::Resume => Skin::RGB(60, 180, 75),
+            MenuOption::Save => Skin::RGB(0, 130, 200),
+            MenuOption::Load => Skin::RGB(70, 200, 220),
+            MenuOption::Settings => Skin::RGB(145, 110, 180),
+            MenuOption::MainMenu | MenuOption::Back => Skin::RGB(245, 130, 50),
+            MenuOption::Quit => Skin::RGB(200, 40, 40),
+            MenuOption::Rebind(action) => {
+                // spread the actions around so neighbouring tiles differ
+                let index = PlayerAction::ALL
+                    .iter()
+                    .position(|other| other == action)
+                    .unwrap_or(0) as u32;
+                Skin::RGB(
+                    (80 + index * 53 % 170) as u8,
+                    (200 - index * 31 % 150) as u8,
+                    (120 + index * 71 % 130) as u8,
+                )
+            }
+            MenuOption::Unbind(action) => {
+                // a darker version of the rebind tile
+                let (red, green, blue) = MenuOption::Rebind(*action)
+                    .get_skin()
+                    .get_rgb_colors()
+                    .unwrap();
+                Skin::RGB(red / 2, green / 2, blue / 2)
+            }
+            MenuOption::ResetBindings => Skin::RGB(220, 220, 220),
+        }
+    }
+}
+
+/// A list of options where one is selected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Menu {
+    options: Vec<MenuOption>,
+    selected: usize,
+}
+
+impl Menu {
+    /// Creates a new instance of Menu with the first option selected.
+    pub fn new(options: Vec<MenuOption>) -> Menu {
+        Menu {
+            options,
+            selected: 0,
+        }
+    }
+    /// The options of the pause menu.
+    pub fn pause_menu() -> Menu {
+        Menu::new(vec![
+            MenuOption::Resume,
+            MenuOption::Save,
+            MenuOption::Load,
+            MenuOption::Settings,
+            MenuOption::MainMenu,
+            MenuOption::Quit,
+        ])
+    }
+    /// Returns a reference to the options.
+    pub fn get_options(&self) -> &Vec<MenuOption> {
+        &self.options
+    }
+    /// Returns the index of the selected option.
+    pub fn get_selected(&self) -> usize {
+        self.selected
+    }
+    /// Moves the selection up one, wrapping around to the bottom.
+    pub fn select_previous(&mut self) {
+        if self.options.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + self.options.len() - 1) % self.options.len();
+    }
+    /// Moves the selection down one, wrapping around to the top.
+    pub fn select_next(&mut self) {
+        if self.options.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + 1) % self.options.len();
+    }
+    /// Handles moving the selection and choosing an option.
+    fn handle_action(&mut self, action: PlayerAction, data: &mut Data) -> Transition {
+        match action {
+            PlayerAction::MoveUp => self.select_previous(),
+            PlayerAction::MoveDown => self.select_next(),
+            PlayerAction::Confirm => {
+                if let Some(option) = self.options.get(self.selected) {
+                    return choose_option(*option, data);
+                }
+            }
+            _ => {}
+        }
+        Transition::Stay
+    }
+}
+
+/// Performs a menu option and returns what happens to the screen stack.
+pub fn choose_option(option: MenuOption, data: &mut Data) -> Transition {
+    match option {
+        MenuOption::NewGame => {
+            data.new_game();
+            Transition::Reset(Screen::Game)
+        }
+        MenuOption::Resume => Transition::Pop,
+        MenuOption::Save => {
+            match Save::from_data(data).write(&data.get_save_path()) {
+                Ok(()) => data.add_message(MessageCategory::System, "Game saved."),
+                Err(error) => data.add_error(&error.to_string()),
+            }
+            Transition::Stay
+        }
+        MenuOption::Load => {
+            let path = data.get_save_path();
+            match Save::read(&path).and_then(|save| save.apply(data)) {
+                Ok(()) => {
+                    data.add_message(MessageCategory::System, "Game loaded.");
+                    Transition::Reset(Screen::Game)
+                }
+                Err(error) => {
+                    data.add_error(&error.to_string());
+                    Transition::Stay
+                }
+            }
+        }
+        MenuOption::Settings => Transition::Push(Screen::Settings(MenuLevel::for_type(
+            data.get_map(),
+            LevelType::Settings,
+        ))),
+        MenuOption::MainMenu => Transition::Reset(Screen::MainMenu(MenuLevel::for_type(
+            data.get_map(),
+            LevelType::MainMenu,
+        ))),
+        MenuOption::Back => Transition::Pop,
+        MenuOption::Quit => Transition::Quit,
+        MenuOption::Rebind(action) => Transition::Push(Screen::Rebind(Rebinding::new(action))),
+        MenuOption::Unbind(action) => {
+            let profile = data.get_last_profile_name().map(str::to_string);
+            if data
+                .get_mut_settings()
+                .unbind(action, profile.as_deref())
+                .is_some()
+            {
+                save_settings(data);
+            }
+            Transition::Stay
+        }
+        MenuOption::ResetBindings => {
+            data.get_mut_settings().reset_bindings();
+            save_settings(data);
+            Transition::Stay
+        }
+    }
+}
+
+/// Writes the settings to the settings file so they last between games.
+fn save_settings(data: &mut Data) {
+    let path = data.get_settings_path();
+    if let Err(error) = data.get_settings().write(&path) {
+        data.add_error(&error.to_string());
+    }
+}
+
+/// A menu the player walks around in. Options are entities with a menu
+/// ability, and walking into one chooses it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MenuLevel {
+    level: Level,
+    player: Entity,
+    field_of_view: FieldOfView,
+    camera: Camera,
+}
+
+impl MenuLevel {
+    /// Creates a new instance of MenuLevel. The player starts at the
+    /// level's spawn, or the top left corner inside the boundary if it has
+    /// none.
+    pub fn new(level: Level) -> MenuLevel {
+        let spawn = level.get_spawn().unwrap_or_else(|| Point::new(1, 1));
+        let mut menu_level = MenuLevel {
+            level,
+            player: Entity::new_player(spawn.get_x(), spawn.get_y()),
+            field_of_view: FieldOfView::compute(spawn, MENU_SIGHT_RADIUS, &[]),
+            camera: Camera::new().build().unwrap(),
+        };
+        menu_level.update_field_of_view();
+        menu_level
+    }
+    /// Uses the first level of level_type in the map if there is one,
+    /// otherwise the built in menu for that type.
+    pub fn for_type(map: &Map, level_type: LevelType) -> MenuLevel {
+        match map
+            .find_level(level_type, 1)
+            .and_then(|id| map.create_level(id))
+        {
+            Some(level) => MenuLevel::new(level),
+            None => match level_type {
+                LevelType::Settings => MenuLevel::settings(),
+                _ => MenuLevel::main_menu(),
+            },
+        }
+    }
+    /// The built in main menu. New game is up, quit is down, load is left
+    /// and settings is right.
+    pub fn main_menu() -> MenuLevel {
+        let mut entities = vec![
+            Entity::new_menu_option(8, 2, 5, 2, MenuOption::NewGame),
+            Entity::new_menu_option(3, 5, 2, 3, MenuOption::Load),
+            Entity::new_menu_option(16, 5, 2, 3, MenuOption::Settings),
+            Entity::new_menu_option(8, 9, 5, 2, MenuOption::Quit),
+        ];
+        entities.append(&mut Map::get_boundry_entities(Rectangle::new(0, 0, 21, 13)));
+        MenuLevel::new(
+            Level::new()
+                .id(LevelId::new(0))
+                .name("Main Menu")
+                .level_type(LevelType::MainMenu)
+                .number(1)
+                .entities(entities)
+                .spawn(Point::new(10, 6))
+                .build()
+                .unwrap(),
+        )
+    }
+    /// The built in settings menu. A row of tiles along the top adds a key
+    /// to each action, with a darker tile below it to remove one. Reset
+    /// and back are at the bottom.
+    pub fn settings() -> MenuLevel {
+        let mut entities: Vec<Entity> = Vec::new();
+        let actions: Vec<&PlayerAction> = PlayerAction::ALL
+            .iter()
+            .filter(|action| **action != PlayerAction::Back)
+            .collect();
+        // wide enough for a tile per action with a gap at each end
+        let width = actions.len() as i32 * 2 + 3;
+        for (index, action) in actions.into_iter().enumerate() {
+            let x = 2 + index as i32 * 2;
+            entities.push(Entity::new_menu_option(
+                x,
+                2,
+                1,
+                1,
+                MenuOption::Rebind(*action),
+            ));
+            entities.push(Entity::new_menu_option(
+                x,
+                4,
+                1,
+                1,
+                MenuOption::Unbind(*action),
+            ));
+        }
+        entities.push(Entity::new_menu_option(
+            2,
+            10,
+            3,
+            1,
+            MenuOption::ResetBindings,
+        ));
+        entities.push(Entity::new_menu_option(
+            width / 2 - 2,
+            10,
+            5,
+            1,
+            MenuOption::Back,
+        ));
+        entities.append(&mut Map::get_boundry_entities(Rectangle::new(
+            0,
+            0,
+            width as u32,
+            13,
+        )));
+        MenuLevel::new(
+            Level::new()
+                .id(LevelId::new(0))
+                .name("Settings")
+                .level_type(LevelType::Settings)
+                .number(1)
+                .entities(entities)
+                .spawn(Point::new(width / 2, 7))
+                .build()
+                .unwrap(),
+        )
+    }
+    /// Returns a reference to the level.
+    pub fn get_level(&self) -> &Level {
+        &self.level
+    }
+    /// Returns a reference to the player walking around the menu.
+    pub fn get_player(&self) -> &Entity {
+        &self.player
+    }
+    /// Returns what the player can see in the menu.
+    pub fn get_field_of_view(&self) -> &FieldOfView {
+        &self.field_of_view
+    }
+    /// Returns the camera looking at the menu.
+    pub fn get_camera(&self) -> &Camera {
+        &self.camera
+    }
+    /// Moves the camera after the player, inside the menu's walls.
+    pub fn update_camera(&mut self, tile_size: u32, screen_size: Dimension, elapsed: Duration) {
+        self.camera.follow(
+            self.player.get_rectangle(),
+            self.level.get_bounds(),
+            tile_size,
+            screen_size,
+            elapsed,
+        );
+    }
+    /// Moves the player if the action is a move. Returns the option of the
+    /// entity in the way, if there was one with an option.
+    fn move_player(&mut self, action: PlayerAction) -> Option<MenuOption> {
+        let destination = match action {
+            PlayerAction::MoveLeft => self.player.get_move_left(),
+            PlayerAction::MoveRight => self.player.get_move_right(),
+            PlayerAction::MoveUp => self.player.get_move_up(),
+            PlayerAction::MoveDown => self.player.get_move_down(),
+            _ => return None,
+        };
+        let blocking = self
+            .level
+            .get_level_entities()
+            .iter()
+            .find(|entity| entity.get_rectangle().is_colliding(destination));
+        match blocking {
+            Some(entity) => entity.get_abilities().get_menu_option(),
+            None if self.level.get_tiles().get_area_cost(destination).is_none() => None,
+            None => {
+                self.player
+                    .set_position(Point::new(destination.x(), destination.y()));
+                self.update_field_of_view();
+                None
+            }
+        }
+    }
+    fn update_field_of_view(&mut self) {
+        let opaque: Vec<Rectangle> = self
+            .level
+            .get_level_entities()
+            .iter()
+            .filter(|entity| entity.get_abilities().is_opaque())
+            .map(|entity| entity.get_rectangle())
+            .collect();
+        self.field_of_view =
+            FieldOfView::compute(self.player.get_position(), MENU_SIGHT_RADIUS, &opaque);
+    }
+    /// Handles walking around the menu level.
+    fn handle_action(&mut self, action: PlayerAction, data: &mut Data) -> Transition {
+        match self.move_player(action) {
+            Some(option) => choose_option(option, data),
+            None => Transition::Stay,
+        }
+    }
+}
+
+/// Waiting for a key, button or stick to add to an action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rebinding {
+    action: PlayerAction,
+    conflict: Option<PlayerAction>,
+}
+
+impl Rebinding {
+    /// Creates a new instance of Rebinding for action.
+    pub fn new(action: PlayerAction) -> Rebinding {
+        Rebinding {
+            action,
+            conflict: None,
+        }
+    }
+    /// Returns the action an input is being bound to.
+    pub fn get_action(&self) -> PlayerAction {
+        self.action
+    }
+    /// Returns the action that already had the last input pressed, if it
+    /// was taken.
+    pub fn get_conflict(&self) -> Option<PlayerAction> {
+        self.conflict
+    }
+    /// Binds the next key, button or stick pressed to the action. Back
+    /// cancels, and an input that belongs to another action is refused and
+    /// shown as a conflict until a free one is pressed.
+    ///
+    /// Controller inputs go into the profile of the controller they were
+    /// pressed on.
+    fn handle_input(&mut self, input_event: &InputEvent, data: &mut Data) -> Transition {
+        if data.get_action(input_event) == Some(PlayerAction::Back) {
+            return Transition::Pop;
+        }
+        let profile = data.get_profile_name(input_event).map(str::to_string);
+        let input = input_event.get_input();
+        match data
+            .get_mut_settings()
+            .bind(input, self.action, profile.as_deref())
+        {
+            Ok(()) => {
+                save_settings(data);
+                Transition::Pop
+            }
+            Err(other) => {
+                self.conflict = Some(other);
+                Transition::Stay
+            }
+        }
+    }
+}
+
+/// Scrolling back through the message log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageHistory {
+    scroll: usize,
+}
+
+impl MessageHistory {
+    /// Creates a new instance of MessageHistory showing the newest
+    /// messages.
+    pub fn new() -> MessageHistory {
+        MessageHistory { scroll: 0 }
+    }
+    /// Returns how many messages are hidden below the bottom of the
+    /// history, 0 when the newest message is shown.
+    pub fn get_scroll(&self) -> usize {
+        self.scroll
+    }
+    /// Scrolls towards older messages by count, stopping so the oldest of
+    /// the total messages is still shown.
+    pub fn scroll_up(&mut self, count: usize, total: usize) {
+        self.scroll = (self.scroll + count).min(total.saturating_sub(1));
+    }
+    /// Scrolls towards newer messages by count.
+    pub fn scroll_down(&mut self, count: usize) {
+        self.scroll = self.scroll.saturating_sub(count);
+    }
+    /// Up and down scroll a message at a time, left and right a page.
+    /// Back, confirm or history closes it.
+    fn handle_action(&mut self, action: PlayerAction, data: &mut Data) -> Transition {
+        let total = data.get_messages().len();
+        match action {
+            PlayerAction::MoveUp => self.scroll_up(1, total),
+            PlayerAction::MoveDown => self.scroll_down(1),
+            PlayerAction::MoveLeft => self.scroll_up(HISTORY_PAGE, total),
+            PlayerAction::MoveRight => self.scroll_down(HISTORY_PAGE),
+            PlayerAction::Back | PlayerAction::Confirm | PlayerAction::History => {
+                return Transition::Pop
+            }
+            _ => {}
+        }
+        Transition::Stay
+    }
+}
+
+impl Default for MessageHistory {
+    fn default() -> Self {
+        MessageHistory::new()
+    }
+}
+
+/// Something the player can do to a tile from the context menu.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Interaction {
+    /// Walks to the tile.
+    WalkHere,
+    /// Walks into the warp on the tile.
+    Enter,
+    /// Hits what is on the tile, when it is next to the player.
+    Strike,
+    /// Writes what is on the tile in the message log.
+    Inspect,
+    /// Closes the menu.
+    Cancel,
+}
+
+impl Interaction {
+    /// Returns what the menu shows for the interaction.
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Interaction::WalkHere => "Walk here",
+            Interaction::Enter => "Enter",
+            Interaction::Strike => "Strike",
+            Interaction::Inspect => "Inspect",
+            Interaction::Cancel => "Cancel",
+        }
+    }
+}
+
+/// The menu right clicking a tile opens, listing what can be done to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContextMenu {
+    target: Point,
+    interactions: Vec<Interaction>,
+    ui: Ui,
+}
+
+impl ContextMenu {
+    /// Creates a new instance of ContextMenu for the tile at target, opened
+    /// with the mouse at pointer.
+    pub fn new(data: &Data, target: Point, pointer: Point) -> ContextMenu {
+        let tile = Rectangle::new(target.get_x(), target.get_y(), 1, 1);
+        let mut interactions = Vec::new();
+        match data.check_position(tile) {
+            Some(entity) if entity.get_abilities().is_warp().is_some() => {
+                interactions.push(Interaction::Enter)
+            }
+            Some(entity) if entity.get_health().is_some() => {
+                let player = data.get_player().get_position();
+                let distance = (player.get_x() - target.get_x()).abs()
+                    + (player.get_y() - target.get_y()).abs();
+                if distance == 1 {
+                    interactions.push(Interaction::Strike)
+                }
+            }
+            Some(_) => {}
+            None if !data.get_player().get_rectangle().is_colliding(tile) => {
+                interactions.push(Interaction::WalkHere)
+            }
+            None => {}
+        }
+        if data.describe_tile(target).is_some() {
+            interactions.push(Interaction::Inspect);
+        }
+        interactions.push(Interaction::Cancel);
+
+        // next to the mouse, but kept on the screen
+        let (screen_width, screen_height) = data.get_screen_size().get_coordinates();
+        let (width, height) = (
+            CONTEXT_MENU_WIDTH,
+            CONTEXT_MENU_ROW_HEIGHT * interactions.len() as f32,
+        );
+        let x = (pointer.get_x() as f32 / screen_width.max(1) as f32).min(1.0 - width);
+        let y = (pointer.get_y() as f32 / screen_height.max(1) as f32).min(1.0 - height);
+        let items = interactions
+            .iter()
+            .map(|interaction| interaction.get_label().to_string())
+            .collect();
+        ContextMenu {
+            target,
+            ui: Ui::new().add(
+                "interactions",
+                Widget::List(ui::List::new(items, interactions.len())),
+                Area::new(x.max(0.0), y.max(0.0), width, height),
+            ),
+            interactions,
+        }
+    }
+    /// Returns the tile the menu is for.
+    pub fn get_target(&self) -> Point {
+        self.target
+    }
+    /// Returns what can be done, in the order they are listed.
+    pub fn get_interactions(&self) -> &Vec<Interaction> {
+        &self.interactions
+    }
+    /// Returns the widgets of the menu.
+    pub fn get_ui(&self) -> &Ui {
+        &self.ui
+    }
+    /// Does an interaction and closes the menu.
+    fn interact(&self, interaction: Interaction, data: &mut Data) -> Transition {
+        match interaction {
+            Interaction::WalkHere | Interaction::Enter => {
+                data.travel_to(self.target);
+            }
+            Interaction::Strike => events::strike(data, self.target),
+            Interaction::Inspect => {
+                if let Some(description) = data.describe_tile(self.target) {
+                    data.add_message(MessageCategory::System, &description);
+                }
+            }
+            Interaction::Cancel => {}
+        }
+        Transition::Pop
+    }
+    /// Turns what the player did in the menu into a transition.
+    fn handle_event(&self, event: Option<UiEvent>, data: &mut Data) -> Transition {
+        match event {
+            Some(UiEvent::Chosen(_, index)) => match self.interactions.get(index) {
+                Some(interaction) => self.interact(*interaction, data),
+                None => Transition::Pop,
+            },
+            Some(UiEvent::Back) => Transition::Pop,
+            _ => Transition::Stay,
+        }
+    }
+    /// Clicking anywhere outside the menu closes it.
+    fn handle_pointer(&mut self, pointer: PointerEvent, data: &mut Data) -> Transition {
+        let screen_size = data.get_screen_size();
+        if let PointerEvent::Clicked(_, point) = pointer {
+            let inside = self
+                .ui
+                .place(screen_size)
+                .iter()
+                .any(|rect| rect.contains_point(point));
+            if !inside {
+                return Transition::Pop;
+            }
+        }
+        let event = self.ui.handle_pointer(pointer, screen_size);
+        self.handle_event(event, data)
+    }
+}
+
+/// The screens of the game. Each one handles its own input, and is drawn
+/// by render::render_screen based on which screen it is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Screen {
+    /// The first screen, for starting or loading a game.
+    MainMenu(MenuLevel),
+    /// Changing settings, such as key bindings.
+    Settings(MenuLevel),
+    /// Waiting for an input to bind to an action.
+    Rebind(Rebinding),
+    /// WIP Looking through the players inventory.
+    Inventory(Ui),
+    /// WIP Fighting.
+    Combat,
+    /// Playing the game.
+    Game,
+    /// WIP Talking with an entity.
+    Dialog,
+    /// The pause menu, shown over the game.
+    Pause(Menu),
+    /// The message log, shown over the game.
+    History(MessageHistory),
+    /// What can be done to a tile, shown over the game.
+    Context(ContextMenu),
+}
+
+impl Screen {
+    /// Returns true if the screens below this one should still be drawn.
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Screen::Pause(_)
+                | Screen::Rebind(_)
+                | Screen::Inventory(_)
+                | Screen::Dialog
+                | Screen::History(_)
+                | Screen::Context(_)
+        )
+    }
+    /// Handles an input while this is the top screen.
+    pub fn handle_input(&mut self, input_event: &InputEvent, data: &mut Data) -> Transition {
+        if let Screen::Rebind(rebinding) = self {
+            return rebinding.handle_input(input_event, data);
+        }
+        if let Screen::Inventory(ui) = self {
+            let event = ui.handle_input(input_event.get_input(), data.get_action(input_event));
+            return handle_inventory_event(event, data);
+        }
+        if let Screen::Context(menu) = self {
+            let event = menu
+                .ui
+                .handle_input(input_event.get_input(), data.get_action(input_event));
+            return menu.handle_event(event, data);
+        }
+        match data.get_action(input_event) {
+            Some(action) => self.handle_action(action, data),
+            None => Transition::Stay,
+        }
+    }
+    /// Handles an action while this is the top screen.
+    pub fn handle_action(&mut self, action: PlayerAction, data: &mut Data) -> Transition {
+        // works the same on every screen
+        if action == PlayerAction::ToggleFullscreen {
+            data.toggle_fullscreen();
+            return Transition::Stay;
+        }
+        match self {
+            Screen::MainMenu(menu_level) => menu_level.handle_action(action, data),
+            Screen::Settings(menu_level) => match action {
+                PlayerAction::Back => Transition::Pop,
+                _ => menu_level.handle_action(action, data),
+            },
+            Screen::Pause(menu) => match action {
+                PlayerAction::Back => Transition::Pop,
+                _ => menu.handle_action(action, data),
+            },
+            Screen::Game => match action {
+                PlayerAction::Back => Transition::Push(Screen::Pause(Menu::pause_menu())),
+                PlayerAction::Inventory => Transition::Push(Screen::Inventory(inventory_ui(data))),
+                PlayerAction::History => Transition::Push(Screen::History(MessageHistory::new())),
+                PlayerAction::Settings => choose_option(MenuOption::Settings, data),
+                _ => {
+                    action.perform_action(data);
+                    Transition::Stay
+                }
+            },
+            Screen::History(history) => history.handle_action(action, data),
+            Screen::Context(menu) => {
+                let event = menu.ui.handle_action(action);
+                menu.handle_event(event, data)
+            }
+            Screen::Inventory(ui) => handle_inventory_event(ui.handle_action(action), data),
+            Screen::Combat | Screen::Dialog => match action {
+                PlayerAction::Back | PlayerAction::Confirm => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            // handled above
+            Screen::Rebind(_) => Transition::Stay,
+        }
+    }
+    /// Handles the mouse while this is the top screen. In the game left
+    /// clicking walks to a tile and right clicking opens its context menu.
+    pub fn handle_pointer(&mut self, pointer: PointerEvent, data: &mut Data) -> Transition {
+        match self {
+            Screen::Game => match pointer {
+                PointerEvent::Clicked(MouseButton::Left, point) => {
+                    data.travel_to(data.screen_to_world(point));
+                    Transition::Stay
+                }
+                PointerEvent::Clicked(MouseButton::Right, point) => {
+                    data.cancel_travel();
+                    let target = data.screen_to_world(point);
+                    Transition::Push(Screen::Context(ContextMenu::new(data, target, point)))
+                }
+                _ => Transition::Stay,
+            },
+            Screen::Context(menu) => menu.handle_pointer(pointer, data),
+            Screen::Inventory(ui) => {
+                handle_inventory_event(ui.handle_pointer(pointer, data.get_screen_size()), data)
+            }
+            _ => Transition::Stay,
+        }
+    }
+    /// Handles typed text while this is the top screen.
+    pub fn handle_text(&mut self, text: &str, data: &mut Data) -> Transition {
+        match self {
+            Screen::Inventory(ui) => handle_inventory_event(ui.handle_text(text), data),
+            _ => Transition::Stay,
+        }
+    }
+}
+
+/// The widgets of the inventory. There are no items yet, so it shows the
+/// player's health and an empty list.
+pub fn inventory_ui(data: &Data) -> Ui {
+    let (health, most) = data.get_player().get_health().unwrap_or((0, 0));
+    Ui::new()
+        .add(
+            "panel",
+            Widget::Panel(ui::PANEL_COLOR),
+            Area::new(0.2, 0.15, 0.6, 0.7),
+        )
+        .add(
+            "title",
+            Widget::Label(Text::new("Inventory", ui::TEXT_COLOR)),
+            Area::new(0.25, 0.19, 0.5, 0.05),
+        )
+        .add(
+            "health",
+            Widget::ProgressBar(ProgressBar::new(health, most, ui::HEALTH_COLOR)),
+            Area::new(0.25, 0.26, 0.5, 0.05),
+        )
+        .add(
+            "items",
+            Widget::List(ui::List::new(vec!["Nothing yet".to_string()], 8)),
+            Area::new(0.25, 0.34, 0.5, 0.38),
+        )
+        .add(
+            "close",
+            Widget::Button("Close".to_string()),
+            Area::new(0.4, 0.75, 0.2, 0.06),
+        )
+}
+
+/// Turns what the player did in the inventory into a transition. Close,
+/// back and the inventory action leave it.
+fn handle_inventory_event(event: Option<UiEvent>, data: &mut Data) -> Transition {
+    match event {
+        Some(UiEvent::Back) | Some(UiEvent::Unused(PlayerAction::Inventory)) => Transition::Pop,
+        Some(UiEvent::Pressed(name)) if name == "close" => Transition::Pop,
+        Some(UiEvent::Unused(PlayerAction::ToggleFullscreen)) => {
+            data.toggle_fullscreen();
+            Transition::Stay
+        }
+        _ => Transition::Stay,
+    }
+}
+
+/// Gives the input to the top screen and changes the stack based on what
+/// it returns.
+pub fn handle_input(input_event: &InputEvent, data: &mut Data) {
+    with_top_screen(data, |screen, data| screen.handle_input(input_event, data));
+}
+
+/// Gives what the mouse did to the top screen.
+pub fn handle_pointer(pointer: PointerEvent, data: &mut Data) {
+    with_top_screen(data, |screen, data| screen.handle_pointer(pointer, data));
+}
+
+/// Gives typed text to the top screen.
+pub fn handle_text(text: &str, data: &mut Data) {
+    with_top_screen(data, |screen, data| screen.handle_text(text, data));
+}
+
+/// Gives an action straight to the top screen, skipping bindings. This is
+/// the way in for frontends that don't use SDL inputs.
+pub fn handle_action(action: PlayerAction, data: &mut Data) {
+    with_top_screen(data, |screen, data| screen.handle_action(action, data));
+}
+
+/// Runs handle on the top screen and applies the transition it returns.
+fn with_top_screen<F: FnOnce(&mut Screen, &mut Data) -> Transition>(data: &mut Data, handle: F) {
+    let index = match data.get_screens().len() {
+        0 => return,
+        length => length - 1,
+    };
+    // taken out so it can change data, put back at the same index so
+    // screens pushed while handling stay above it
+    let mut screen = data.get_mut_screens().remove(index);
+    let transition = handle(&mut screen, data);
+    data.get_mut_screens().insert(index, screen);
+    apply_transition(transition, data);
+}
+
+/// Changes the screen stack in data. Anything held down stops repeating
+/// when the screen changes, so walking into a menu option doesn't carry on
+/// into the next screen.
+pub fn apply_transition(transition: Transition, data: &mut Data) {
+    if transition != Transition::Stay {
+        data.get_mut_input_state().clear_held();
+    }
+    match transition {
+        Transition::Stay => {}
+        Transition::Push(screen) => data.push_screen(screen),
+        Transition::Pop => {
+            data.pop_screen();
+        }
+        Transition::Replace(screen) => {
+            data.get_mut_screens().pop();
+            data.push_screen(screen);
+        }
+        Transition::Reset(screen) => {
+            data.clear_screens();
+            data.push_screen(screen);
+        }
+        Transition::Quit => data.quit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hud::HudItem;
+    use std::path::Path;
+
+    #[test]
+    fn loading_without_a_save_shows_why() {
+        let mut data = Data::new();
+        data.set_save_directory(Path::new("no/such/directory"));
+        assert!(matches!(data.get_screen(), Some(Screen::MainMenu(_))));
+        assert_eq!(choose_option(MenuOption::Load, &mut data), Transition::Stay);
+
+        let screen = data.get_screen().unwrap();
+        let items = data.get_hud().get_screen_items(screen);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0, HudItem::Messages);
+        let text = items[0].0.get_text(&data).unwrap();
+        let last = text.get_spans().last().unwrap();
+        assert_eq!(last.get_color(), crate::messages::ERROR_COLOR);
+    }
+}
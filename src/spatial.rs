@@ -0,0 +1,62 @@
+//! A grid for quickly finding the entities in part of a level.
+//!
+//! The level is split into square cells and every entity is listed in the
+//! cells it covers. Looking up an area only has to check the entities in
+//! the cells the area touches, instead of every entity in the level. The
+//! renderer uses it to skip everything off screen.
+//!
+//! The grid only holds indices, so it has to be rebuilt whenever the list
+//! of entities it was built from changes.
+//!
+//! # Planned changes:
+//!
+//! - Move entities between cells instead of rebuilding.
+
+use std::collections::HashMap;
+
+use crate::math::Rectangle;
+
+/// The width and height of a cell in tiles.
+pub const CELL_SIZE: i32 = 16;
+
+/// Entity indices sorted into cells of CELL_SIZE tiles.
+#[derive(Clone, Debug, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// Creates a new instance of SpatialGrid from the rectangles of a list
+    /// of entities. The index of each rectangle is what queries return.
+    pub fn new<I: IntoIterator<Item = Rectangle>>(rectangles: I) -> SpatialGrid {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, rectangle) in rectangles.into_iter().enumerate() {
+            for cell in get_cells(rectangle) {
+                cells.entry(cell).or_default().push(index);
+            }
+        }
+        SpatialGrid { cells }
+    }
+    /// Returns the index of every rectangle that might touch area, in the
+    /// order they were given. Big rectangles can be in more than one cell
+    /// but are only returned once.
+    pub fn query(&self, area: Rectangle) -> Vec<usize> {
+        let mut indices: Vec<usize> = get_cells(area)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+/// Returns every cell a rectangle covers.
+fn get_cells(rectangle: Rectangle) -> impl Iterator<Item = (i32, i32)> {
+    let x_min = rectangle.x_min().div_euclid(CELL_SIZE);
+    let x_max = rectangle.x_max().div_euclid(CELL_SIZE);
+    let y_min = rectangle.y_min().div_euclid(CELL_SIZE);
+    let y_max = rectangle.y_max().div_euclid(CELL_SIZE);
+    (y_min..=y_max).flat_map(move |y| (x_min..=x_max).map(move |x| (x, y)))
+}
@@ -1,143 +1,68 @@
-//! The start sdl and argument handler for tiny rpg.
+//! The start sdl handler for tiny rpg.
 //!
-//! Checks given, arguments, environment variables etc. and parses
-//! them to something readable for the main program.
-//!
-//! Initializes the sdl2 graphic. May be removed from here to make
-//! the code less sdl2 dependent.
+//! Reads the arguments and config file into an Environment, see
+//! environment.rs, then initializes the sdl2 graphic with it.
 use sdl2::pixels::Color;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
-use crate::helper::{Builder, BuilderError};
-use crate::math;
+use crate::environment::{Arguments, Environment, HELP};
 
 /// Things done before main_loop. adds created variable to an Init enum to send
 /// to main loop.
-pub fn main_start() -> Result<Init, ()> {
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let environment = Environment::new().set_title("An RPG").build().unwrap();
-
-    let window = video_subsystem
-        .window(
-            environment.get_window_title(),
-            environment.get_window_width(),
-            environment.get_window_height(),
-        )
-        .position_centered()
-        .build()
-        .unwrap();
-
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
-    canvas.set_draw_color(Color::RGB(0, 0, 0));
-    canvas.clear();
-    canvas.present();
-
-    Ok(Init::new(sdl_context, canvas, environment))
-}
-
-/// Builds the environment.
 ///
-/// In the future will handle parsing arguments, etc.
-pub struct EnvironmentBuilder {
-    window_title: &'static str,
-    window_size: math::Dimension,
-    tile_size: u32,
-}
+/// Returns None when there is nothing to run, like after printing --help.
+pub fn main_start() -> Result<Option<Init>, ()> {
+    let arguments = match Arguments::parse(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{}\nTry --help.", error);
+            return Err(());
+        }
+    };
+    if arguments.is_help() {
+        println!("{}", HELP);
+        return Ok(None);
+    }
+    let environment = match arguments.to_environment() {
+        Ok(environment) => environment,
+        Err(error) => {
+            eprintln!("{}", error);
+            return Err(());
+        }
+    };
 
-impl EnvironmentBuilder {
-    /// Sets the window height.
-    pub fn set_window_height(mut self, width: u32) -> Self {
-        self.window_size.set_width(width);
-        self
-    }
-    /// Sets the window width.
-    pub fn set_window_width(mut self, height: u32) -> Self {
-        self.window_size.set_height(height);
-        self
-    }
-    /// Sets the window height and width using a Dimension.
-    pub fn set_window_dimensions(mut self, dimensions: math::Dimension) -> Self {
-        self.window_size = dimensions;
-        self
-    }
-    /// Sets the title of the window.
-    pub fn set_window_title(mut self, title: &'static str) -> Self {
-        self.window_title = title;
-        self
-    }
-    /// Short hand for set_window_title.
-    ///
-    /// Probably will be removed or changed.
-    pub fn set_title(mut self, title: &'static str) -> Self {
-        self.window_title = title;
-        self
-    }
-    /// Set the size of tile, smallest entities, etc. in pixels.
-    pub fn set_tile_size(mut self, pixels: u32) -> Self {
-        self.tile_size = pixels;
-        self
+    let sdl_context = sdl2::init().unwrap();
+    let video_subsystem = sdl_context.video().unwrap();
+    let mut window_builder = video_subsystem.window(
+        environment.get_window_title(),
+        environment.get_window_width(),
+        environment.get_window_height(),
+    );
+    window_builder.position_centered().resizable();
+    let mut window = window_builder.build().unwrap();
+    if environment.is_fullscreen() {
+        if let Err(error) = window.set_fullscreen(environment.get_fullscreen_mode().into()) {
+            eprintln!("{}", error);
+        }
     }
-}
-
-impl Builder for EnvironmentBuilder {
-    type product = Environment;
 
-    /// Creates a new instance of EnvironmentBuilder.
-    /// May later handle parsing of arguments, etc.
-    fn new() -> EnvironmentBuilder {
-        let tile_size = 32;
-        EnvironmentBuilder {
-            window_size: math::Dimension::new(tile_size * 30, tile_size * 20),
-            window_title: "Application",
-            tile_size,
+    let mut canvas_builder = window.into_canvas();
+    if environment.is_vsync() {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    if let Some(renderer) = environment.get_renderer() {
+        match sdl2::render::drivers().position(|driver| driver.name == renderer) {
+            Some(index) => canvas_builder = canvas_builder.index(index as u32),
+            None => eprintln!("There is no {} renderer, using the default.", renderer),
         }
     }
-    /// Build Environment from EnvironmentBuilder.
-    fn build(self) -> Result<Environment, BuilderError> {
-        Ok(Environment {
-            window_size: self.window_size,
-            window_title: self.window_title,
-            tile_size: self.tile_size,
-        })
-    }
-}
-
-/// The Environment is used to get information from the environment surrounding the game.
-///
-/// Some of this may get merged with Settings from data.rs.
-pub struct Environment {
-    window_size: math::Dimension,
-    window_title: &'static str,
-    tile_size: u32,
-}
+    let mut canvas = canvas_builder.build().unwrap();
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+    canvas.present();
 
-impl Environment {
-    /// Creates an instance of EnvironmentBuilder to build Environment
-    pub fn new() -> EnvironmentBuilder {
-        EnvironmentBuilder::new()
-    }
-    /// Returns the window size.
-    pub fn get_window_size(&self) -> math::Dimension {
-        self.window_size
-    }
-    /// Returns the window width.
-    pub fn get_window_width(&self) -> u32 {
-        self.window_size.get_width()
-    }
-    /// Returns the window height.
-    pub fn get_window_height(&self) -> u32 {
-        self.window_size.get_height()
-    }
-    /// Returns the window title.
-    pub fn get_window_title(&self) -> &'static str {
-        self.window_title
-    }
-    /// Returns the tile size.
-    pub fn get_tile_size(&self) -> u32 {
-        self.tile_size
-    }
+    Ok(Some(Init::new(sdl_context, canvas, environment)))
 }
 
 /// Holds information that is sent to the main game loop.
@@ -0,0 +1,282 @@
+//! The ground of a level, a layer of tiles under the entities.
+//!
+//! Every tile can have a Terrain, like grass, water or lava. Terrain is
+//! drawn under everything else, says if a tile can be walked on and how
+//! many turns it takes to walk onto it. Tiles without a terrain are open
+//! and take one turn, the same as before there was a tile layer.
+//!
+//! Levels files add terrain types with a `terrain` record and fill
+//! rectangles of tiles with them using `tiles x y width height name`, see
+//! campaign.rs. A few types are there without being added.
+//!
+//! # Planned changes:
+//!
+//! - Terrain for the generated dungeons.
+//! - Entities other than the player taking longer on slow terrain.
+//! - Terrain that hurts, like lava you can walk into.
+
+use std::collections::HashMap;
+
+use crate::entity::Skin;
+use crate::math::{Point, Rectangle};
+
+/// How many turns it takes to walk onto a tile, unless its terrain says
+/// otherwise.
+pub const DEFAULT_COST: u32 = 1;
+/// The most tiles a single fill can cover, so a typo in a level file can't
+/// fill millions of them.
+pub const MAX_FILL_AREA: u64 = 250_000;
+
+/// A type of ground.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Terrain {
+    name: String,
+    cost: u32,
+    passable: bool,
+    skin: Skin,
+}
+
+impl Terrain {
+    /// Creates a new instance of Terrain that can be walked on and takes
+    /// DEFAULT_COST turns.
+    pub fn new(name: &str, skin: Skin) -> Terrain {
+        Terrain {
+            name: name.to_string(),
+            cost: DEFAULT_COST,
+            passable: true,
+            skin,
+        }
+    }
+    /// Sets how many turns it takes to walk onto the terrain, at least 1.
+    pub fn set_cost(mut self, cost: u32) -> Self {
+        self.cost = cost.max(1);
+        self
+    }
+    /// Sets if the terrain can be walked on.
+    pub fn set_passable(mut self, passable: bool) -> Self {
+        self.passable = passable;
+        self
+    }
+    /// Sets how the terrain is drawn.
+    pub fn set_skin(mut self, skin: Skin) -> Self {
+        self.skin = skin;
+        self
+    }
+    /// Returns the name, used in level files.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Returns how many turns it takes to walk onto the terrain.
+    pub fn get_cost(&self) -> u32 {
+        self.cost
+    }
+    /// Returns true if the terrain can be walked on.
+    pub fn is_passable(&self) -> bool {
+        self.passable
+    }
+    /// Returns how the terrain is drawn.
+    pub fn get_skin(&self) -> &Skin {
+        &self.skin
+    }
+    /// Returns what the player is told when looking at the terrain.
+    pub fn get_description(&self) -> String {
+        let mut name = self.name.replace('_', " ");
+        if let Some(first) = name.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        match (self.passable, self.cost) {
+            (false, _) => format!("{}, there is no way across.", name),
+            (true, 1) => format!("{}.", name),
+            (true, cost) => format!("{}, {} turns a step.", name, cost),
+        }
+    }
+    /// Reads a terrain from the rest of a level file record, the name
+    /// followed by key=value words:
+    /// `water cost=2 passable=true skin=rgb:40,70,150`.
+    pub fn from_record(record: &str) -> Result<Terrain, &'static str> {
+        let mut words = record.split_whitespace();
+        let mut terrain = match words.next() {
+            Some(name) => Terrain::new(name, Skin::RGB(0, 0, 0)),
+            None => return Err("Terrain needs a name."),
+        };
+        for word in words {
+            let (key, value) = word
+                .split_once('=')
+                .ok_or("Terrain records are a name then key=value words.")?;
+            terrain = match key {
+                "cost" => {
+                    terrain.set_cost(value.parse().map_err(|_| "Terrain cost is not a number.")?)
+                }
+                "passable" => terrain.set_passable(
+                    value
+                        .parse()
+                        .map_err(|_| "Terrain passable is not true or false.")?,
+                ),
+                "skin" => terrain.set_skin(Skin::from_record(value)?),
+                _ => return Err("Unknown terrain key."),
+            };
+        }
+        Ok(terrain)
+    }
+}
+
+/// The terrain of each tile of a level, along with the types of terrain
+/// it can use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    terrains: Vec<Terrain>,
+    tiles: HashMap<Point, usize>,
+}
+
+impl TileLayer {
+    /// Creates a new instance of TileLayer with no tiles and the built in
+    /// terrain types: floor, grass, water, deep_water and lava.
+    pub fn new() -> TileLayer {
+        TileLayer {
+            terrains: vec![
+                Terrain::new("floor", Skin::RGB(35, 35, 40)),
+                Terrain::new("grass", Skin::RGB(40, 85, 40)),
+                Terrain::new("water", Skin::RGB(40, 70, 150)).set_cost(2),
+                Terrain::new("deep_water", Skin::RGB(20, 35, 105)).set_passable(false),
+                Terrain::new("lava", Skin::RGB(170, 60, 20)).set_passable(false),
+            ],
+            tiles: HashMap::new(),
+        }
+    }
+    /// Adds a type of terrain. One with the same name is replaced, along
+    /// with any tiles already using it.
+    pub fn add_terrain(&mut self, terrain: Terrain) {
+        match self
+            .terrains
+            .iter()
+            .position(|known| known.get_name() == terrain.get_name())
+        {
+            Some(index) => self.terrains[index] = terrain,
+            None => self.terrains.push(terrain),
+        }
+    }
+    /// Returns every type of terrain the layer can use.
+    pub fn get_terrains(&self) -> &Vec<Terrain> {
+        &self.terrains
+    }
+    /// Sets every tile in area to the terrain called name. Returns an
+    /// error if there is no terrain with that name or the area is bigger
+    /// than MAX_FILL_AREA.
+    pub fn fill(&mut self, area: Rectangle, name: &str) -> Result<(), &'static str> {
+        if area.width() as u64 * area.height() as u64 > MAX_FILL_AREA {
+            return Err("Too many tiles to fill, check the width and height.");
+        }
+        let index = self
+            .terrains
+            .iter()
+            .position(|terrain| terrain.get_name() == name)
+            .ok_or("Unknown terrain, add it with a terrain record first.")?;
+        for point in area.get_points() {
+            self.tiles.insert(point, index);
+        }
+        Ok(())
+    }
+    /// Returns the terrain at point, if it has one.
+    pub fn get_terrain(&self, point: Point) -> Option<&Terrain> {
+        self.tiles.get(&point).map(|index| &self.terrains[*index])
+    }
+    /// Returns true if point can be walked on. Tiles without terrain can.
+    pub fn is_passable(&self, point: Point) -> bool {
+        self.get_cost(point).is_some()
+    }
+    /// Returns how many turns it takes to walk onto point, or None if it
+    /// can't be walked on.
+    pub fn get_cost(&self, point: Point) -> Option<u32> {
+        match self.get_terrain(point) {
+            Some(terrain) if !terrain.is_passable() => None,
+            Some(terrain) => Some(terrain.get_cost()),
+            None => Some(DEFAULT_COST),
+        }
+    }
+    /// Returns how many turns it takes for something covering area to move
+    /// onto it, the slowest of its tiles, or None if any can't be walked on.
+    pub fn get_area_cost(&self, area: Rectangle) -> Option<u32> {
+        area.get_points()
+            .map(|point| self.get_cost(point))
+            .try_fold(DEFAULT_COST, |most, cost| Some(most.max(cost?)))
+    }
+    /// Returns true if no tile has terrain.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+impl Default for TileLayer {
+    fn default() -> Self {
+        TileLayer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Water in the top left 2x2, lava at 3,0 and mud costing 3 at 0,3.
+    fn layer() -> TileLayer {
+        let mut tiles = TileLayer::new();
+        tiles.add_terrain(Terrain::from_record("mud cost=3").unwrap());
+        tiles.fill(Rectangle::new(0, 0, 2, 2), "water").unwrap();
+        tiles.fill(Rectangle::new(3, 0, 1, 1), "lava").unwrap();
+        tiles.fill(Rectangle::new(0, 3, 1, 1), "mud").unwrap();
+        tiles
+    }
+
+    #[test]
+    fn tiles_have_costs() {
+        let tiles = layer();
+        assert_eq!(tiles.get_cost(Point::new(1, 1)), Some(2));
+        assert_eq!(tiles.get_cost(Point::new(0, 3)), Some(3));
+        assert_eq!(tiles.get_cost(Point::new(3, 0)), None);
+        // no terrain is open ground
+        assert_eq!(tiles.get_cost(Point::new(9, 9)), Some(DEFAULT_COST));
+        assert!(tiles.is_passable(Point::new(9, 9)));
+        assert!(!tiles.is_passable(Point::new(3, 0)));
+    }
+
+    #[test]
+    fn areas_cost_their_slowest_tile() {
+        let tiles = layer();
+        assert_eq!(tiles.get_area_cost(Rectangle::new(5, 5, 2, 2)), Some(1));
+        assert_eq!(tiles.get_area_cost(Rectangle::new(1, 1, 2, 2)), Some(2));
+        assert_eq!(tiles.get_area_cost(Rectangle::new(0, 1, 1, 3)), Some(3));
+        assert_eq!(tiles.get_area_cost(Rectangle::new(2, 0, 2, 2)), None);
+    }
+
+    #[test]
+    fn fills_need_a_known_terrain_and_a_sane_size() {
+        let mut tiles = layer();
+        assert!(tiles.fill(Rectangle::new(0, 0, 1, 1), "marsh").is_err());
+        assert!(tiles.fill(Rectangle::new(0, 0, 1000, 1000), "mud").is_err());
+        assert_eq!(tiles.get_cost(Point::new(0, 0)), Some(2));
+        // adding a terrain again changes tiles already using it
+        tiles.add_terrain(Terrain::new("water", Skin::RGB(0, 0, 0)).set_cost(4));
+        assert_eq!(tiles.get_cost(Point::new(0, 0)), Some(4));
+    }
+
+    #[test]
+    fn terrain_records() {
+        let terrain = Terrain::from_record("bog cost=0 passable=false").unwrap();
+        assert_eq!(terrain.get_name(), "bog");
+        // costs are at least one turn
+        assert_eq!(terrain.get_cost(), 1);
+        assert!(!terrain.is_passable());
+        let terrain = Terrain::from_record("tar cost=4 skin=rgb:10,10,10").unwrap();
+        assert_eq!(terrain.get_cost(), 4);
+        assert_eq!(terrain.get_skin(), &Skin::RGB(10, 10, 10));
+        assert_eq!(terrain.get_description(), "Tar, 4 turns a step.");
+        for bad in [
+            "",
+            "bog cost",
+            "bog cost=slow",
+            "bog passable=no",
+            "bog depth=2",
+        ] {
+            assert!(Terrain::from_record(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Lays out text drawn with bitmap fonts.
+//!
+//! A font is an atlas with one glyph per frame, in ASCII order from a first
+//! character, usually the space. Every glyph is the same size, so a glyph's
+//! frame already holds the gap to the next one. Fonts are listed in
+//! atlases.txt like atlases, with the first character code at the end if
+//! it isn't 32:
+//!
+//! ```text
+//! font default font.bmp 6x8
+//! ```
+//!
+//! Black is see through, so glyphs are drawn white on black and tinted
+//! with the colour of their span.
+//!
+//! Text is made of spans of one colour. Layout wraps it at spaces to fit a
+//! width and aligns each line, giving glyph frames and rectangles in
+//! pixels. render::DrawList::add_text draws it. Spaces only go between
+//! words, so spaces at the start or end of a line are dropped and "  lead"
+//! is as wide as "lead".
+//!
+//! # Planned changes:
+//!
+//! - Fonts with glyphs of different widths.
+//! - Characters outside ASCII.
+
+use crate::color::Color;
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+
+/// The name of the font used when no other is asked for.
+pub const DEFAULT_FONT: &str = "default";
+/// The character drawn for characters the font doesn't have.
+const MISSING_GLYPH: char = '?';
+
+/// How lines of text line up in the width they are laid out in.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Align {
+    /// Lines start at the left edge.
+    Left,
+    /// Lines are in the middle.
+    Center,
+    /// Lines end at the right edge.
+    Right,
+}
+
+/// A piece of text in one colour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    text: String,
+    color: Color,
+}
+
+impl Span {
+    /// Creates a new instance of Span.
+    pub fn new(text: &str, color: Color) -> Span {
+        Span {
+            text: text.to_string(),
+            color,
+        }
+    }
+    /// Returns the text of the span.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+    /// Returns the colour of the span.
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+/// Spans of text, how they line up and how big they are drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    spans: Vec<Span>,
+    align: Align,
+    scale: u32,
+}
+
+impl Text {
+    /// Creates a new instance of Text with a single span, left aligned and
+    /// drawn at the size of the font.
+    pub fn new(text: &str, color: Color) -> Text {
+        Text {
+            spans: vec![Span::new(text, color)],
+            align: Align::Left,
+            scale: 1,
+        }
+    }
+    /// Adds a span to the end of the text.
+    pub fn add_span(mut self, text: &str, color: Color) -> Self {
+        self.spans.push(Span::new(text, color));
+        self
+    }
+    /// Sets how lines line up.
+    pub fn set_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+    /// Draws each glyph scale times bigger, so small fonts stay sharp.
+    pub fn set_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+    /// Returns the spans, in order.
+    pub fn get_spans(&self) -> &Vec<Span> {
+        &self.spans
+    }
+    /// Returns how lines line up.
+    pub fn get_align(&self) -> Align {
+        self.align
+    }
+    /// Returns how many times bigger than the font it is drawn.
+    pub fn get_scale(&self) -> u32 {
+        self.scale
+    }
+}
+
+/// A glyph placed on the screen, with its frame in the font atlas.
+pub type PlacedGlyph = (u32, Rectangle, Color);
+
+/// The size of the glyphs in a font atlas and which characters it has.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Font {
+    glyph_size: Dimension,
+    first: u32,
+    glyph_count: u32,
+}
+
+impl Font {
+    /// Creates a new instance of Font. first is the character code of frame
+    /// 0 and glyph_count how many frames the atlas has.
+    pub fn new(glyph_size: Dimension, first: u32, glyph_count: u32) -> Font {
+        Font {
+            glyph_size,
+            first,
+            glyph_count,
+        }
+    }
+    /// Returns the size of a glyph in pixels, before scaling.
+    pub fn get_glyph_size(&self) -> Dimension {
+        self.glyph_size
+    }
+    /// Returns the frame of a character, if the font has it.
+    pub fn get_glyph(&self, character: char) -> Option<u32> {
+        let code = character as u32;
+        if code >= self.first && code - self.first < self.glyph_count {
+            Some(code - self.first)
+        } else {
+            None
+        }
+    }
+    /// Returns how wide and tall a line of text is, without wrapping.
+    pub fn measure_line(&self, line: &str, scale: u32) -> Dimension {
+        Dimension::new(
+            line.chars().count() as u32 * self.glyph_size.get_width() * scale,
+            self.glyph_size.get_height() * scale,
+        )
+    }
+    /// Returns how much room text takes up wrapped to fit width, or
+    /// without wrapping if there is no width. The width is of the longest
+    /// line, not of the box, and doesn't count spaces at either end.
+    pub fn measure(&self, text: &Text, width: Option<u32>) -> Dimension {
+        let lines = self.wrap(text, width);
+        let longest = lines.iter().map(|line| line.len()).max().unwrap_or(0) as u32;
+        Dimension::new(
+            longest * self.glyph_size.get_width() * text.scale,
+            lines.len() as u32 * self.glyph_size.get_height() * text.scale,
+        )
+    }
+    /// Places every glyph of text with its top left at position, wrapped
+    /// and aligned to width. Without a width lines only break at newlines
+    /// and are aligned to the longest line. Spaces aren't placed.
+    pub fn layout(&self, text: &Text, position: Point, width: Option<u32>) -> Vec<PlacedGlyph> {
+        let (advance, line_height) = (
+            self.glyph_size.get_width() * text.scale,
+            self.glyph_size.get_height() * text.scale,
+        );
+        let lines = self.wrap(text, width);
+        let box_width = width.unwrap_or_else(|| {
+            lines.iter().map(|line| line.len()).max().unwrap_or(0) as u32 * advance
+        });
+        let mut glyphs = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            let line_width = line.len() as u32 * advance;
+            let offset = match text.align {
+                Align::Left => 0,
+                Align::Center => box_width.saturating_sub(line_width) / 2,
+                Align::Right => box_width.saturating_sub(line_width),
+            };
+            for (column, (character, color)) in line.iter().enumerate() {
+                if character.is_whitespace() {
+                    continue;
+                }
+                let frame = self
+                    .get_glyph(*character)
+                    .or_else(|| self.get_glyph(MISSING_GLYPH))
+                    .unwrap_or(0);
+                glyphs.push((
+                    frame,
+                    Rectangle::new(
+                        position.get_x() + (offset + column as u32 * advance) as i32,
+                        position.get_y() + (row as u32 * line_height) as i32,
+                        advance,
+                        line_height,
+                    ),
+                    *color,
+                ));
+            }
+        }
+        glyphs
+    }
+    /// Splits text into lines of coloured characters. Lines break at
+    /// newlines and, with a width, before the word that would go past it.
+    /// Words longer than a whole line are broken anywhere. Spaces before
+    /// the first word or after the last word of a line are dropped.
+    fn wrap(&self, text: &Text, width: Option<u32>) -> Vec<Vec<(char, Color)>> {
+        let max_columns = width.map(|width| {
+            (width / (self.glyph_size.get_width() * text.scale).max(1)).max(1) as usize
+        });
+        let mut lines = vec![Vec::new()];
+        let mut word: Vec<(char, Color)> = Vec::new();
+        let mut spaces: Vec<(char, Color)> = Vec::new();
+        let characters = text
+            .spans
+            .iter()
+            .flat_map(|span| {
+                span.text
+                    .chars()
+                    .map(move |character| (character, span.color))
+            })
+            .chain(std::iter::once(('\n', Color::RGB(0, 0, 0))));
+        for (character, color) in characters {
+            if character != '\n' && !character.is_whitespace() {
+                word.push((character, color));
+                continue;
+            }
+            place_word(&mut lines, &mut spaces, &mut word, max_columns);
+            if character == '\n' {
+                lines.push(Vec::new());
+                spaces.clear();
+            } else {
+                spaces.push((' ', color));
+            }
+        }
+        // the newline added to finish the last word
+        lines.pop();
+        lines
+    }
+}
+
+/// Puts word on the last line after the spaces before it, starting a new
+/// line first if it doesn't fit.
+fn place_word(
+    lines: &mut Vec<Vec<(char, Color)>>,
+    spaces: &mut Vec<(char, Color)>,
+    word: &mut Vec<(char, Color)>,
+    max_columns: Option<usize>,
+) {
+    if word.is_empty() {
+        return;
+    }
+    let line = lines.last_mut().unwrap();
+    let fits = match max_columns {
+        Some(max) => line.len() + spaces.len() + word.len() <= max,
+        None => true,
+    };
+    if fits || line.is_empty() {
+        if !line.is_empty() {
+            line.append(spaces);
+        }
+    } else {
+        lines.push(Vec::new());
+    }
+    spaces.clear();
+    for character in word.drain(..) {
+        let line = lines.last_mut().unwrap();
+        if matches!(max_columns, Some(max) if line.len() >= max) {
+            lines.push(Vec::new());
+        }
+        lines.last_mut().unwrap().push(character);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Color = Color::RGB(255, 255, 255);
+    const RED: Color = Color::RGB(255, 0, 0);
+
+    /// A 6x8 font with every printable ASCII character.
+    fn font() -> Font {
+        Font::new(Dimension::new(6, 8), 32, 95)
+    }
+
+    /// Returns the lines wrapped to columns glyphs, as strings.
+    fn wrap_to(text: &str, columns: Option<u32>) -> Vec<String> {
+        font()
+            .wrap(&Text::new(text, WHITE), columns.map(|columns| columns * 6))
+            .iter()
+            .map(|line| line.iter().map(|(character, _)| character).collect())
+            .collect()
+    }
+
+    #[test]
+    fn lines_wrap_at_spaces() {
+        assert_eq!(
+            wrap_to("the quick brown fox", Some(10)),
+            vec!["the quick", "brown fox"]
+        );
+        assert_eq!(wrap_to("one\ntwo three", None), vec!["one", "two three"]);
+        assert_eq!(wrap_to("one\n\ntwo", Some(10)), vec!["one", "", "two"]);
+        let text = Text::new("the quick brown fox", WHITE);
+        assert_eq!(font().measure(&text, Some(60)), Dimension::new(54, 16));
+        assert_eq!(font().measure(&text, None), Dimension::new(114, 8));
+        assert_eq!(
+            font().measure(&text.set_scale(2), Some(120)),
+            Dimension::new(108, 32)
+        );
+    }
+
+    #[test]
+    fn long_words_are_split() {
+        assert_eq!(wrap_to("abcdefghij", Some(4)), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap_to("ab abcdefgh", Some(4)), vec!["ab", "abcd", "efgh"]);
+        // a line is never less than one glyph wide
+        assert_eq!(wrap_to("abc", Some(0)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn spaces_at_the_ends_are_dropped() {
+        assert_eq!(wrap_to("  lead", None), vec!["lead"]);
+        assert_eq!(wrap_to("trail  ", None), vec!["trail"]);
+        assert_eq!(wrap_to("one  two", Some(3)), vec!["one", "two"]);
+        assert_eq!(
+            font().measure(&Text::new("  lead", WHITE), None),
+            Dimension::new(24, 8)
+        );
+    }
+
+    #[test]
+    fn lines_are_aligned_in_the_width() {
+        let first_x = |align: Align, width: Option<u32>| {
+            let text = Text::new("abcd\nab", WHITE).set_align(align);
+            let glyphs = font().layout(&text, Point::new(10, 20), width);
+            // the first glyph of the second line
+            glyphs[4].1.get_point().get_x()
+        };
+        assert_eq!(first_x(Align::Left, Some(60)), 10);
+        assert_eq!(first_x(Align::Center, Some(60)), 10 + 24);
+        assert_eq!(first_x(Align::Right, Some(60)), 10 + 48);
+        // without a width lines align to the longest
+        assert_eq!(first_x(Align::Center, None), 10 + 6);
+        assert_eq!(first_x(Align::Right, None), 10 + 12);
+    }
+
+    #[test]
+    fn spans_keep_their_colours() {
+        let text = Text::new("ab", WHITE).add_span(" c?", RED);
+        let glyphs = font().layout(&text, Point::new(0, 0), None);
+        // the space isn't placed but still takes room
+        assert_eq!(
+            glyphs,
+            vec![
+                (65, Rectangle::new(0, 0, 6, 8), WHITE),
+                (66, Rectangle::new(6, 0, 6, 8), WHITE),
+                (67, Rectangle::new(18, 0, 6, 8), RED),
+                (31, Rectangle::new(24, 0, 6, 8), RED),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_characters_use_the_missing_glyph() {
+        let glyphs = font().layout(&Text::new("é", WHITE), Point::new(0, 0), None);
+        assert_eq!(glyphs[0].0, font().get_glyph(MISSING_GLYPH).unwrap());
+        assert_eq!(font().get_glyph('~'), Some(94));
+        assert_eq!(font().get_glyph('\u{7f}'), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Slides entities between tiles when they move.
+//!
+//! Moving still puts an entity straight on its new tile, so turns work the
+//! same as ever. The entity just remembers where it came from, and the
+//! renderer draws it part of the way there until the tween is done. Like
+//! clips, a tween is stamped with the render clock at the end of the turn.
+//!
+//! Tweening in Settings says how long a slide takes and what happens to
+//! movement pressed during one. It can wait for the slide to end, or skip
+//! the rest of every slide and go straight away.
+//!
+//! # Planned changes:
+//!
+//! - Easing instead of sliding at one speed.
+
+use std::time::Duration;
+
+use crate::math::Rectangle;
+
+/// How long, in milliseconds, a move takes to slide by default.
+pub const DEFAULT_TWEEN_DURATION: u32 = 80;
+
+/// What happens to movement pressed while the player is still sliding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TweenInput {
+    /// The input stays in the input buffer until the slide ends.
+    Queue,
+    /// Every slide jumps to its end and the input happens straight away.
+    FastForward,
+}
+
+impl TweenInput {
+    /// Returns the name used in the settings file.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TweenInput::Queue => "queue",
+            TweenInput::FastForward => "fast_forward",
+        }
+    }
+    /// Returns the TweenInput with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<TweenInput> {
+        match name {
+            "queue" => Some(TweenInput::Queue),
+            "fast_forward" => Some(TweenInput::FastForward),
+            _ => None,
+        }
+    }
+}
+
+/// How moves slide, kept in Settings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Tweening {
+    duration: u32,
+    input: TweenInput,
+}
+
+impl Tweening {
+    /// Creates a new instance of Tweening. duration is in milliseconds, 0
+    /// turns sliding off.
+    pub fn new(duration: u32, input: TweenInput) -> Tweening {
+        Tweening { duration, input }
+    }
+    /// Returns how long a move takes to slide.
+    pub fn get_duration(&self) -> Duration {
+        Duration::from_millis(self.duration as u64)
+    }
+    /// Returns what happens to movement pressed during a slide.
+    pub fn get_input(&self) -> TweenInput {
+        self.input
+    }
+}
+
+impl Default for Tweening {
+    fn default() -> Tweening {
+        Tweening::new(DEFAULT_TWEEN_DURATION, TweenInput::Queue)
+    }
+}
+
+/// A slide from where an entity was to where it is now.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tween {
+    from: Rectangle,
+    started: Option<Duration>,
+    duration: Duration,
+}
+
+impl Tween {
+    /// Creates a new instance of Tween from a rectangle. It doesn't move
+    /// until it is started.
+    pub fn new(from: Rectangle) -> Tween {
+        Tween {
+            from,
+            started: None,
+            duration: Duration::from_millis(0),
+        }
+    }
+    /// Returns where the slide started.
+    pub fn get_from(&self) -> Rectangle {
+        self.from
+    }
+    /// Returns true if the tween hasn't been stamped yet.
+    pub fn is_pending(&self) -> bool {
+        self.started.is_none()
+    }
+    /// Sets when the tween started and how long it takes, if it is waiting
+    /// for it.
+    pub fn start(&mut self, clock: Duration, duration: Duration) {
+        if self.is_pending() {
+            self.started = Some(clock);
+            self.duration = duration;
+        }
+    }
+    /// Returns how far along the slide is at clock, from 0 to 1.
+    pub fn get_progress(&self, clock: Duration) -> f32 {
+        match self.started {
+            None => 0.0,
+            Some(_) if self.duration.as_nanos() == 0 => 1.0,
+            Some(started) => {
+                (clock.saturating_sub(started).as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+            }
+        }
+    }
+    /// Returns true once the slide has reached its end.
+    pub fn is_done(&self, clock: Duration) -> bool {
+        self.get_progress(clock) >= 1.0
+    }
+    /// Returns the top left of the entity on its way to `to`, in tiles.
+    pub fn get_position(&self, to: Rectangle, clock: Duration) -> (f32, f32) {
+        let progress = self.get_progress(clock);
+        (
+            self.from.x() as f32 + (to.x() - self.from.x()) as f32 * progress,
+            self.from.y() as f32 + (to.y() - self.from.y()) as f32 * progress,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tween from the origin, started at 1 second.
+    fn started(duration: u64) -> Tween {
+        let mut tween = Tween::new(Rectangle::new(0, 0, 1, 1));
+        tween.start(Duration::from_secs(1), Duration::from_millis(duration));
+        tween
+    }
+
+    #[test]
+    fn pending_tweens_stay_at_the_start() {
+        let tween = Tween::new(Rectangle::new(2, 3, 1, 1));
+        assert!(tween.is_pending());
+        assert_eq!(tween.get_progress(Duration::from_secs(5)), 0.0);
+        assert_eq!(
+            tween.get_position(Rectangle::new(3, 3, 1, 1), Duration::from_secs(5)),
+            (2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn no_duration_is_done_straight_away() {
+        let tween = started(0);
+        assert_eq!(tween.get_progress(Duration::from_secs(1)), 1.0);
+        assert!(tween.is_done(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn progress_goes_to_one_and_stops() {
+        let tween = started(100);
+        let to = Rectangle::new(-2, 4, 1, 1);
+        // before it started counts as the start
+        assert_eq!(tween.get_progress(Duration::from_millis(500)), 0.0);
+        assert_eq!(tween.get_progress(Duration::from_millis(1050)), 0.5);
+        assert_eq!(
+            tween.get_position(to, Duration::from_millis(1050)),
+            (-1.0, 2.0)
+        );
+        assert!(!tween.is_done(Duration::from_millis(1050)));
+        assert_eq!(tween.get_progress(Duration::from_secs(3)), 1.0);
+        assert_eq!(tween.get_position(to, Duration::from_secs(3)), (-2.0, 4.0));
+    }
+
+    #[test]
+    fn tweens_only_start_once() {
+        let mut tween = started(100);
+        tween.start(Duration::from_secs(2), Duration::from_millis(100));
+        assert!(tween.is_done(Duration::from_millis(1100)));
+    }
+
+    #[test]
+    fn input_names_round_trip() {
+        for input in [TweenInput::Queue, TweenInput::FastForward] {
+            assert_eq!(TweenInput::from_name(input.get_name()), Some(input));
+        }
+        assert_eq!(TweenInput::from_name("skip"), None);
+    }
+}
@@ -0,0 +1,613 @@
+//! Widgets for building screens, like the inventory.
+//!
+//! A Ui is a list of named widgets, each placed in an Area, which is a
+//! part of the window given as fractions of its size. Widgets are placed
+//! again every frame from the size of the window, so screens keep their
+//! shape as it resizes.
+//!
+//! Lists, buttons and text inputs can take focus. The movement actions
+//! move focus between them in the order they were added, apart from up and
+//! down in a list, which move its selection. Pointing at a widget with the
+//! mouse focuses it and clicking uses it. What the player did comes back
+//! as a UiEvent with the widget's name, for the screen to act on.
+//!
+//! render::render_ui draws a Ui.
+//!
+//! # Planned changes:
+//!
+//! - Widgets inside other widgets, so a panel moves with its contents.
+//! - A cursor that moves inside text inputs.
+
+use crate::color::Color;
+use crate::events::PlayerAction;
+use crate::input::{Input, Key, MouseButton, PointerEvent};
+use crate::math::{Dimension, Point, Rectangle, TwoDimensional};
+use crate::text::Text;
+
+/// The colour of panels behind widgets.
+pub const PANEL_COLOR: Color = Color {
+    r: 30,
+    g: 30,
+    b: 42,
+    a: 235,
+};
+/// The colour of text on widgets.
+pub const TEXT_COLOR: Color = Color {
+    r: 235,
+    g: 235,
+    b: 235,
+    a: 255,
+};
+/// The colour of health bars.
+pub const HEALTH_COLOR: Color = Color {
+    r: 200,
+    g: 50,
+    b: 50,
+    a: 255,
+};
+
+/// A part of the window, as fractions of its width and height.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Area {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Area {
+    /// Creates a new instance of Area. Every value is a fraction of the
+    /// window, so 0.5 is halfway.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Area {
+        Area {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+    /// The whole window.
+    pub fn full() -> Area {
+        Area::new(0.0, 0.0, 1.0, 1.0)
+    }
+    /// Returns the area in pixels on a window of screen_size.
+    pub fn place(&self, screen_size: Dimension) -> Rectangle {
+        let (screen_width, screen_height) = (
+            screen_size.get_width() as f32,
+            screen_size.get_height() as f32,
+        );
+        Rectangle::new(
+            (self.x * screen_width).round() as i32,
+            (self.y * screen_height).round() as i32,
+            ((self.width * screen_width).round() as u32).max(1),
+            ((self.height * screen_height).round() as u32).max(1),
+        )
+    }
+}
+
+/// A list of choices where one is selected. Only rows items fit in the
+/// list at once, and it scrolls to keep the selection shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct List {
+    items: Vec<String>,
+    selected: usize,
+    scroll: usize,
+    rows: usize,
+}
+
+impl List {
+    /// Creates a new instance of List showing rows items at a time, with
+    /// the first selected.
+    pub fn new(items: Vec<String>, rows: usize) -> List {
+        List {
+            items,
+            selected: 0,
+            scroll: 0,
+            rows: rows.max(1),
+        }
+    }
+    /// Returns the items, in order.
+    pub fn get_items(&self) -> &Vec<String> {
+        &self.items
+    }
+    /// Returns the index of the selected item.
+    pub fn get_selected(&self) -> usize {
+        self.selected
+    }
+    /// Returns the index of the first item shown.
+    pub fn get_scroll(&self) -> usize {
+        self.scroll
+    }
+    /// Returns how many items are shown at once.
+    pub fn get_rows(&self) -> usize {
+        self.rows
+    }
+    /// Selects the item at index, scrolling to it. Indexes past the end
+    /// select the last item.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.rows {
+            self.scroll = self.selected + 1 - self.rows;
+        }
+    }
+    /// Scrolls by count rows, down when positive, without moving the
+    /// selection.
+    pub fn scroll_by(&mut self, count: i32) {
+        let most = self.items.len().saturating_sub(self.rows) as i32;
+        self.scroll = (self.scroll as i32 + count).clamp(0, most) as usize;
+    }
+    /// Returns the scrollbar for the list.
+    pub fn get_scrollbar(&self) -> Scrollbar {
+        Scrollbar::new(self.scroll, self.rows, self.items.len())
+    }
+}
+
+/// Shows how far through something longer than its space the view is.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Scrollbar {
+    offset: usize,
+    visible: usize,
+    total: usize,
+}
+
+impl Scrollbar {
+    /// Creates a new instance of Scrollbar where visible of total things
+    /// are shown, starting at offset.
+    pub fn new(offset: usize, visible: usize, total: usize) -> Scrollbar {
+        Scrollbar {
+            offset,
+            visible,
+            total,
+        }
+    }
+    /// Returns the first thing shown.
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+    /// Returns true if everything fits, so there is nothing to scroll.
+    pub fn is_full(&self) -> bool {
+        self.visible >= self.total
+    }
+    /// Returns the handle inside a track, a rectangle the length of the
+    /// bar. The track's longer side is the one scrolled along.
+    pub fn get_handle(&self, track: Rectangle) -> Rectangle {
+        if self.is_full() {
+            return track;
+        }
+        let vertical = track.height() >= track.width();
+        let length = if vertical {
+            track.height()
+        } else {
+            track.width()
+        };
+        let size = (length as usize * self.visible / self.total).max(1) as u32;
+        let start = (length as usize * self.offset / self.total) as i32;
+        if vertical {
+            Rectangle::new(track.x(), track.y() + start, track.width(), size)
+        } else {
+            Rectangle::new(track.x() + start, track.y(), size, track.height())
+        }
+    }
+    /// Moves the offset so the handle is centred on point in the track.
+    pub fn point_to(&mut self, track: Rectangle, point: Point) {
+        if self.is_full() {
+            return;
+        }
+        let (along, length) = if track.height() >= track.width() {
+            (point.get_y() - track.y(), track.height())
+        } else {
+            (point.get_x() - track.x(), track.width())
+        };
+        let along = along.clamp(0, length as i32) as usize;
+        let middle = along * self.total / length.max(1) as usize;
+        self.offset = middle
+            .saturating_sub(self.visible / 2)
+            .min(self.total - self.visible);
+    }
+}
+
+/// How full something is, like health.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProgressBar {
+    value: u32,
+    most: u32,
+    color: Color,
+}
+
+impl ProgressBar {
+    /// Creates a new instance of ProgressBar, filled value out of most in
+    /// color.
+    pub fn new(value: u32, most: u32, color: Color) -> ProgressBar {
+        ProgressBar {
+            value: value.min(most),
+            most,
+            color,
+        }
+    }
+    /// Returns how full it is, from 0 to 1.
+    pub fn get_fraction(&self) -> f32 {
+        if self.most == 0 {
+            return 0.0;
+        }
+        self.value as f32 / self.most as f32
+    }
+    /// Returns the value and the most it can be.
+    pub fn get_value(&self) -> (u32, u32) {
+        (self.value, self.most)
+    }
+    /// Returns the colour of the filled part.
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+/// A line of text the player types into.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextInput {
+    text: String,
+    placeholder: String,
+    max_length: usize,
+}
+
+impl TextInput {
+    /// Creates a new instance of TextInput that is empty, showing
+    /// placeholder, and takes up to max_length characters.
+    pub fn new(placeholder: &str, max_length: usize) -> TextInput {
+        TextInput {
+            text: String::new(),
+            placeholder: placeholder.to_string(),
+            max_length,
+        }
+    }
+    /// Returns what has been typed.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+    /// Returns what is shown while it is empty.
+    pub fn get_placeholder(&self) -> &str {
+        &self.placeholder
+    }
+    /// Adds typed text to the end, leaving out anything past the longest
+    /// it can be and characters that aren't printable.
+    pub fn type_text(&mut self, text: &str) {
+        let room = self.max_length.saturating_sub(self.text.chars().count());
+        self.text.extend(
+            text.chars()
+                .filter(|character| !character.is_control())
+                .take(room),
+        );
+    }
+    /// Removes the last character.
+    pub fn backspace(&mut self) {
+        self.text.pop();
+    }
+    /// Empties it.
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+}
+
+/// The kinds of widget.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Widget {
+    /// A filled rectangle, usually behind other widgets.
+    Panel(Color),
+    /// Text that can't be used.
+    Label(Text),
+    /// A list of choices.
+    List(List),
+    /// A button with a label.
+    Button(String),
+    /// A scrollbar that isn't part of a list.
+    Scrollbar(Scrollbar),
+    /// A bar filled part of the way.
+    ProgressBar(ProgressBar),
+    /// A line of typed text.
+    TextInput(TextInput),
+}
+
+impl Widget {
+    /// Returns true if the widget can take focus.
+    pub fn is_focusable(&self) -> bool {
+        matches!(
+            self,
+            Widget::List(_) | Widget::Button(_) | Widget::TextInput(_)
+        )
+    }
+}
+
+/// What the player did with a Ui.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UiEvent {
+    /// The named button was pressed.
+    Pressed(String),
+    /// An item of the named list was chosen.
+    Chosen(String, usize),
+    /// The text of the named input was confirmed.
+    Submitted(String, String),
+    /// The named widget changed, like typing in an input or moving a
+    /// scrollbar.
+    Changed(String),
+    /// The player wants to leave.
+    Back,
+    /// The Ui had no use for an action, so the screen can have it.
+    Unused(PlayerAction),
+}
+
+/// A widget with its name and where it goes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    name: String,
+    widget: Widget,
+    area: Area,
+}
+
+impl Element {
+    /// Returns the name events use for the widget.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the widget.
+    pub fn get_widget(&self) -> &Widget {
+        &self.widget
+    }
+    /// Returns where the widget goes.
+    pub fn get_area(&self) -> Area {
+        self.area
+    }
+}
+
+/// Widgets drawn in order, with one of them focused.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ui {
+    elements: Vec<Element>,
+    focus: Option<usize>,
+}
+
+impl Ui {
+    /// Creates a new instance of Ui without any widgets.
+    pub fn new() -> Ui {
+        Ui::default()
+    }
+    /// Adds a widget called name in area, drawn over the ones before it.
+    /// The first widget that can take focus gets it.
+    pub fn add(mut self, name: &str, widget: Widget, area: Area) -> Self {
+        if self.focus.is_none() && widget.is_focusable() {
+            self.focus = Some(self.elements.len());
+        }
+        self.elements.push(Element {
+            name: name.to_string(),
+            widget,
+            area,
+        });
+        self
+    }
+    /// Returns the widgets, in the order they are drawn.
+    pub fn get_elements(&self) -> &Vec<Element> {
+        &self.elements
+    }
+    /// Returns the widget called name, if there is one.
+    pub fn get_widget(&self, name: &str) -> Option<&Widget> {
+        self.elements
+            .iter()
+            .find(|element| element.name == name)
+            .map(|element| &element.widget)
+    }
+    /// Returns the widget called name to change it, if there is one.
+    pub fn get_mut_widget(&mut self, name: &str) -> Option<&mut Widget> {
+        self.elements
+            .iter_mut()
+            .find(|element| element.name == name)
+            .map(|element| &mut element.widget)
+    }
+    /// Returns the index of the focused widget.
+    pub fn get_focus(&self) -> Option<usize> {
+        self.focus
+    }
+    /// Focuses the widget called name if it can take focus.
+    pub fn focus(&mut self, name: &str) {
+        if let Some(index) = self
+            .elements
+            .iter()
+            .position(|element| element.name == name && element.widget.is_focusable())
+        {
+            self.focus = Some(index);
+        }
+    }
+    /// Moves focus to the next widget that can take it, wrapping around.
+    pub fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+    /// Moves focus to the previous widget that can take it, wrapping
+    /// around.
+    pub fn focus_previous(&mut self) {
+        self.move_focus(self.elements.len().saturating_sub(1));
+    }
+    /// Returns true while a text input is focused, so keys type instead of
+    /// acting.
+    pub fn is_typing(&self) -> bool {
+        matches!(self.get_focused(), Some(Widget::TextInput(_)))
+    }
+    /// Returns where every widget goes on a window of screen_size, in
+    /// order.
+    pub fn place(&self, screen_size: Dimension) -> Vec<Rectangle> {
+        self.elements
+            .iter()
+            .map(|element| element.area.place(screen_size))
+            .collect()
+    }
+    /// Handles a pressed input and the action it is bound to. While typing
+    /// keys don't act, apart from return, backspace and escape.
+    pub fn handle_input(&mut self, input: Input, action: Option<PlayerAction>) -> Option<UiEvent> {
+        if self.is_typing() {
+            if let Input::Key(key) = input {
+                return match key {
+                    Key::Return | Key::KpEnter => self.use_focused(),
+                    Key::Backspace => self.edit_focused(TextInput::backspace),
+                    Key::Escape => Some(UiEvent::Back),
+                    _ => None,
+                };
+            }
+        }
+        action.and_then(|action| self.handle_action(action))
+    }
+    /// Handles an action. Movement moves focus, or the selection in a
+    /// focused list, and confirm uses the focused widget.
+    pub fn handle_action(&mut self, action: PlayerAction) -> Option<UiEvent> {
+        match (action, self.get_focused_mut()) {
+            (PlayerAction::MoveUp, Some(Widget::List(list))) if list.selected > 0 => {
+                list.select(list.selected - 1);
+            }
+            (PlayerAction::MoveDown, Some(Widget::List(list)))
+                if list.selected + 1 < list.items.len() =>
+            {
+                list.select(list.selected + 1);
+            }
+            (PlayerAction::MoveUp | PlayerAction::MoveLeft, _) => self.focus_previous(),
+            (PlayerAction::MoveDown | PlayerAction::MoveRight, _) => self.focus_next(),
+            (PlayerAction::Confirm, _) => return self.use_focused(),
+            (PlayerAction::Back, _) => return Some(UiEvent::Back),
+            (action, _) => return Some(UiEvent::Unused(action)),
+        }
+        None
+    }
+    /// Handles the mouse on a window of screen_size. Pointing focuses,
+    /// clicking with the left button uses what is under the mouse and the
+    /// wheel scrolls the focused list.
+    pub fn handle_pointer(
+        &mut self,
+        pointer: PointerEvent,
+        screen_size: Dimension,
+    ) -> Option<UiEvent> {
+        match pointer {
+            PointerEvent::Moved(point) => {
+                if let Some(index) = self.get_focusable_at(point, screen_size) {
+                    self.focus = Some(index);
+                }
+                None
+            }
+            PointerEvent::Clicked(MouseButton::Left, point) => self.click(point, screen_size),
+            PointerEvent::Clicked(..) => None,
+            PointerEvent::Scrolled(notches) => {
+                if let Some(Widget::List(list)) = self.get_focused_mut() {
+                    list.scroll_by(-notches);
+                }
+                None
+            }
+        }
+    }
+    /// Types text into the focused input, if one is focused.
+    pub fn handle_text(&mut self, text: &str) -> Option<UiEvent> {
+        if text.is_empty() {
+            return None;
+        }
+        self.edit_focused(|input| input.type_text(text))
+    }
+    fn get_focused(&self) -> Option<&Widget> {
+        self.focus
+            .and_then(|index| self.elements.get(index))
+            .map(|element| &element.widget)
+    }
+    fn get_focused_mut(&mut self) -> Option<&mut Widget> {
+        let index = self.focus?;
+        self.elements
+            .get_mut(index)
+            .map(|element| &mut element.widget)
+    }
+    fn get_focused_name(&self) -> String {
+        self.focus
+            .and_then(|index| self.elements.get(index))
+            .map(|element| element.name.clone())
+            .unwrap_or_default()
+    }
+    /// Moves focus forward by step, modulo the number of widgets, until a
+    /// widget that can take it is found.
+    fn move_focus(&mut self, step: usize) {
+        let count = self.elements.len();
+        let start = match self.focus {
+            Some(index) => index,
+            None if step == 1 => count.saturating_sub(1),
+            None => 0,
+        };
+        let mut index = start;
+        for _ in 0..count {
+            index = (index + step) % count;
+            if self.elements[index].widget.is_focusable() {
+                self.focus = Some(index);
+                return;
+            }
+        }
+    }
+    /// Presses, chooses or submits the focused widget.
+    fn use_focused(&mut self) -> Option<UiEvent> {
+        let name = self.get_focused_name();
+        match self.get_focused()? {
+            Widget::Button(_) => Some(UiEvent::Pressed(name)),
+            Widget::List(list) if !list.items.is_empty() => {
+                Some(UiEvent::Chosen(name, list.selected))
+            }
+            Widget::TextInput(input) => Some(UiEvent::Submitted(name, input.text.clone())),
+            _ => None,
+        }
+    }
+    /// Changes the focused text input with edit.
+    fn edit_focused<F: FnOnce(&mut TextInput)>(&mut self, edit: F) -> Option<UiEvent> {
+        let name = self.get_focused_name();
+        match self.get_focused_mut()? {
+            Widget::TextInput(input) => {
+                edit(input);
+                Some(UiEvent::Changed(name))
+            }
+            _ => None,
+        }
+    }
+    /// Returns the topmost widget under point that can take focus.
+    fn get_focusable_at(&self, point: Point, screen_size: Dimension) -> Option<usize> {
+        self.place(screen_size)
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(index, rect)| {
+                self.elements[*index].widget.is_focusable() && rect.contains_point(point)
+            })
+            .map(|(index, _)| index)
+    }
+    /// Uses whatever is clicked at point. Lists select the row clicked.
+    fn click(&mut self, point: Point, screen_size: Dimension) -> Option<UiEvent> {
+        let places = self.place(screen_size);
+        let (index, rect) = places
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, rect)| rect.contains_point(point))?;
+        let name = self.elements[index].name.clone();
+        match &mut self.elements[index].widget {
+            Widget::Button(_) => {
+                self.focus = Some(index);
+                Some(UiEvent::Pressed(name))
+            }
+            Widget::List(list) => {
+                self.focus = Some(index);
+                let row_height = (rect.height() as usize / list.rows).max(1);
+                let row = (point.get_y() - rect.y()) as usize / row_height;
+                let item = list.scroll + row;
+                if item >= list.items.len() {
+                    return None;
+                }
+                list.select(item);
+                Some(UiEvent::Chosen(name, item))
+            }
+            Widget::TextInput(_) => {
+                self.focus = Some(index);
+                None
+            }
+            Widget::Scrollbar(scrollbar) => {
+                scrollbar.point_to(*rect, point);
+                Some(UiEvent::Changed(name))
+            }
+            _ => None,
+        }
+    }
+}